@@ -0,0 +1,46 @@
+//! Rough throughput of decimating by two through a long FIR filter, first
+//! filtering every sample and then downsampling, then with the fused
+//! polyphase decimator:
+//!
+//! ```text
+//! cargo run --release --example decimator_throughput
+//! ```
+
+use std::{hint::black_box, time::Instant};
+
+use qmf::{DownSampler, DynFirFilter, PolyphaseDecimator};
+
+fn main() {
+    let taps: Vec<f64> = (0..64).map(|k| 1. / (k as f64 + 1.)).collect();
+    let input: Vec<f64> = (0..1 << 16).map(|n| (n as f64 * 0.01).sin()).collect();
+    let rounds = 50;
+
+    let mut filter = DynFirFilter::<f64>::from_taps(&taps);
+    let mut downsampler = DownSampler::new(2).unwrap();
+    let start = Instant::now();
+    for _ in 0..rounds {
+        let filtered = black_box(&input).iter().map(|&x| filter.consume(x));
+        let output: Vec<f64> = downsampler.iter(filtered).collect();
+        black_box(&output);
+    }
+    report(
+        "filter then downsample",
+        start.elapsed(),
+        rounds * input.len(),
+    );
+
+    let mut decimator = PolyphaseDecimator::new(&taps, 2).unwrap();
+    let start = Instant::now();
+    for _ in 0..rounds {
+        let output = decimator.process(black_box(&input));
+        black_box(&output);
+    }
+    report("polyphase", start.elapsed(), rounds * input.len());
+}
+
+fn report(name: &str, elapsed: std::time::Duration, samples: usize) {
+    println!(
+        "{name}: {:.1} ns per input sample",
+        elapsed.as_nanos() as f64 / samples as f64
+    );
+}
@@ -0,0 +1,35 @@
+//! Rough throughput of a long FIR filter, to compare builds with and
+//! without the `fma` feature:
+//!
+//! ```text
+//! cargo run --release --example fir_throughput
+//! cargo run --release --example fir_throughput --features fma
+//! ```
+//!
+//! Build with `RUSTFLAGS="-C target-cpu=native"` or similar for the fused
+//! version to use hardware FMA instructions.
+
+use std::{hint::black_box, time::Instant};
+
+use qmf::DynFirFilter;
+
+fn main() {
+    let taps: Vec<f64> = (0..64).map(|k| 1. / (k as f64 + 1.)).collect();
+    let mut filter = DynFirFilter::<f64>::from_taps(&taps);
+    let input: Vec<f64> = (0..1 << 16).map(|n| (n as f64 * 0.01).sin()).collect();
+    let mut output = vec![0.; input.len()];
+
+    let rounds = 50;
+    let start = Instant::now();
+    for _ in 0..rounds {
+        filter.consume_slice(black_box(&input), &mut output);
+        black_box(&output);
+    }
+    let elapsed = start.elapsed();
+    let samples = (rounds * input.len()) as f64;
+    println!(
+        "fma {}: {:.1} ns per sample",
+        cfg!(feature = "fma"),
+        elapsed.as_nanos() as f64 / samples
+    );
+}
@@ -0,0 +1,45 @@
+//! Rough throughput of one Haar analysis/synthesis level on blocks of eight
+//! samples, first with the allocating `Band`, then with the stack-only
+//! `FixedBand`:
+//!
+//! ```text
+//! cargo run --release --example fixed_band_throughput
+//! ```
+
+use std::{hint::black_box, time::Instant};
+
+use qmf::{Band, FixedBand};
+
+fn main() {
+    let input: Vec<f64> = (0..1 << 16).map(|n| (n as f64 * 0.01).sin()).collect();
+    let rounds = 50;
+
+    let mut band = Band::<f64>::new();
+    let mut out = [0.; 8];
+    let start = Instant::now();
+    for _ in 0..rounds {
+        for block in black_box(&input).chunks_exact(8) {
+            let (low, high) = band.analysis(block);
+            band.synthesis(&low, &high, &mut out);
+            black_box(&out);
+        }
+    }
+    report("Band", start.elapsed(), rounds * input.len());
+
+    let mut band = FixedBand::<f64, 8>::new();
+    let start = Instant::now();
+    for _ in 0..rounds {
+        for block in black_box(&input).chunks_exact(8) {
+            let (low, high) = band.analysis::<4>(block.try_into().unwrap());
+            black_box(band.synthesis(&low, &high));
+        }
+    }
+    report("FixedBand", start.elapsed(), rounds * input.len());
+}
+
+fn report(name: &str, elapsed: std::time::Duration, samples: usize) {
+    println!(
+        "{name}: {:.1} ns per input sample",
+        elapsed.as_nanos() as f64 / samples as f64
+    );
+}
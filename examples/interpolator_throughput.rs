@@ -0,0 +1,46 @@
+//! Rough throughput of interpolating by two through an 8-tap FIR filter,
+//! first upsampling and filtering every sample, then with the polyphase
+//! interpolator that skips the stuffed zeros:
+//!
+//! ```text
+//! cargo run --release --example interpolator_throughput
+//! ```
+
+use std::{hint::black_box, time::Instant};
+
+use qmf::{DynFirFilter, PolyphaseInterpolator, UpSampler};
+
+fn main() {
+    let taps: Vec<f64> = (0..8).map(|k| 1. / (k as f64 + 1.)).collect();
+    let input: Vec<f64> = (0..1 << 16).map(|n| (n as f64 * 0.01).sin()).collect();
+    let rounds = 50;
+
+    let mut filter = DynFirFilter::<f64>::from_taps(&taps);
+    let mut upsampler = UpSampler::with_zero(2).unwrap();
+    let start = Instant::now();
+    for _ in 0..rounds {
+        let upsampled = upsampler.iter(black_box(&input).iter().copied());
+        let output: Vec<f64> = upsampled.map(|x| filter.consume(x)).collect();
+        black_box(&output);
+    }
+    report(
+        "upsample then filter",
+        start.elapsed(),
+        rounds * input.len(),
+    );
+
+    let mut interpolator = PolyphaseInterpolator::new(&taps, 2).unwrap();
+    let start = Instant::now();
+    for _ in 0..rounds {
+        let output = interpolator.process(black_box(&input));
+        black_box(&output);
+    }
+    report("polyphase", start.elapsed(), rounds * input.len());
+}
+
+fn report(name: &str, elapsed: std::time::Duration, samples: usize) {
+    println!(
+        "{name}: {:.1} ns per input sample",
+        elapsed.as_nanos() as f64 / samples as f64
+    );
+}
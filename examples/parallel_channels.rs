@@ -0,0 +1,47 @@
+//! Rough timing of `MultiBands::process_par` against a serial loop over the
+//! same channels:
+//!
+//! ```text
+//! cargo run --release --example parallel_channels --features parallel
+//! ```
+//!
+//! The speedup depends on the number of cores available.
+
+use std::{hint::black_box, time::Instant};
+
+use qmf::MultiBands;
+
+const CHANNELS: usize = 8;
+
+fn main() {
+    let mut bands = MultiBands::<f64, 5, CHANNELS>::new();
+    let mut buffers: Vec<Vec<f64>> = (0..CHANNELS)
+        .map(|c| {
+            (0..1 << 16)
+                .map(|n| (n as f64 * 0.01 * (c + 1) as f64).sin())
+                .collect()
+        })
+        .collect();
+    let rounds = 10;
+
+    let start = Instant::now();
+    for _ in 0..rounds {
+        let mut views: Vec<&mut [f64]> = buffers.iter_mut().map(Vec::as_mut_slice).collect();
+        bands.process_par(&mut views, |_band, _level, _channel| {});
+        black_box(&buffers);
+    }
+    let parallel = start.elapsed();
+
+    let mut interleaved = vec![0.; CHANNELS << 16];
+    let start = Instant::now();
+    for _ in 0..rounds {
+        bands.process(black_box(&mut interleaved), |_band, _level, _channel| {});
+    }
+    let serial = start.elapsed();
+
+    println!(
+        "{CHANNELS} channels: serial {:.1} ms, parallel {:.1} ms per round",
+        serial.as_secs_f64() * 1e3 / rounds as f64,
+        parallel.as_secs_f64() * 1e3 / rounds as f64,
+    );
+}
@@ -0,0 +1,26 @@
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use qmf::{Bands, DownSampler, HaarFilter, UpSampler};
+
+/// Runs a block through a three level bank, dropping the finest details.
+pub fn smooth(buffer: &mut [f32]) {
+    let mut bands: Bands<f32, 3> = Bands::new();
+    bands.process(buffer, |band, level| {
+        if level == 0 {
+            band.fill(0.);
+        }
+    });
+}
+
+/// Halves and restores the rate of `xs` through the Haar lowpass.
+pub fn resample_round_trip(xs: &[f32]) -> Vec<f32> {
+    let mut lowpass: HaarFilter<f32> = HaarFilter::new(0.5, 0.5);
+    let mut down = DownSampler::new(2).unwrap();
+    let mut up = UpSampler::with_zero(2).unwrap();
+    let low: Vec<f32> = down.iter(xs.iter().map(|&x| lowpass.consume(x))).collect();
+    up.iter(low.into_iter()).collect()
+}
@@ -0,0 +1,304 @@
+use alloc::{collections::VecDeque, vec::Vec};
+use num_traits::Float;
+
+use crate::{
+    bands::{analysis_vecs, Pairs, QmfBand},
+    fir::{DelayLine, FilterKernel},
+};
+
+// two first-order sections per branch, 70 dB of stopband attenuation with a
+// transition band of 0.1 (half-band design after Valenzuela and Constantinides)
+const PATH0: [f64; 2] = [0.07986642623635751, 0.5453236510711322];
+const PATH1: [f64; 2] = [0.28382934487410993, 0.8344118914807379];
+
+/// A power-symmetric IIR quadrature mirror filter bank built from two allpass
+/// branches, `H0(z) = (A0(z²) + z⁻¹A1(z²)) / 2` and
+/// `H1(z) = (A0(z²) - z⁻¹A1(z²)) / 2`, run in polyphase form.
+///
+/// Much cheaper than a long FIR band for the same stopband, but the round
+/// trip is `z⁻¹A0(z²)A1(z²)`: aliasing cancels and the magnitude is
+/// preserved, while the phase is distorted by the allpass. Its
+/// [`delay`](QmfBand::delay) is only the `z⁻¹`, so aligning the output by it
+/// leaves the signal late by the frequency dependent lag of the allpass.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllpassQmf<T>
+where
+    T: Float,
+{
+    path0: Vec<T>,
+    path1: Vec<T>,
+
+    in_path0: AllpassCascade<T>,
+    in_path1: AllpassCascade<T>,
+    out_path0: AllpassCascade<T>,
+    out_path1: AllpassCascade<T>,
+
+    pairs: Pairs<T>,
+}
+
+impl<T> AllpassQmf<T>
+where
+    T: Float,
+{
+    pub fn new() -> Self {
+        let coefficients =
+            |path: &[f64]| -> Vec<T> { path.iter().map(|&a| T::from(a).unwrap()).collect() };
+        Self::from_coefficients(&coefficients(&PATH0), &coefficients(&PATH1))
+    }
+
+    /// A band from the first-order allpass coefficients of each branch.
+    pub fn from_coefficients(path0: &[T], path1: &[T]) -> Self {
+        Self {
+            path0: path0.to_vec(),
+            path1: path1.to_vec(),
+            in_path0: AllpassCascade::new(path0, 1, 0),
+            in_path1: AllpassCascade::new(path1, 1, 0),
+            out_path0: AllpassCascade::new(path0, 1, 0),
+            out_path1: AllpassCascade::new(path1, 1, 0),
+            pairs: Pairs::new(),
+        }
+    }
+}
+
+impl<T> Default for AllpassQmf<T>
+where
+    T: Float,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> QmfBand<T> for AllpassQmf<T>
+where
+    T: Float,
+{
+    type Compensator = AllpassCascade<T>;
+
+    fn analysis(&mut self, xs: &[T]) -> (Vec<T>, Vec<T>) {
+        analysis_vecs(xs.len(), |low, high| self.analysis_into(xs, low, high))
+    }
+
+    fn analysis_into(&mut self, xs: &[T], low: &mut [T], high: &mut [T]) -> usize {
+        let half = T::from(0.5).unwrap();
+        let (in_path0, in_path1) = (&mut self.in_path0, &mut self.in_path1);
+        self.pairs.analysis_into(xs, low, high, |odd, even| {
+            let a0 = in_path0.consume(even);
+            let a1 = in_path1.consume(odd);
+            ((a0 + a1) * half, (a0 - a1) * half)
+        })
+    }
+
+    fn synthesis(&mut self, low: &[T], high: &[T], out: &mut [T]) {
+        let (out_path0, out_path1) = (&mut self.out_path0, &mut self.out_path1);
+        self.pairs.synthesis(low, high, out, |l, h| {
+            // l + h and l - h are the even and odd phases through A0 and A1;
+            // crossing over gives both phases the same A0·A1
+            (out_path0.consume(l - h), out_path1.consume(l + h))
+        })
+    }
+
+    fn reset(&mut self) {
+        self.in_path0.reset();
+        self.in_path1.reset();
+        self.out_path0.reset();
+        self.out_path1.reset();
+        self.pairs = Pairs::new();
+    }
+
+    /// The `z⁻¹` of the round trip only, without the dispersion of the
+    /// allpass branches that [`group_delay_at`](QmfBand::group_delay_at)
+    /// accounts for.
+    fn delay(&self) -> usize {
+        1
+    }
+
+    /// Only a bank of zero coefficients, whose sections are pure delays,
+    /// lags the same at every frequency.
+    fn group_delay(&self) -> Option<T> {
+        let mut coefficients = self.path0.iter().chain(self.path1.iter());
+        coefficients
+            .all(|a| a.is_zero())
+            .then(|| self.group_delay_at(T::zero()))
+    }
+
+    /// The `z⁻¹` and each section `(a + z⁻²) / (1 + a·z⁻²)` of both branches,
+    /// which lags by `2·(1 - a²) / (1 + 2a·cos 2ω + a²)`.
+    fn group_delay_at(&self, omega: T) -> T {
+        let two = T::from(2).unwrap();
+        let cos = (two * omega).cos();
+        self.path0
+            .iter()
+            .chain(self.path1.iter())
+            .fold(T::one(), |delay, &a| {
+                delay + two * (T::one() - a * a) / (T::one() + two * a * cos + a * a)
+            })
+    }
+
+    fn compensator(&self, stride: usize) -> AllpassCascade<T> {
+        let sections: Vec<T> = self
+            .path0
+            .iter()
+            .chain(self.path1.iter())
+            .copied()
+            .collect();
+        AllpassCascade::new(&sections, 2 * stride, stride)
+    }
+}
+
+/// A chain of first-order allpass sections `(a + z⁻ᴸ) / (1 + a·z⁻ᴸ)` behind a
+/// plain delay.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllpassCascade<T>
+where
+    T: Float,
+{
+    delay: DelayLine<T>,
+    sections: Vec<AllpassSection<T>>,
+}
+
+impl<T> AllpassCascade<T>
+where
+    T: Float,
+{
+    fn new(coefficients: &[T], lag: usize, delay: usize) -> Self {
+        Self {
+            delay: DelayLine::new(delay),
+            sections: coefficients
+                .iter()
+                .map(|&a| AllpassSection::new(a, lag))
+                .collect(),
+        }
+    }
+}
+
+impl<T> FilterKernel<T> for AllpassCascade<T>
+where
+    T: Float,
+{
+    fn consume(&mut self, x: T) -> T {
+        let x = self.delay.consume(x);
+        self.sections
+            .iter_mut()
+            .fold(x, |x, section| section.consume(x))
+    }
+
+    fn reset(&mut self) {
+        self.delay.reset();
+        self.sections.iter_mut().for_each(AllpassSection::reset);
+    }
+
+    /// Samples until the impulse response has decayed by 60 dB.
+    fn len(&self) -> usize {
+        let decay = T::from(1e-3).unwrap().ln();
+        self.sections.iter().fold(self.delay.len(), |len, section| {
+            let a = section.coefficient.abs();
+            let steps = if a > T::zero() {
+                (decay / a.ln()).ceil().to_usize().unwrap_or(usize::MAX)
+            } else {
+                1
+            };
+            len.saturating_add(steps.saturating_mul(section.inputs.len()))
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct AllpassSection<T> {
+    coefficient: T,
+    inputs: VecDeque<T>,
+    outputs: VecDeque<T>,
+}
+
+impl<T> AllpassSection<T>
+where
+    T: Float,
+{
+    fn new(coefficient: T, lag: usize) -> Self {
+        Self {
+            coefficient,
+            inputs: VecDeque::from(alloc::vec![T::zero(); lag]),
+            outputs: VecDeque::from(alloc::vec![T::zero(); lag]),
+        }
+    }
+
+    fn consume(&mut self, x: T) -> T {
+        let x_lag = self.inputs.pop_front().unwrap();
+        let y_lag = self.outputs.pop_front().unwrap();
+        let y = self.coefficient * (x - y_lag) + x_lag;
+        self.inputs.push_back(x);
+        self.outputs.push_back(y);
+        y
+    }
+
+    fn reset(&mut self) {
+        self.inputs.iter_mut().for_each(|x| *x = T::zero());
+        self.outputs.iter_mut().for_each(|y| *y = T::zero());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        allpass::AllpassQmf,
+        bands::{Bands, QmfBand},
+        fir::FilterKernel,
+        test_util::white_noise,
+    };
+
+    fn rms(xs: &[f64]) -> f64 {
+        (xs.iter().map(|x| x * x).sum::<f64>() / xs.len() as f64).sqrt()
+    }
+
+    #[test]
+    fn test_swept_sine_magnitude() {
+        let mut bands = Bands::<f64, 3, AllpassQmf<f64>>::allpass();
+        // the `z⁻¹` of each level adds up to 7 samples, and the allpass
+        // branches lag on top of that
+        assert_eq!(7, bands.delay());
+        assert_eq!(None, bands.group_delay());
+        assert!(bands.group_delay_at(0.1) > 7.);
+
+        // logarithmic sweep from 500 Hz to 20 kHz at 48 kHz
+        let (len, rate, f0, f1): (usize, f64, f64, f64) = (1 << 16, 48000., 500., 20000.);
+        let duration = len as f64 / rate;
+        let k = (f1 / f0).ln();
+        let input: Vec<f64> = (0..len)
+            .map(|n| {
+                let t = n as f64 / rate;
+                let phase = 2. * core::f64::consts::PI * f0 * duration / k
+                    * ((t / duration * k).exp() - 1.);
+                phase.sin()
+            })
+            .collect();
+        let mut output = input.clone();
+        bands.process(output.as_mut_slice(), |_d, _c| {});
+
+        let window = 4096;
+        for start in (window..len - window).step_by(window) {
+            let expected = rms(&input[start..start + window]);
+            let actual = rms(&output[start..start + window]);
+            let db = 20. * (actual / expected).log10();
+            assert!(db.abs() < 0.1, "{db} dB at sample {start}");
+        }
+    }
+
+    #[test]
+    fn test_round_trip_is_allpass() {
+        // a tree of three levels reproduces R(z)·R(z²)·R(z⁴)
+        let band = AllpassQmf::<f64>::new();
+        let mut response: Vec<_> = [1, 2, 4].map(|stride| band.compensator(stride)).into();
+        let input = white_noise(4096, 23);
+        let expected: Vec<f64> = input
+            .iter()
+            .map(|&x| response.iter_mut().fold(x, |x, r| r.consume(x)))
+            .collect();
+
+        let mut bands = Bands::<f64, 3, AllpassQmf<f64>>::allpass();
+        let mut output = input.clone();
+        bands.process(output.as_mut_slice(), |_d, _c| {});
+        for (y, e) in output.iter().zip(expected.iter()) {
+            assert!((y - e).abs() < 1e-12, "{y} != {e}");
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use alloc::vec::Vec;
+use num_traits::Float;
+
+use crate::{
+    design::{convolve, halfband, hilbert, modulate, Window},
+    fir::DynFirFilter,
+};
+
+/// A complex sample as `(re, im)`.
+pub type ComplexSample<T> = (T, T);
+
+/// An analysis band with complex, approximately analytic outputs: each
+/// branch runs its filter and the Hilbert transform of it side by side, as the
+/// real and imaginary part. A one-sided spectrum does not alias when halved,
+/// so both branches are decimated by two like a [`Band`](crate::Band).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComplexBand<T>
+where
+    T: Float,
+{
+    low: DynFirFilter<T>,
+    low_quadrature: DynFirFilter<T>,
+    high: DynFirFilter<T>,
+    high_quadrature: DynFirFilter<T>,
+    // the next input is an odd sample, which decimation drops
+    odd: bool,
+}
+
+impl<T> ComplexBand<T>
+where
+    T: Float,
+{
+    /// A band split at a quarter of the sample rate by a 63 tap half-band
+    /// lowpass and its mirror.
+    pub fn new() -> Self {
+        let lowpass = halfband(63, Window::Kaiser { beta: 8. });
+        let transformer = hilbert(63, Window::Kaiser { beta: 6. });
+        let convert =
+            |taps: Vec<f64>| -> Vec<T> { taps.iter().map(|&h| T::from(h).unwrap()).collect() };
+        Self::from_taps(&convert(lowpass), &convert(transformer))
+    }
+
+    /// A band from a lowpass, whose mirror makes the highpass, and an odd
+    /// length Hilbert transformer such as [`hilbert`] designs.
+    ///
+    /// Panics if either filter is empty or the transformer has an even length.
+    pub fn from_taps(lowpass: &[T], transformer: &[T]) -> Self {
+        assert!(!lowpass.is_empty(), "the lowpass needs taps");
+        assert!(
+            transformer.len() % 2 == 1,
+            "a Hilbert transformer needs an odd number of taps"
+        );
+        let highpass = modulate(lowpass);
+        // delays the real part by the center of the transformer
+        let mut center = alloc::vec![T::zero(); transformer.len()];
+        center[transformer.len() / 2] = T::one();
+        let series = |a: &[T], b: &[T]| {
+            let mut taps = alloc::vec![T::zero(); a.len() + b.len() - 1];
+            convolve(a, b, &mut taps);
+            DynFirFilter::from_taps(&taps)
+        };
+        let pair = |taps: &[T]| (series(taps, &center), series(taps, transformer));
+        let (low, low_quadrature) = pair(lowpass);
+        let (high, high_quadrature) = pair(&highpass);
+        Self {
+            low,
+            low_quadrature,
+            high,
+            high_quadrature,
+            odd: false,
+        }
+    }
+
+    /// Splits `xs` into half-rate complex low and high bands.
+    pub fn analysis(&mut self, xs: &[T]) -> (Vec<ComplexSample<T>>, Vec<ComplexSample<T>>) {
+        let mut low = Vec::with_capacity(xs.len() / 2 + 1);
+        let mut high = Vec::with_capacity(xs.len() / 2 + 1);
+        for &x in xs {
+            if self.odd {
+                self.low.skip(x);
+                self.low_quadrature.skip(x);
+                self.high.skip(x);
+                self.high_quadrature.skip(x);
+            } else {
+                low.push((self.low.consume(x), self.low_quadrature.consume(x)));
+                high.push((self.high.consume(x), self.high_quadrature.consume(x)));
+            }
+            self.odd = !self.odd;
+        }
+        (low, high)
+    }
+
+    pub fn reset(&mut self) {
+        self.low.reset();
+        self.low_quadrature.reset();
+        self.high.reset();
+        self.high_quadrature.reset();
+        self.odd = false;
+    }
+
+    /// Input samples the outputs lag behind, the group delay of the
+    /// linear phase filters.
+    pub fn delay(&self) -> usize {
+        (self.low.taps().len() - 1) / 2
+    }
+}
+
+impl<T> Default for ComplexBand<T>
+where
+    T: Float,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The magnitude of each complex sample, which for an analytic subband is
+/// its envelope.
+pub fn envelope<T: Float>(coeffs: &[ComplexSample<T>]) -> Vec<T> {
+    coeffs.iter().map(|&(re, im)| re.hypot(im)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{envelope, ComplexBand};
+    use core::f64::consts::PI;
+
+    #[test]
+    fn test_envelope_tracks_modulator() {
+        let modulator = |n: f64| 1. + 0.5 * (0.01 * n).cos();
+        for carrier in [PI / 4., 3. * PI / 4.] {
+            let input: Vec<f64> = (0..4096)
+                .map(|n| modulator(n as f64) * (carrier * n as f64).sin())
+                .collect();
+            let mut band = ComplexBand::<f64>::new();
+            let delay = band.delay();
+            let (low, high) = band.analysis(&input);
+            let (inside, outside) = if carrier < PI / 2. {
+                (low, high)
+            } else {
+                (high, low)
+            };
+
+            // once the filters have filled up, at half the rate
+            let start = delay + 1;
+            for (m, e) in envelope(&inside).iter().enumerate().skip(start) {
+                let expected = modulator((2 * m - delay) as f64);
+                assert!((e - expected).abs() < 0.01, "{e} != {expected} at {m}");
+            }
+            assert!(envelope(&outside)[start..].iter().all(|e| *e < 1e-3));
+        }
+    }
+}
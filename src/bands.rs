@@ -1,24 +1,185 @@
+use alloc::vec::Vec;
 use core::array;
 use num_traits::Float;
 
 use crate::{
-    haar::HaarFilter,
-    sampling::{DownSampler, UpSampler},
+    allpass::AllpassQmf,
+    coeffs::WaveletCoeffs,
+    compress::{dequantize, quantize, QuantizedCoeffs},
+    denoise::{soft_threshold, universal_threshold},
+    error::QmfError,
+    fir::{DelayLine, DynFirFilter, FilterKernel, FirFilter},
+    haar::FixedHaarFilter,
+    lifting::LiftingHaar,
+    sampling::{decimate, interpolate, DownSampler, UpSampler},
+    wavelet::{FilterSet, Normalization, Wavelet},
 };
 
-struct Band<T>
+/// One level of a [`Bands`] tree: splits a signal into half-rate low and high
+/// bands and merges them back.
+pub trait QmfBand<T> {
+    /// Filter reproducing the analysis/synthesis round trip of the band.
+    type Compensator: FilterKernel<T>;
+
+    fn analysis(&mut self, xs: &[T]) -> (Vec<T>, Vec<T>);
+
+    /// Like [`analysis`](Self::analysis), writing to the front of `low` and
+    /// `high` instead, which need room for `xs.len() / 2 + 1` samples each.
+    /// Returns how many samples each band got.
+    ///
+    /// The default implementation goes through `analysis` and so allocates.
+    fn analysis_into(&mut self, xs: &[T], low: &mut [T], high: &mut [T]) -> usize
+    where
+        T: Copy,
+    {
+        let (l, h) = self.analysis(xs);
+        low[..l.len()].copy_from_slice(&l);
+        high[..h.len()].copy_from_slice(&h);
+        l.len()
+    }
+
+    fn synthesis(&mut self, low: &[T], high: &[T], out: &mut [T]);
+
+    fn reset(&mut self);
+
+    /// Whole samples the round trip lags behind the input. A band without
+    /// linear phase lags by more, depending on the frequency; this leaves
+    /// that dispersion out, see [`group_delay_at`](Self::group_delay_at).
+    fn delay(&self) -> usize;
+
+    /// Group delay of the round trip in samples if it is the same at every
+    /// frequency, and `None` otherwise. The default is the
+    /// [`delay`](Self::delay), as for a perfect reconstruction FIR band.
+    fn group_delay(&self) -> Option<T>
+    where
+        T: Float,
+    {
+        T::from(self.delay())
+    }
+
+    /// Group delay of the round trip in samples at the angular frequency
+    /// `omega` in `[0, π]`. The default is the [`delay`](Self::delay).
+    fn group_delay_at(&self, omega: T) -> T
+    where
+        T: Float,
+    {
+        let _ = omega;
+        T::from(self.delay()).unwrap()
+    }
+
+    /// The round trip response `R(z)` as `R(z^stride)`, for a stream running
+    /// `stride` times faster than the input of this band. An outer level runs
+    /// its highpass branch through these to stay aligned with its lowpass.
+    fn compensator(&self, stride: usize) -> Self::Compensator;
+}
+
+/// Runs an [`analysis_into`](QmfBand::analysis_into) of `len` samples on
+/// new vectors, trimmed to the samples it wrote.
+pub(crate) fn analysis_vecs<T: Float>(
+    len: usize,
+    analysis_into: impl FnOnce(&mut [T], &mut [T]) -> usize,
+) -> (Vec<T>, Vec<T>) {
+    let mut low = alloc::vec![T::zero(); len / 2 + 1];
+    let mut high = alloc::vec![T::zero(); len / 2 + 1];
+    let n = analysis_into(&mut low, &mut high);
+    low.truncate(n);
+    high.truncate(n);
+    (low, high)
+}
+
+/// The sample pairing of a band that runs in polyphase form: analysis takes
+/// the input two samples at a time and synthesis gives two outputs per pair,
+/// both across calls.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Pairs<T> {
+    // the odd input sample waiting for the next even one
+    odd: Option<T>,
+    // the second output of a synthesis pair that did not fit into `out`
+    pending: Option<T>,
+}
+
+impl<T> Pairs<T>
+where
+    T: Float,
+{
+    pub(crate) fn new() -> Self {
+        Self {
+            odd: Some(T::zero()),
+            pending: None,
+        }
+    }
+
+    /// Calls `split(odd, even)` on each pair of `xs` for its low and high
+    /// sample, as [`QmfBand::analysis_into`].
+    pub(crate) fn analysis_into(
+        &mut self,
+        xs: &[T],
+        low: &mut [T],
+        high: &mut [T],
+        mut split: impl FnMut(T, T) -> (T, T),
+    ) -> usize {
+        let mut n = 0;
+        for &x in xs {
+            // an even sample pairs up with the odd one before it
+            let Some(odd) = self.odd.take() else {
+                self.odd = Some(x);
+                continue;
+            };
+            (low[n], high[n]) = split(odd, x);
+            n += 1;
+        }
+        n
+    }
+
+    /// Writes the two outputs `merge(low, high)` of each pair to `out`,
+    /// starting with the one left over from the last call.
+    pub(crate) fn synthesis(
+        &mut self,
+        low: &[T],
+        high: &[T],
+        out: &mut [T],
+        mut merge: impl FnMut(T, T) -> (T, T),
+    ) {
+        let mut out = out.iter_mut();
+        if let Some(p) = self.pending.take() {
+            match out.next() {
+                Some(o) => *o = p,
+                None => self.pending = Some(p),
+            }
+        }
+        for (&l, &h) in low.iter().zip(high.iter()) {
+            let Some(o) = out.next() else {
+                break;
+            };
+            let p;
+            (*o, p) = merge(l, h);
+            match out.next() {
+                Some(o) => *o = p,
+                None => self.pending = Some(p),
+            }
+        }
+    }
+}
+
+/// A two-channel FIR filter bank.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Band<T, F = DynFirFilter<T>>
 where
     T: Float,
+    F: FilterKernel<T>,
 {
-    in_lowpass_filter: HaarFilter<T>,
-    in_highpass_filter: HaarFilter<T>,
-    out_lowpass_filter: HaarFilter<T>,
-    out_highpass_filter: HaarFilter<T>,
+    in_lowpass_filter: F,
+    in_highpass_filter: F,
+    out_lowpass_filter: F,
+    out_highpass_filter: F,
 
     low_upsampler: UpSampler<T>,
     low_downsampler: DownSampler,
     high_upsampler: UpSampler<T>,
     high_downsampler: DownSampler,
+
+    delay: usize,
 }
 
 impl<T> Band<T>
@@ -26,43 +187,252 @@ where
     T: Float,
 {
     pub fn new() -> Self {
-        // rational number coefficients are taken from
-        // [奥村 博造. ハールウェーブレット変換と完全再構成QMフィルタ](https://nagano.repo.nii.ac.jp/record/457/files/nagano_20-04-01.pdf)
-        Self {
-            in_lowpass_filter: HaarFilter::new(0.5, 0.5),
-            in_highpass_filter: HaarFilter::new(-0.5, 0.5),
-            out_lowpass_filter: HaarFilter::new(1., 1.),
-            out_highpass_filter: HaarFilter::new(1., -1.),
+        Self::from_filter_set(&FilterSet::haar())
+    }
+
+    pub fn from_filter_set(filters: &FilterSet<T>) -> Self {
+        Self::from_kernels(
+            DynFirFilter::from_taps(filters.analysis_low()),
+            DynFirFilter::from_taps(filters.analysis_high()),
+            DynFirFilter::from_taps(filters.synthesis_low()),
+            DynFirFilter::from_taps(filters.synthesis_high()),
+        )
+    }
+
+    /// A Daubechies-4 (db2) band, see [`Bands::daubechies4`].
+    pub fn daubechies4() -> Self {
+        Self::from_filter_set(&Wavelet::daubechies(2).unwrap())
+    }
+
+    /// A Haar band in the given convention.
+    pub fn with_normalization(normalization: Normalization) -> Self {
+        Self::from_filter_set(&FilterSet::haar().normalized(normalization))
+    }
 
-            low_upsampler: UpSampler::with_zero(2),
-            low_downsampler: DownSampler::new(2),
-            high_upsampler: UpSampler::with_zero(2),
-            high_downsampler: DownSampler::new(2),
+    pub fn with_filters(
+        analysis_low: &[T],
+        analysis_high: &[T],
+        synthesis_low: &[T],
+        synthesis_high: &[T],
+    ) -> Result<Self, QmfError> {
+        let filters = FilterSet::new(analysis_low, analysis_high, synthesis_low, synthesis_high)?;
+        Ok(Self::from_filter_set(&filters))
+    }
+
+    /// The taps currently in use, e.g. to inspect a band built by a design
+    /// routine.
+    pub fn filter_set(&self) -> FilterSet<T> {
+        FilterSet {
+            analysis_low: self.in_lowpass_filter.taps().to_vec(),
+            analysis_high: self.in_highpass_filter.taps().to_vec(),
+            synthesis_low: self.out_lowpass_filter.taps().to_vec(),
+            synthesis_high: self.out_highpass_filter.taps().to_vec(),
         }
     }
 
-    pub fn analysis(&mut self, xs: &[T]) -> (alloc::vec::Vec<T>, alloc::vec::Vec<T>) {
-        let mut low = alloc::vec::Vec::from(xs);
-        let mut high = alloc::vec::Vec::from(xs);
-        for (l, h) in core::iter::zip(low.iter_mut(), high.iter_mut()) {
-            *l = self.in_lowpass_filter.consume(*l);
-            *h = self.in_highpass_filter.consume(*h);
+    /// Rescales the synthesis filters so a round trip has unit gain, see
+    /// [`FilterSet::with_unity_gain`].
+    pub fn normalize_reconstruction(&mut self) -> Result<(), QmfError> {
+        let filters = self.filter_set().with_unity_gain()?;
+        self.out_lowpass_filter.set_taps(filters.synthesis_low(), 0);
+        self.out_highpass_filter
+            .set_taps(filters.synthesis_high(), 0);
+        Ok(())
+    }
+
+    /// Ramps all four filters to new taps of the same lengths over
+    /// `ramp_samples` samples, see [`FirFilter::set_taps`]. The delay stays
+    /// as measured for the old taps.
+    ///
+    /// [`FirFilter::set_taps`]: crate::FirFilter::set_taps
+    pub fn set_filters(&mut self, filters: &FilterSet<T>, ramp_samples: usize) {
+        self.in_lowpass_filter
+            .set_taps(filters.analysis_low(), ramp_samples);
+        self.in_highpass_filter
+            .set_taps(filters.analysis_high(), ramp_samples);
+        self.out_lowpass_filter
+            .set_taps(filters.synthesis_low(), ramp_samples);
+        self.out_highpass_filter
+            .set_taps(filters.synthesis_high(), ramp_samples);
+    }
+}
+
+impl<T, F> Band<T, F>
+where
+    T: Float,
+    F: FilterKernel<T>,
+{
+    pub fn from_kernels(
+        in_lowpass_filter: F,
+        in_highpass_filter: F,
+        out_lowpass_filter: F,
+        out_highpass_filter: F,
+    ) -> Self {
+        let mut band = Self {
+            in_lowpass_filter,
+            in_highpass_filter,
+            out_lowpass_filter,
+            out_highpass_filter,
+
+            low_upsampler: UpSampler::with_zero(2).unwrap(),
+            low_downsampler: DownSampler::new(2).unwrap(),
+            high_upsampler: UpSampler::with_zero(2).unwrap(),
+            high_downsampler: DownSampler::new(2).unwrap(),
+
+            delay: 0,
+        };
+        band.delay = band.reconstruction_delay();
+        band
+    }
+
+    /// The analysis/synthesis round trip of a perfect reconstruction band is a
+    /// pure delay; this is where its impulse response peaks. Leaves the band
+    /// reset.
+    fn reconstruction_delay(&mut self) -> usize {
+        let low = self.in_lowpass_filter.len() + self.out_lowpass_filter.len();
+        let high = self.in_highpass_filter.len() + self.out_highpass_filter.len();
+        let len = low.max(high).next_multiple_of(2);
+
+        let mut impulse = alloc::vec![T::zero(); len];
+        impulse[0] = T::one();
+        let (lows, highs) = self.analysis(&impulse);
+        self.synthesis(&lows, &highs, &mut impulse);
+        self.reset();
+
+        impulse
+            .iter()
+            .map(|y| y.abs())
+            .enumerate()
+            .fold(
+                (0, T::zero()),
+                |best, (i, v)| if v > best.1 { (i, v) } else { best },
+            )
+            .0
+    }
+
+    pub fn analysis(&mut self, xs: &[T]) -> (Vec<T>, Vec<T>) {
+        analysis_vecs(xs.len(), |low, high| self.analysis_into(xs, low, high))
+    }
+
+    /// See [`QmfBand::analysis_into`].
+    pub fn analysis_into(&mut self, xs: &[T], low: &mut [T], high: &mut [T]) -> usize {
+        // the dropped half of each branch is never computed
+        let mut n = 0;
+        for &x in xs {
+            if let Some(y) = decimate(&mut self.in_lowpass_filter, &mut self.low_downsampler, x) {
+                low[n] = y;
+                n += 1;
+            }
         }
-        (
-            self.low_downsampler.iter(low.into_iter()).collect(),
-            self.high_downsampler.iter(high.into_iter()).collect(),
-        )
+        let mut i = 0;
+        for &x in xs {
+            if let Some(y) = decimate(&mut self.in_highpass_filter, &mut self.high_downsampler, x) {
+                high[i] = y;
+                i += 1;
+            }
+        }
+        n
+    }
+
+    /// Lazily analyses `src`, yielding a `(low, high)` pair for every pair of
+    /// inputs. Gives the same samples as [`analysis`](Self::analysis) of the
+    /// collected input, and leaves the band in the same state.
+    pub fn analysis_iter<I: Iterator<Item = T>>(&mut self, src: I) -> Analysis<'_, T, F, I> {
+        Analysis { band: self, src }
     }
 
+    /// Upsamples both branches, filters them and sums them into `out`.
+    ///
+    /// `low` and `high` have the same length, and `out` takes every sample
+    /// they upsample to: twice that length, plus a stuffed zero still owed
+    /// from the last call. It may also be one shorter, stopping right after
+    /// the last input and owing its zero to the next call, as odd block
+    /// lengths do. Debug builds panic on any other length; release builds
+    /// stop at whichever of `out` and the branches runs out first.
     pub fn synthesis(&mut self, low: &[T], high: &[T], out: &mut [T]) {
-        for ((l, h), o) in core::iter::zip(
-            self.low_upsampler.iter(low.iter().copied()),
-            self.high_upsampler.iter(high.iter().copied()),
-        )
-        .zip(out.iter_mut())
-        {
-            *o = self.out_lowpass_filter.consume(l) + self.out_highpass_filter.consume(h)
+        debug_assert_eq!(low.len(), high.len(), "branch lengths differ");
+        debug_assert!(
+            {
+                let owed = self.low_upsampler.phase();
+                let upsampled = 2 * low.len() + owed;
+                out.len() == upsampled || out.len() + 1 == upsampled
+            },
+            "output length does not match the upsampled branches"
+        );
+        // going by `out` keeps from pulling an extra sample out of the
+        // upsamplers, which would be lost with the next block; the filters
+        // skip the stuffed zeros
+        let (mut low, mut high) = (low.iter().copied(), high.iter().copied());
+        for o in out.iter_mut() {
+            let Some(l) = interpolate(
+                &mut self.out_lowpass_filter,
+                &mut self.low_upsampler,
+                &mut low,
+            ) else {
+                break;
+            };
+            let Some(h) = interpolate(
+                &mut self.out_highpass_filter,
+                &mut self.high_upsampler,
+                &mut high,
+            ) else {
+                break;
+            };
+            *o = l + h
+        }
+    }
+
+    /// Whether all four samplers sit at the start of a pair, as after a
+    /// reset or after blocks of even length.
+    pub fn is_aligned(&self) -> bool {
+        [self.low_upsampler.phase(), self.high_upsampler.phase()]
+            .into_iter()
+            .chain([self.low_downsampler.phase(), self.high_downsampler.phase()])
+            .all(|phase| phase == 0)
+    }
+
+    pub fn reset(&mut self) {
+        self.in_lowpass_filter.reset();
+        self.in_highpass_filter.reset();
+        self.out_lowpass_filter.reset();
+        self.out_highpass_filter.reset();
+
+        self.low_upsampler.reset();
+        self.low_downsampler.reset();
+        self.high_upsampler.reset();
+        self.high_downsampler.reset();
+    }
+}
+
+/// Iterator returned by [`Band::analysis_iter`].
+pub struct Analysis<'a, T, F, I>
+where
+    T: Float,
+    F: FilterKernel<T>,
+{
+    band: &'a mut Band<T, F>,
+    src: I,
+}
+
+impl<'a, T, F, I> Iterator for Analysis<'a, T, F, I>
+where
+    T: Float,
+    F: FilterKernel<T>,
+    I: Iterator<Item = T>,
+{
+    type Item = (T, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let band = &mut *self.band;
+        for x in self.src.by_ref() {
+            // both downsamplers share their phase, so they keep the same input
+            let low = decimate(&mut band.in_lowpass_filter, &mut band.low_downsampler, x);
+            let high = decimate(&mut band.in_highpass_filter, &mut band.high_downsampler, x);
+            if let (Some(low), Some(high)) = (low, high) {
+                return Some((low, high));
+            }
         }
+        None
     }
 }
 
@@ -75,11 +445,58 @@ where
     }
 }
 
-pub struct Bands<T, const N: usize>
+impl<T, F> QmfBand<T> for Band<T, F>
+where
+    T: Float,
+    F: FilterKernel<T>,
+{
+    type Compensator = DelayLine<T>;
+
+    fn analysis(&mut self, xs: &[T]) -> (Vec<T>, Vec<T>) {
+        Band::analysis(self, xs)
+    }
+
+    fn analysis_into(&mut self, xs: &[T], low: &mut [T], high: &mut [T]) -> usize {
+        Band::analysis_into(self, xs, low, high)
+    }
+
+    fn synthesis(&mut self, low: &[T], high: &[T], out: &mut [T]) {
+        Band::synthesis(self, low, high, out)
+    }
+
+    fn reset(&mut self) {
+        Band::reset(self)
+    }
+
+    fn delay(&self) -> usize {
+        self.delay
+    }
+
+    fn compensator(&self, stride: usize) -> DelayLine<T> {
+        DelayLine::new(self.delay * stride)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T: serde::Serialize, B: serde::Serialize, B::Compensator: serde::Serialize",
+        deserialize = "T: serde::Deserialize<'de>, B: serde::Deserialize<'de>, B::Compensator: serde::Deserialize<'de>"
+    ))
+)]
+pub struct Bands<T, const N: usize, B = Band<T>>
 where
     T: Float,
+    B: QmfBand<T>,
 {
-    bands: [Band<T>; N],
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
+    bands: [B; N],
+    // the highpass branch of each band has to wait for its lowpass branch to
+    // come back out of the deeper bands before the two are recombined
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
+    compensators: [Vec<B::Compensator>; N],
 }
 
 impl<T, const N: usize> Bands<T, N>
@@ -87,36 +504,313 @@ where
     T: Float,
 {
     pub fn new() -> Self {
+        Self::from_bands(array::from_fn(|_| Band::new()))
+    }
+
+    /// A Haar filter bank in the given convention.
+    pub fn with_normalization(normalization: Normalization) -> Self {
+        Self::from_bands(array::from_fn(|_| Band::with_normalization(normalization)))
+    }
+
+    /// A filter bank built on the Daubechies-4 (db2) wavelet instead of Haar.
+    /// Its two vanishing moments leave no detail in linear trends, which
+    /// Haar splits into a constant detail.
+    pub fn daubechies4() -> Self {
+        Self::from_bands(array::from_fn(|_| Band::daubechies4()))
+    }
+
+    pub fn with_wavelet(wavelet: Wavelet) -> Result<Self, QmfError> {
+        Ok(Self::from_filter_set(&wavelet.filter_set()?))
+    }
+
+    /// A filter bank running user supplied taps at every level.
+    pub fn with_filters(
+        analysis_low: &[T],
+        analysis_high: &[T],
+        synthesis_low: &[T],
+        synthesis_high: &[T],
+    ) -> Result<Self, QmfError> {
+        let band = Band::with_filters(analysis_low, analysis_high, synthesis_low, synthesis_high)?;
+        Ok(Self::from_bands(array::from_fn(|_| band.clone())))
+    }
+
+    /// Like [`with_filters`](Self::with_filters), with the synthesis filters
+    /// rescaled for unit round trip gain, see [`FilterSet::with_unity_gain`].
+    pub fn with_normalized_filters(
+        analysis_low: &[T],
+        analysis_high: &[T],
+        synthesis_low: &[T],
+        synthesis_high: &[T],
+    ) -> Result<Self, QmfError> {
+        let filters = FilterSet::new(analysis_low, analysis_high, synthesis_low, synthesis_high)?;
+        Ok(Self::from_filter_set(&filters.with_unity_gain()?))
+    }
+
+    /// A filter bank running the same filters at every level.
+    pub fn from_filter_set(filters: &FilterSet<T>) -> Self {
+        Self::from_bands(array::from_fn(|_| Band::from_filter_set(filters)))
+    }
+
+    /// The taps in use at every level, finest first.
+    pub fn filter_sets(&self) -> [FilterSet<T>; N] {
+        array::from_fn(|level| self.bands[level].filter_set())
+    }
+
+    /// Rescales the synthesis filters of every level so that the bank
+    /// reconstructs its input at unit gain, see
+    /// [`Band::normalize_reconstruction`].
+    pub fn normalize_reconstruction(&mut self) -> Result<(), QmfError> {
+        self.bands
+            .iter_mut()
+            .try_for_each(Band::normalize_reconstruction)
+    }
+
+    /// Retunes every level while running, see [`Band::set_filters`].
+    pub fn set_filters(&mut self, filters: &FilterSet<T>, ramp_samples: usize) {
+        for band in self.bands.iter_mut() {
+            band.set_filters(filters, ramp_samples);
+        }
+    }
+}
+
+impl<T, const N: usize> Bands<T, N, AllpassQmf<T>>
+where
+    T: Float,
+{
+    /// A filter bank of IIR allpass bands, see [`AllpassQmf`].
+    pub fn allpass() -> Self {
+        Self::from_bands(array::from_fn(|_| AllpassQmf::new()))
+    }
+}
+
+impl<T, const N: usize> Bands<T, N, LiftingHaar<T>>
+where
+    T: Float,
+{
+    /// A Haar filter bank computed by lifting, see [`LiftingHaar`].
+    pub fn lifting_haar() -> Self {
+        Self::from_bands(array::from_fn(|_| LiftingHaar::new()))
+    }
+}
+
+impl<T, const N: usize, F> Bands<T, N, Band<T, F>>
+where
+    T: Float,
+    F: FilterKernel<T> + Clone,
+{
+    /// A filter bank running copies of the same four kernels at every level,
+    /// see [`Band::from_kernels`].
+    pub fn from_kernels(
+        in_lowpass_filter: F,
+        in_highpass_filter: F,
+        out_lowpass_filter: F,
+        out_highpass_filter: F,
+    ) -> Self {
+        let band = Band::from_kernels(
+            in_lowpass_filter,
+            in_highpass_filter,
+            out_lowpass_filter,
+            out_highpass_filter,
+        );
+        Self::from_bands(array::from_fn(|_| band.clone()))
+    }
+
+    /// Whether every level is [aligned](Band::is_aligned), so the next block
+    /// starts a new group of `2^N` samples.
+    pub fn is_aligned(&self) -> bool {
+        self.bands.iter().all(Band::is_aligned)
+    }
+}
+
+impl<T, const N: usize, B> Bands<T, N, B>
+where
+    T: Float,
+    B: QmfBand<T>,
+{
+    /// A filter bank from its levels, outermost first.
+    pub fn from_bands(bands: [B; N]) -> Self {
+        let compensators = array::from_fn(|level| compensators(&bands[level + 1..]));
         Self {
-            bands: array::from_fn(|_| Band::new()),
+            bands,
+            compensators,
         }
     }
 
+    /// Splits `buffer` into subbands, hands each to `closure` (the
+    /// approximation as band `N`, then the details from band `N - 1` down to
+    /// band 0) and resynthesizes it in place. The state carries over between
+    /// calls, so a stream can be fed in blocks of any length, not only
+    /// multiples of `2^N`.
     pub fn process<F>(&mut self, buffer: &mut [T], mut closure: F)
     where
         F: FnMut(&mut [T], usize),
     {
-        self.process_band(buffer, &mut closure, 0)
+        process_levels(
+            &mut self.bands,
+            &mut self.compensators,
+            buffer,
+            &mut closure,
+        )
     }
 
-    fn process_band<F>(&mut self, buffer: &mut [T], closure: &mut F, count: usize)
+    /// [`process`](Self::process) without touching the heap, keeping the
+    /// subbands in `scratch` instead, which needs at least
+    /// [`scratch_len`](Self::scratch_len) samples.
+    pub fn process_in<F>(&mut self, buffer: &mut [T], scratch: &mut [T], mut closure: F)
     where
         F: FnMut(&mut [T], usize),
     {
-        let (mut lows, mut highs) = self.bands[count].analysis(buffer);
+        assert!(
+            scratch.len() >= scratch_len(N, buffer.len()),
+            "scratch buffer too short"
+        );
+        process_levels_in(
+            &mut self.bands,
+            &mut self.compensators,
+            0,
+            buffer,
+            scratch,
+            &mut closure,
+        )
+    }
+
+    /// Scratch samples [`process_in`](Self::process_in) needs for a buffer of
+    /// `buffer_len` samples: room for both halves of every level, `2·mₖ` with
+    /// `m₀ = buffer_len / 2 + 1` and `mₖ₊₁ = mₖ / 2 + 1`, a little over
+    /// `2·buffer_len` in all.
+    pub fn scratch_len(&self, buffer_len: usize) -> usize {
+        scratch_len(N, buffer_len)
+    }
 
-        if count + 1 >= N {
-            closure(lows.as_mut_slice(), count + 1);
-        } else {
-            self.process_band(lows.as_mut_slice(), closure, count + 1);
+    /// Scales every subband before resynthesis, as a multi-band equalizer.
+    /// `gains[k]` applies to the details of level `k` (0 being the highest
+    /// frequencies) and `gains[N]` to the approximation.
+    ///
+    /// Panics unless there are `N + 1` gains.
+    pub fn process_with_gains(&mut self, buffer: &mut [T], gains: &[T]) {
+        assert_eq!(N + 1, gains.len(), "need one gain per subband");
+        self.process(buffer, |band, level| {
+            band.iter_mut().for_each(|x| *x = *x * gains[level]);
+        })
+    }
+
+    /// Soft thresholds every detail band by `lambda`, leaving the
+    /// approximation alone; see [`soft_threshold`].
+    pub fn denoise(&mut self, buffer: &mut [T], lambda: T) {
+        self.process(buffer, |band, level| {
+            if level < N {
+                band.iter_mut()
+                    .for_each(|x| *x = soft_threshold(*x, lambda));
+            }
+        })
+    }
+
+    /// Like [`denoise`](Self::denoise) with the [`universal_threshold`] of
+    /// the finest detail band of `buffer`.
+    pub fn denoise_auto(&mut self, buffer: &mut [T]) {
+        denoise_auto(self.analyze(buffer), buffer, |c, out| {
+            self.synthesize(c, out)
+        })
+    }
+
+    /// Clears the state of every band so the next `process` starts from silence.
+    pub fn reset(&mut self) {
+        reset_levels(&mut self.bands, &mut self.compensators)
+    }
+
+    /// Number of samples the reconstructed signal lags behind the input.
+    ///
+    /// A band whose own round trip lags by `d` samples, wrapped around a
+    /// subtree lagging by `D` samples at half its rate, lags by `2·D + d`:
+    /// the inner samples are twice as long, and the even phase kept by the
+    /// downsampler lines up with the zero stuffed by the upsampler so no
+    /// extra sample is lost in between. Unrolling from the innermost band
+    /// gives `Σ 2^k · d_k` over levels `k`, so `2^N - 1` for Haar bands,
+    /// whose filters delay by one sample per level.
+    ///
+    /// Bands without linear phase, such as [`AllpassQmf`], count only their
+    /// pure delay here and lag by more, see
+    /// [`group_delay_at`](Self::group_delay_at).
+    pub fn delay(&self) -> usize {
+        tree_delay(&self.bands)
+    }
+
+    /// Group delay of the round trip in samples if it is the same at every
+    /// frequency, and `None` otherwise. That is the [`delay`](Self::delay)
+    /// for perfect reconstruction FIR bands, while [`AllpassQmf`] bands lag
+    /// by a different amount at each frequency, see
+    /// [`group_delay_at`](Self::group_delay_at).
+    pub fn group_delay(&self) -> Option<T> {
+        tree_group_delay(&self.bands)
+    }
+
+    /// Group delay of the round trip in samples at the angular frequency
+    /// `omega` in `[0, π]`. Composes like the [`delay`](Self::delay): level
+    /// `k` lags by `2^k·τₖ(2^k·ω)`, as it sees the frequency scaled up by its
+    /// slower rate.
+    pub fn group_delay_at(&self, omega: T) -> T {
+        tree_group_delay_at(&self.bands, omega)
+    }
+
+    /// Round trips `buffer` from silence with an identity closure and returns
+    /// the largest deviation of the output, [`delay`](Self::delay) samples
+    /// late, from the input. Resets the bank before and after.
+    ///
+    /// Only meaningful for linear phase bands: the output of any other, such
+    /// as [`AllpassQmf`], is not a delayed copy of the input.
+    pub fn reconstruction_error(&mut self, buffer: &[T]) -> T {
+        self.reset();
+        let mut data = buffer.to_vec();
+        self.process(&mut data, |_d, _c| {});
+        self.reset();
+        max_error(buffer, &data[self.delay().min(data.len())..])
+    }
+
+    /// Lengths of the detail bands, finest first, and of the approximation
+    /// that [`analyze`](Self::analyze) returns for `input_len` samples.
+    ///
+    /// Every level keeps the first of each pair of samples, so a level given
+    /// `n` samples splits them into `⌈n / 2⌉`. This holds for a bank at rest,
+    /// i.e. fresh, reset, or after blocks whose lengths are multiples of
+    /// `2^N`; within a pair the split of the next block shifts by one.
+    pub const fn subband_lengths(input_len: usize) -> ([usize; N], usize) {
+        let mut details = [0; N];
+        let mut len = input_len;
+        let mut level = 0;
+        while level < N {
+            len = len.div_ceil(2);
+            details[level] = len;
+            level += 1;
         }
-        closure(highs.as_mut_slice(), count);
+        (details, len)
+    }
+
+    /// Runs the analysis half of `process`, handing back the subbands.
+    pub fn analyze(&mut self, buffer: &[T]) -> WaveletCoeffs<T> {
+        analyze_levels(&mut self.bands, buffer)
+    }
+
+    /// Energy (sum of squares) of each subband of `buffer`, ordered like the
+    /// gains of [`process_with_gains`](Self::process_with_gains). Advances the
+    /// analysis state just as [`analyze`](Self::analyze) does.
+    pub fn band_energies(&mut self, buffer: &[T]) -> Vec<T> {
+        band_energies(&self.analyze(buffer))
+    }
+
+    /// Runs the synthesis half of `process`; `out` receives the signal
+    /// [`delay`](Self::delay) samples late.
+    pub fn synthesize(&mut self, coeffs: &WaveletCoeffs<T>, out: &mut [T]) {
+        synthesize_levels(&mut self.bands, &mut self.compensators, coeffs.clone(), out)
+    }
 
-        self.bands[count].synthesis(lows.as_slice(), highs.as_slice(), buffer);
+    /// Analyzes `buffer` and [`quantize`]s the subbands with the given steps.
+    pub fn compress(&mut self, buffer: &[T], steps: &[T]) -> QuantizedCoeffs<T> {
+        quantize(&self.analyze(buffer), steps)
     }
 
-    pub const fn delay(&self) -> usize {
-        2_i32.pow(N as u32) as usize
+    /// Synthesizes `out` from [`compress`](Self::compress)ed subbands.
+    pub fn decompress(&mut self, quantized: &QuantizedCoeffs<T>, out: &mut [T]) {
+        self.synthesize(&dequantize(quantized), out)
     }
 }
 
@@ -129,20 +823,1914 @@ where
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::Bands;
+/// Independent [`Bands`] for each channel of an interleaved buffer, e.g.
+/// `[L, R, L, R, …]` for stereo.
+pub struct MultiBands<T, const N: usize, const CH: usize, B = Band<T>>
+where
+    T: Float,
+    B: QmfBand<T>,
+{
+    channels: [Bands<T, N, B>; CH],
+    // one channel at a time, deinterleaved
+    scratch: Vec<T>,
+}
 
-    #[test]
-    fn test_bands_reconstruct() {
-        let mut bands: Bands<f64, 3> = Bands::new();
+impl<T, const N: usize, const CH: usize> MultiBands<T, N, CH>
+where
+    T: Float,
+{
+    pub fn new() -> Self {
+        Self::from_channels(array::from_fn(|_| Bands::new()))
+    }
 
-        let mut in_data = vec![1.; 128];
-        bands.process(in_data.as_mut_slice(), |_d, _c| {});
-        assert_eq!(vec![1.; 120], in_data[bands.delay()..]);
+    pub fn from_filter_set(filters: &FilterSet<T>) -> Self {
+        Self::from_channels(array::from_fn(|_| Bands::from_filter_set(filters)))
+    }
+}
 
-        let mut in_data = vec![1.; 128];
-        bands.process(in_data.as_mut_slice(), |_d, _c| {});
-        assert_eq!(vec![1.; 128], in_data);
+impl<T, const N: usize, const CH: usize, B> MultiBands<T, N, CH, B>
+where
+    T: Float,
+    B: QmfBand<T>,
+{
+    pub fn from_channels(channels: [Bands<T, N, B>; CH]) -> Self {
+        Self {
+            channels,
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Like [`Bands::process`] on each channel in turn; the closure also gets
+    /// the channel index.
+    ///
+    /// Panics unless the buffer holds whole frames of `CH` samples.
+    pub fn process<F>(&mut self, buffer: &mut [T], mut closure: F)
+    where
+        F: FnMut(&mut [T], usize, usize),
+    {
+        assert!(
+            buffer.len().is_multiple_of(CH),
+            "the buffer does not hold whole frames"
+        );
+        for (channel, bands) in self.channels.iter_mut().enumerate() {
+            self.scratch.clear();
+            self.scratch
+                .extend(buffer.iter().skip(channel).step_by(CH).copied());
+            bands.process(&mut self.scratch, |band, level| {
+                closure(band, level, channel)
+            });
+            for (x, &y) in buffer
+                .iter_mut()
+                .skip(channel)
+                .step_by(CH)
+                .zip(self.scratch.iter())
+            {
+                *x = y;
+            }
+        }
+    }
+
+    /// Like [`process`](Self::process) on one buffer per channel instead of
+    /// an interleaved one, running each channel on a thread of its own. The
+    /// channels share no state, so the output matches the serial version;
+    /// the speedup is bounded by the number of cores and pays off once the
+    /// buffers are long enough to outweigh spawning the threads.
+    ///
+    /// Panics unless there are exactly `CH` buffers.
+    #[cfg(feature = "parallel")]
+    pub fn process_par<F>(&mut self, buffers: &mut [&mut [T]], closure: F)
+    where
+        T: Send,
+        Bands<T, N, B>: Send,
+        F: Fn(&mut [T], usize, usize) + Sync,
+    {
+        assert_eq!(CH, buffers.len(), "one buffer per channel");
+        let closure = &closure;
+        std::thread::scope(|scope| {
+            for (channel, (bands, buffer)) in self.channels.iter_mut().zip(buffers).enumerate() {
+                scope.spawn(move || {
+                    bands.process(buffer, |band, level| closure(band, level, channel))
+                });
+            }
+        });
+    }
+
+    pub fn reset(&mut self) {
+        self.channels.iter_mut().for_each(Bands::reset);
+    }
+
+    /// Frames the reconstructed signal lags behind the input.
+    pub fn delay(&self) -> usize {
+        self.channels.first().map_or(0, Bands::delay)
+    }
+}
+
+impl<T, const N: usize, const CH: usize> Default for MultiBands<T, N, CH>
+where
+    T: Float,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A Haar [`Band`] in fixed point, on `i32` samples, for targets without an
+/// FPU. The analysis halves by arithmetic shift, so every subband sample is
+/// rounded to the nearest integer and the round trip is exact to within one
+/// unit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedHaarBand {
+    in_lowpass_filter: FixedHaarFilter,
+    in_highpass_filter: FixedHaarFilter,
+    out_lowpass_filter: FixedHaarFilter,
+    out_highpass_filter: FixedHaarFilter,
+
+    low_upsampler: UpSampler<i32>,
+    low_downsampler: DownSampler,
+    high_upsampler: UpSampler<i32>,
+    high_downsampler: DownSampler,
+}
+
+impl FixedHaarBand {
+    pub fn new() -> Self {
+        // one fractional bit holds the ±0.5 and ±1 taps exactly
+        Self {
+            in_lowpass_filter: FixedHaarFilter::new(1, 1, 1),
+            in_highpass_filter: FixedHaarFilter::new(-1, 1, 1),
+            out_lowpass_filter: FixedHaarFilter::new(2, 2, 1),
+            out_highpass_filter: FixedHaarFilter::new(2, -2, 1),
+
+            low_upsampler: UpSampler::with_zero(2).unwrap(),
+            low_downsampler: DownSampler::new(2).unwrap(),
+            high_upsampler: UpSampler::with_zero(2).unwrap(),
+            high_downsampler: DownSampler::new(2).unwrap(),
+        }
+    }
+
+    /// See [`Band::analysis`].
+    pub fn analysis(&mut self, xs: &[i32]) -> (Vec<i32>, Vec<i32>) {
+        let lows = xs.iter().map(|&x| self.in_lowpass_filter.consume(x));
+        let low = self.low_downsampler.iter(lows).collect();
+        let highs = xs.iter().map(|&x| self.in_highpass_filter.consume(x));
+        let high = self.high_downsampler.iter(highs).collect();
+        (low, high)
+    }
+
+    /// See [`Band::synthesis`]; the sum of the branches saturates.
+    pub fn synthesis(&mut self, low: &[i32], high: &[i32], out: &mut [i32]) {
+        for (o, (l, h)) in out.iter_mut().zip(core::iter::zip(
+            self.low_upsampler.iter(low.iter().copied()),
+            self.high_upsampler.iter(high.iter().copied()),
+        )) {
+            *o = self
+                .out_lowpass_filter
+                .consume(l)
+                .saturating_add(self.out_highpass_filter.consume(h))
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.in_lowpass_filter.reset();
+        self.in_highpass_filter.reset();
+        self.out_lowpass_filter.reset();
+        self.out_highpass_filter.reset();
+
+        self.low_upsampler.reset();
+        self.low_downsampler.reset();
+        self.high_upsampler.reset();
+        self.high_downsampler.reset();
+    }
+
+    /// Samples the round trip lags, one as for [`Band::new`].
+    pub fn delay(&self) -> usize {
+        1
+    }
+}
+
+impl Default for FixedHaarBand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A [`Band`] on blocks of `LEN` samples known at compile time, running
+/// `TAPS`-tap filters with their state on the stack. Analysis and synthesis
+/// go through arrays and never touch the heap, for real-time use.
+///
+/// `LEN` has to be even, so every block hands each branch `LEN / 2`
+/// coefficients. That count is the `HALF` parameter of the methods, which
+/// the array types in a call fix; stable Rust cannot compute it from `LEN`.
+///
+/// ```
+/// use qmf::FixedBand;
+///
+/// let mut band = FixedBand::<f64, 8>::new();
+/// let (low, high): ([f64; 4], [f64; 4]) = band.analysis(&[1.; 8]);
+/// assert_eq!([0.5, 1., 1., 1.], low);
+/// let out = band.synthesis(&low, &high);
+/// assert_eq!([0., 1., 1., 1., 1., 1., 1., 1.], out);
+/// ```
+#[derive(Clone)]
+pub struct FixedBand<T, const LEN: usize, const TAPS: usize = 2>
+where
+    T: Float,
+{
+    band: Band<T, FirFilter<T, TAPS>>,
+}
+
+impl<T, const LEN: usize> FixedBand<T, LEN>
+where
+    T: Float,
+{
+    /// A Haar band, as [`Band::new`].
+    pub fn new() -> Self {
+        let haar = FilterSet::<T>::haar();
+        let taps = |taps: &[T]| FirFilter::from_taps(<[T; 2]>::try_from(taps).unwrap());
+        Self::from_kernels(
+            taps(haar.analysis_low()),
+            taps(haar.analysis_high()),
+            taps(haar.synthesis_low()),
+            taps(haar.synthesis_high()),
+        )
+    }
+}
+
+impl<T, const LEN: usize, const TAPS: usize> FixedBand<T, LEN, TAPS>
+where
+    T: Float,
+{
+    pub fn from_kernels(
+        in_lowpass_filter: FirFilter<T, TAPS>,
+        in_highpass_filter: FirFilter<T, TAPS>,
+        out_lowpass_filter: FirFilter<T, TAPS>,
+        out_highpass_filter: FirFilter<T, TAPS>,
+    ) -> Self {
+        Self {
+            band: Band::from_kernels(
+                in_lowpass_filter,
+                in_highpass_filter,
+                out_lowpass_filter,
+                out_highpass_filter,
+            ),
+        }
+    }
+
+    /// See [`Band::analysis`].
+    pub fn analysis<const HALF: usize>(&mut self, xs: &[T; LEN]) -> ([T; HALF], [T; HALF]) {
+        const { assert!(2 * HALF == LEN, "each branch gets LEN / 2 samples") };
+        let mut low = [T::zero(); HALF];
+        let mut high = [T::zero(); HALF];
+        self.band.analysis_into(xs, &mut low, &mut high);
+        (low, high)
+    }
+
+    /// See [`Band::synthesis`].
+    pub fn synthesis<const HALF: usize>(&mut self, low: &[T; HALF], high: &[T; HALF]) -> [T; LEN] {
+        const { assert!(2 * HALF == LEN, "each branch gets LEN / 2 samples") };
+        let mut out = [T::zero(); LEN];
+        self.band.synthesis(low, high, &mut out);
+        out
+    }
+
+    pub fn reset(&mut self) {
+        self.band.reset()
+    }
+
+    /// See [`Band::delay`](QmfBand::delay).
+    pub fn delay(&self) -> usize {
+        self.band.delay
+    }
+}
+
+impl<T, const LEN: usize> Default for FixedBand<T, LEN>
+where
+    T: Float,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// [`Bands`] fed with chunks of any length, which only ever hands the closure
+/// whole groups of `2^N` input samples: every call of the closure on band `k`
+/// sees a whole number of its coefficients, lined up the same way whatever
+/// the chunk sizes.
+///
+/// Up to `2^N - 1` trailing samples of each chunk are held back for the next
+/// one, on top of the [`delay`](Self::delay) of the bank.
+pub struct StreamingBands<T, const N: usize, B = Band<T>>
+where
+    T: Float,
+    B: QmfBand<T>,
+{
+    bands: Bands<T, N, B>,
+    // input not yet filling a whole group
+    pending: Vec<T>,
+    output: Vec<T>,
+}
+
+impl<T, const N: usize> StreamingBands<T, N>
+where
+    T: Float,
+{
+    pub fn new() -> Self {
+        Self::from_bands(Bands::new())
+    }
+}
+
+impl<T, const N: usize, B> StreamingBands<T, N, B>
+where
+    T: Float,
+    B: QmfBand<T>,
+{
+    pub fn from_bands(bands: Bands<T, N, B>) -> Self {
+        Self {
+            bands,
+            pending: Vec::with_capacity(1 << N),
+            output: Vec::new(),
+        }
+    }
+
+    /// Appends `chunk` to the stream and runs every whole group of `2^N`
+    /// samples through [`Bands::process`]. Returns the output for those, a
+    /// multiple of `2^N` samples long and possibly empty.
+    pub fn push<F>(&mut self, chunk: &[T], closure: F) -> &[T]
+    where
+        F: FnMut(&mut [T], usize),
+    {
+        self.pending.extend_from_slice(chunk);
+        let ready = self.pending.len() - self.pending.len() % (1 << N);
+        self.output.clear();
+        self.output.extend(self.pending.drain(..ready));
+        self.bands.process(&mut self.output, closure);
+        &self.output
+    }
+
+    /// Samples of the last chunks waiting for their group to fill up.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Drops the pending samples and clears the bank.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.bands.reset();
+    }
+
+    /// Samples the output of the bank lags behind its input; the pending
+    /// samples come on top.
+    pub fn delay(&self) -> usize {
+        self.bands.delay()
+    }
+}
+
+impl<T, const N: usize> Default for StreamingBands<T, N>
+where
+    T: Float,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A filter bank whose depth is chosen at runtime.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T: serde::Serialize, B: serde::Serialize, B::Compensator: serde::Serialize",
+        deserialize = "T: serde::Deserialize<'de>, B: serde::Deserialize<'de>, B::Compensator: serde::Deserialize<'de>"
+    ))
+)]
+pub struct DynBands<T, B = Band<T>>
+where
+    T: Float,
+    B: QmfBand<T>,
+{
+    bands: Vec<B>,
+    compensators: Vec<Vec<B::Compensator>>,
+}
+
+impl<T> DynBands<T>
+where
+    T: Float,
+{
+    pub fn new(depth: usize) -> Self {
+        Self::from_bands((0..depth).map(|_| Band::new()).collect())
+    }
+
+    pub fn with_normalization(depth: usize, normalization: Normalization) -> Self {
+        Self::from_bands(
+            (0..depth)
+                .map(|_| Band::with_normalization(normalization))
+                .collect(),
+        )
+    }
+
+    pub fn with_wavelet(depth: usize, wavelet: Wavelet) -> Result<Self, QmfError> {
+        Ok(Self::from_filter_set(depth, &wavelet.filter_set()?))
+    }
+
+    /// A filter bank running the same filters at every level.
+    pub fn from_filter_set(depth: usize, filters: &FilterSet<T>) -> Self {
+        Self::from_bands((0..depth).map(|_| Band::from_filter_set(filters)).collect())
+    }
+
+    pub fn filter_sets(&self) -> Vec<FilterSet<T>> {
+        self.bands.iter().map(Band::filter_set).collect()
+    }
+
+    pub fn normalize_reconstruction(&mut self) -> Result<(), QmfError> {
+        self.bands
+            .iter_mut()
+            .try_for_each(Band::normalize_reconstruction)
+    }
+
+    pub fn set_filters(&mut self, filters: &FilterSet<T>, ramp_samples: usize) {
+        for band in self.bands.iter_mut() {
+            band.set_filters(filters, ramp_samples);
+        }
+    }
+
+    /// See [`Bands::is_aligned`].
+    pub fn is_aligned(&self) -> bool {
+        self.bands.iter().all(Band::is_aligned)
+    }
+}
+
+impl<T, B> DynBands<T, B>
+where
+    T: Float,
+    B: QmfBand<T>,
+{
+    /// A filter bank from its levels, outermost first.
+    pub fn from_bands(bands: Vec<B>) -> Self {
+        let compensators = (0..bands.len())
+            .map(|level| compensators(&bands[level + 1..]))
+            .collect();
+        Self {
+            bands,
+            compensators,
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.bands.len()
+    }
+
+    /// See [`Bands::process`]. With a depth of zero the closure sees the
+    /// whole buffer as band 0.
+    pub fn process<F>(&mut self, buffer: &mut [T], mut closure: F)
+    where
+        F: FnMut(&mut [T], usize),
+    {
+        process_levels(
+            &mut self.bands,
+            &mut self.compensators,
+            buffer,
+            &mut closure,
+        )
+    }
+
+    /// See [`Bands::process_in`].
+    pub fn process_in<F>(&mut self, buffer: &mut [T], scratch: &mut [T], mut closure: F)
+    where
+        F: FnMut(&mut [T], usize),
+    {
+        assert!(
+            scratch.len() >= scratch_len(self.depth(), buffer.len()),
+            "scratch buffer too short"
+        );
+        process_levels_in(
+            &mut self.bands,
+            &mut self.compensators,
+            0,
+            buffer,
+            scratch,
+            &mut closure,
+        )
+    }
+
+    pub fn scratch_len(&self, buffer_len: usize) -> usize {
+        scratch_len(self.depth(), buffer_len)
+    }
+
+    /// See [`Bands::process_with_gains`]; takes `depth() + 1` gains.
+    pub fn process_with_gains(&mut self, buffer: &mut [T], gains: &[T]) {
+        assert_eq!(self.depth() + 1, gains.len(), "need one gain per subband");
+        self.process(buffer, |band, level| {
+            band.iter_mut().for_each(|x| *x = *x * gains[level]);
+        })
+    }
+
+    /// See [`Bands::denoise_auto`].
+    pub fn denoise_auto(&mut self, buffer: &mut [T]) {
+        denoise_auto(self.analyze(buffer), buffer, |c, out| {
+            self.synthesize(c, out)
+        })
+    }
+
+    /// See [`Bands::denoise`].
+    pub fn denoise(&mut self, buffer: &mut [T], lambda: T) {
+        let depth = self.depth();
+        self.process(buffer, |band, level| {
+            if level < depth {
+                band.iter_mut()
+                    .for_each(|x| *x = soft_threshold(*x, lambda));
+            }
+        })
+    }
+
+    pub fn reset(&mut self) {
+        reset_levels(&mut self.bands, &mut self.compensators)
+    }
+
+    pub fn delay(&self) -> usize {
+        tree_delay(&self.bands)
+    }
+
+    /// See [`Bands::group_delay`].
+    pub fn group_delay(&self) -> Option<T> {
+        tree_group_delay(&self.bands)
+    }
+
+    /// See [`Bands::group_delay_at`].
+    pub fn group_delay_at(&self, omega: T) -> T {
+        tree_group_delay_at(&self.bands, omega)
+    }
+
+    /// See [`Bands::reconstruction_error`].
+    pub fn reconstruction_error(&mut self, buffer: &[T]) -> T {
+        self.reset();
+        let mut data = buffer.to_vec();
+        self.process(&mut data, |_d, _c| {});
+        self.reset();
+        max_error(buffer, &data[self.delay().min(data.len())..])
+    }
+
+    /// See [`Bands::subband_lengths`].
+    pub fn subband_lengths(&self, input_len: usize) -> (Vec<usize>, usize) {
+        let mut len = input_len;
+        let details = (0..self.bands.len())
+            .map(|_| {
+                len = len.div_ceil(2);
+                len
+            })
+            .collect();
+        (details, len)
+    }
+
+    pub fn analyze(&mut self, buffer: &[T]) -> WaveletCoeffs<T> {
+        analyze_levels(&mut self.bands, buffer)
+    }
+
+    /// See [`Bands::band_energies`].
+    pub fn band_energies(&mut self, buffer: &[T]) -> Vec<T> {
+        band_energies(&self.analyze(buffer))
+    }
+
+    pub fn synthesize(&mut self, coeffs: &WaveletCoeffs<T>, out: &mut [T]) {
+        synthesize_levels(&mut self.bands, &mut self.compensators, coeffs.clone(), out)
+    }
+
+    pub fn compress(&mut self, buffer: &[T], steps: &[T]) -> QuantizedCoeffs<T> {
+        quantize(&self.analyze(buffer), steps)
+    }
+
+    pub fn decompress(&mut self, quantized: &QuantizedCoeffs<T>, out: &mut [T]) {
+        self.synthesize(&dequantize(quantized), out)
+    }
+}
+
+// the round trips of `inner`, each seen from the level just above them
+fn compensators<T, B: QmfBand<T>>(inner: &[B]) -> Vec<B::Compensator> {
+    inner
+        .iter()
+        .enumerate()
+        .map(|(depth, band)| band.compensator(1 << depth))
+        .collect()
+}
+
+fn process_levels<T, B, F>(
+    bands: &mut [B],
+    compensators: &mut [Vec<B::Compensator>],
+    buffer: &mut [T],
+    closure: &mut F,
+) where
+    T: Float,
+    B: QmfBand<T>,
+    F: FnMut(&mut [T], usize),
+{
+    let mut coeffs = analyze_levels(bands, buffer);
+    closure(coeffs.approximation.as_mut_slice(), bands.len());
+    for (level, details) in coeffs.details.iter_mut().enumerate().rev() {
+        closure(details.as_mut_slice(), level);
+    }
+    synthesize_levels(bands, compensators, coeffs, buffer);
+}
+
+// splits `signal` at `level` and recurses into its low half, writing the
+// reconstruction back over it
+fn process_levels_in<T, B, F>(
+    bands: &mut [B],
+    compensators: &mut [Vec<B::Compensator>],
+    level: usize,
+    signal: &mut [T],
+    scratch: &mut [T],
+    closure: &mut F,
+) where
+    T: Float,
+    B: QmfBand<T>,
+    F: FnMut(&mut [T], usize),
+{
+    let Some((band, inner)) = bands.split_first_mut() else {
+        closure(signal, level);
+        return;
+    };
+    let room = signal.len() / 2 + 1;
+    let (low, scratch) = scratch.split_at_mut(room);
+    let (high, scratch) = scratch.split_at_mut(room);
+    let n = band.analysis_into(signal, low, high);
+    let (low, high) = (&mut low[..n], &mut high[..n]);
+
+    process_levels_in(
+        inner,
+        &mut compensators[1..],
+        level + 1,
+        low,
+        scratch,
+        closure,
+    );
+    closure(high, level);
+    for compensator in compensators[0].iter_mut() {
+        for h in high.iter_mut() {
+            *h = compensator.consume(*h);
+        }
+    }
+    band.synthesis(low, high, signal);
+}
+
+fn scratch_len(depth: usize, buffer_len: usize) -> usize {
+    let mut len = buffer_len;
+    (0..depth)
+        .map(|_| {
+            len = len / 2 + 1;
+            2 * len
+        })
+        .sum()
+}
+
+fn analyze_levels<T, B>(bands: &mut [B], buffer: &[T]) -> WaveletCoeffs<T>
+where
+    T: Float,
+    B: QmfBand<T>,
+{
+    let mut details = Vec::with_capacity(bands.len());
+    let mut approximation = Vec::from(buffer);
+    for band in bands.iter_mut() {
+        let (low, high) = band.analysis(&approximation);
+        details.push(high);
+        approximation = low;
+    }
+    WaveletCoeffs {
+        details,
+        approximation,
+    }
+}
+
+fn synthesize_levels<T, B>(
+    bands: &mut [B],
+    compensators: &mut [Vec<B::Compensator>],
+    coeffs: WaveletCoeffs<T>,
+    out: &mut [T],
+) where
+    T: Float,
+    B: QmfBand<T>,
+{
+    assert_eq!(
+        bands.len(),
+        coeffs.details.len(),
+        "coefficients are for a different depth"
+    );
+    // each level rebuilds as many samples as the level above split
+    let mut lens: Vec<usize> = coeffs.details.iter().map(Vec::len).collect();
+    lens.insert(0, out.len());
+
+    let mut low = coeffs.approximation;
+    for (level, mut high) in coeffs.details.into_iter().enumerate().rev() {
+        for compensator in compensators[level].iter_mut() {
+            for h in high.iter_mut() {
+                *h = compensator.consume(*h);
+            }
+        }
+        if level == 0 {
+            bands[0].synthesis(&low, &high, out);
+            return;
+        }
+        let mut merged = alloc::vec![T::zero(); lens[level]];
+        bands[level].synthesis(&low, &high, &mut merged);
+        low = merged;
+    }
+    // no levels at all
+    for (o, &x) in out.iter_mut().zip(low.iter()) {
+        *o = x;
+    }
+}
+
+// the finest band has to be seen before any band can be thresholded, so this
+// runs analysis and synthesis separately instead of through `process`
+fn denoise_auto<T: Float>(
+    mut coeffs: WaveletCoeffs<T>,
+    buffer: &mut [T],
+    synthesize: impl FnOnce(&WaveletCoeffs<T>, &mut [T]),
+) {
+    let lambda = coeffs
+        .details
+        .first()
+        .map_or(T::zero(), |finest| universal_threshold(finest));
+    for d in coeffs.details.iter_mut().flatten() {
+        *d = soft_threshold(*d, lambda);
+    }
+    synthesize(&coeffs, buffer)
+}
+
+fn max_error<T: Float>(input: &[T], delayed: &[T]) -> T {
+    input
+        .iter()
+        .zip(delayed)
+        .fold(T::zero(), |max, (&x, &y)| max.max((y - x).abs()))
+}
+
+fn band_energies<T: Float>(coeffs: &WaveletCoeffs<T>) -> Vec<T> {
+    let energy = |band: &[T]| band.iter().fold(T::zero(), |sum, &x| sum + x * x);
+    coeffs
+        .details
+        .iter()
+        .map(|band| energy(band))
+        .chain([energy(&coeffs.approximation)])
+        .collect()
+}
+
+fn reset_levels<T, B: QmfBand<T>>(bands: &mut [B], compensators: &mut [Vec<B::Compensator>]) {
+    for band in bands.iter_mut() {
+        band.reset();
+    }
+    for compensator in compensators.iter_mut().flatten() {
+        compensator.reset();
+    }
+}
+
+fn tree_delay<T, B: QmfBand<T>>(bands: &[B]) -> usize {
+    bands
+        .iter()
+        .rev()
+        .fold(0, |inner, band| 2 * inner + band.delay())
+}
+
+fn tree_group_delay<T: Float, B: QmfBand<T>>(bands: &[B]) -> Option<T> {
+    let two = T::from(2).unwrap();
+    bands.iter().rev().try_fold(T::zero(), |inner, band| {
+        Some(two * inner + band.group_delay()?)
+    })
+}
+
+fn tree_group_delay_at<T: Float, B: QmfBand<T>>(bands: &[B], omega: T) -> T {
+    let two = T::from(2).unwrap();
+    let (delay, _) = bands
+        .iter()
+        .fold((T::zero(), T::one()), |(delay, scale), band| {
+            (
+                delay + scale * band.group_delay_at(scale * omega),
+                two * scale,
+            )
+        });
+    delay
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+    use core::{cell::RefCell, f64::consts::TAU};
+
+    use super::{
+        Band, Bands, DynBands, FixedBand, FixedHaarBand, MultiBands, QmfBand, StreamingBands,
+    };
+    use crate::{
+        allpass::AllpassQmf,
+        coeffs::WaveletCoeffs,
+        error::QmfError,
+        fir::{DynFirFilter, FilterKernel, FirFilter},
+        haar::HaarFilter,
+        sampling::{DownSampler, UpSampler},
+        test_util::{allocations, gaussian_noise, white_noise},
+        wavelet::{FilterSet, Normalization, Wavelet},
+    };
+
+    #[test]
+    fn test_bands_reconstruct() {
+        let mut bands: Bands<f64, 3> = Bands::new();
+
+        let mut in_data = vec![1.; 128];
+        bands.process(in_data.as_mut_slice(), |_d, _c| {});
+        assert_eq!(vec![1.; 128 - bands.delay()], in_data[bands.delay()..]);
+
+        let mut in_data = vec![1.; 128];
+        bands.process(in_data.as_mut_slice(), |_d, _c| {});
+        assert_eq!(vec![1.; 128], in_data);
+    }
+
+    #[test]
+    fn test_daubechies4_reconstruct() {
+        let mut bands: Bands<f64, 3> = Bands::daubechies4();
+        assert_eq!(21, bands.delay());
+
+        let mut in_data = vec![1.; 128];
+        bands.process(in_data.as_mut_slice(), |_d, _c| {});
+        for x in &in_data[bands.delay()..] {
+            assert!((x - 1.).abs() < 1e-12, "{x}");
+        }
+
+        let mut in_data = vec![1.; 128];
+        bands.process(in_data.as_mut_slice(), |_d, _c| {});
+        for x in &in_data {
+            assert!((x - 1.).abs() < 1e-12, "{x}");
+        }
+    }
+
+    #[test]
+    fn test_bands_reconstruct_arbitrary() {
+        let input: Vec<f64> = (0..256).map(|i| ((i * 37 % 23) as f64) - 11.).collect();
+        for mut bands in [Bands::<f64, 3>::new(), Bands::<f64, 3>::daubechies4()] {
+            let mut data = input.clone();
+            bands.process(data.as_mut_slice(), |_d, _c| {});
+            let delay = bands.delay();
+            for (y, x) in data[delay..].iter().zip(input.iter()) {
+                assert!((y - x).abs() < 1e-10, "{y} != {x}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_daubechies_family_reconstruct() {
+        for order in 2..=10 {
+            let filters = Wavelet::daubechies(order).unwrap();
+            let mut bands: Bands<f64, 3> = Bands::from_filter_set(&filters);
+            let delay = bands.delay();
+            assert_eq!((2 * order - 1) * 7, delay);
+
+            let mut constant = vec![1.; 512];
+            bands.process(constant.as_mut_slice(), |_d, _c| {});
+            for y in &constant[delay..] {
+                assert!((y - 1.).abs() < 1e-10, "db{order}: {y}");
+            }
+
+            bands.reset();
+            let ramp: Vec<f64> = (0..512).map(|i| i as f64 / 8.).collect();
+            let mut data = ramp.clone();
+            bands.process(data.as_mut_slice(), |_d, _c| {});
+            for (y, x) in data[delay..].iter().zip(ramp.iter()) {
+                assert!((y - x).abs() < 1e-10, "db{order}: {y} != {x}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_symlet_reconstruct() {
+        // single precision round trip of a unit-amplitude signal: the error
+        // stays within a few f32 epsilons per level, so 1e-5 leaves headroom
+        const TOLERANCE: f32 = 1e-5;
+
+        let input: Vec<f32> = (0..512)
+            .map(|i| (i as f32 * 0.05).sin() * 0.5 + ((i * 7 % 5) as f32 - 2.) * 0.2)
+            .collect();
+        for order in 2..=8 {
+            let mut bands = Bands::<f32, 4>::with_wavelet(Wavelet::Symlet(order)).unwrap();
+            let delay = bands.delay();
+            assert_eq!((2 * order - 1) * 15, delay);
+
+            let mut data = input.clone();
+            bands.process(data.as_mut_slice(), |_d, _c| {});
+            for (y, x) in data[delay..].iter().zip(input.iter()) {
+                assert!((y - x).abs() < TOLERANCE, "sym{order}: {y} != {x}");
+            }
+        }
+        assert!(Bands::<f32, 4>::with_wavelet(Wavelet::Symlet(1)).is_err());
+    }
+
+    #[test]
+    fn test_coiflet_reconstruct_white_noise() {
+        let input = white_noise(2048, 5);
+        for order in 1..=5 {
+            let mut bands = Bands::<f64, 3>::with_wavelet(Wavelet::Coiflet(order)).unwrap();
+            let delay = bands.delay();
+
+            let mut data = input.clone();
+            bands.process(data.as_mut_slice(), |_d, _c| {});
+            let error: f64 = data[delay..]
+                .iter()
+                .zip(input.iter())
+                .map(|(y, x)| (y - x).powi(2))
+                .sum();
+            let rms = (error / (data.len() - delay) as f64).sqrt();
+            assert!(rms < 1e-9, "coif{order}: {rms}");
+        }
+    }
+
+    #[test]
+    fn test_legall53_reconstruct() {
+        let mut bands = Bands::<f64, 4>::with_wavelet(Wavelet::LeGall53).unwrap();
+        // the 5/3 pair delays by three samples per level
+        assert_eq!(3 * 15, bands.delay());
+
+        let input: Vec<f64> = white_noise(1024, 53).iter().map(|x| x * 1000.).collect();
+        let mut data = input.clone();
+        bands.process(data.as_mut_slice(), |_d, _c| {});
+        let delay = bands.delay();
+        for (y, x) in data[delay..].iter().zip(input.iter()) {
+            assert!((y - x).abs() < 1e-9, "{y} != {x}");
+        }
+    }
+
+    #[test]
+    fn test_cdf97_sine_snr() {
+        let mut bands = Bands::<f64, 3>::with_wavelet(Wavelet::Cdf97).unwrap();
+        // the 9/7 pair delays by seven samples per level
+        assert_eq!(7 * 7, bands.delay());
+
+        let input: Vec<f64> = (0..4800)
+            .map(|n| (2. * core::f64::consts::PI * 1000. * n as f64 / 48000.).sin())
+            .collect();
+        let mut data = input.clone();
+        bands.process(data.as_mut_slice(), |_d, _c| {});
+
+        let delay = bands.delay();
+        let (signal, noise) = data[delay..]
+            .iter()
+            .zip(input.iter())
+            .fold((0., 0.), |(s, n), (y, x)| {
+                (s + x * x, n + (y - x) * (y - x))
+            });
+        let snr = 10. * (signal / noise).log10();
+        assert!(snr > 120., "SNR {snr} dB");
+    }
+
+    #[test]
+    fn test_with_filters_matches_haar() {
+        let input: Vec<f64> = white_noise(512, 16);
+        let mut haar = Bands::<f64, 3>::new();
+        let mut custom =
+            Bands::<f64, 3>::with_filters(&[0.5, 0.5], &[-0.5, 0.5], &[1., 1.], &[1., -1.])
+                .unwrap();
+        assert_eq!(haar.delay(), custom.delay());
+
+        let mut expected = input.clone();
+        haar.process(expected.as_mut_slice(), |_d, _c| {});
+        let mut actual = input.clone();
+        custom.process(actual.as_mut_slice(), |_d, _c| {});
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_with_filters_rejects_empty() {
+        assert_eq!(
+            Some(QmfError::EmptyFilter),
+            Bands::<f64, 2>::with_filters(&[0.5, 0.5], &[], &[1., 1.], &[1., -1.]).err()
+        );
+        assert_eq!(
+            Some(QmfError::EmptyFilter),
+            Band::<f64>::with_filters(&[], &[-0.5, 0.5], &[1., 1.], &[1., -1.]).err()
+        );
+    }
+
+    #[test]
+    fn test_reset_matches_fresh() {
+        // an odd length leaves the samplers mid-phase
+        let first = white_noise(301, 18);
+        let second = white_noise(256, 81);
+
+        let mut fresh = Bands::<f64, 3>::daubechies4();
+        let mut expected = second.clone();
+        fresh.process(expected.as_mut_slice(), |_d, _c| {});
+
+        let mut reused = Bands::<f64, 3>::daubechies4();
+        let mut data = first.clone();
+        reused.process(data.as_mut_slice(), |_d, _c| {});
+        reused.reset();
+        let mut actual = second.clone();
+        reused.process(actual.as_mut_slice(), |_d, _c| {});
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_band_with_fixed_kernels() {
+        let mut fixed: Band<f64, HaarFilter<f64>> = Band::from_kernels(
+            HaarFilter::new(0.5, 0.5),
+            HaarFilter::new(-0.5, 0.5),
+            HaarFilter::new(1, 1),
+            HaarFilter::new(1, -1),
+        );
+        let mut dynamic: Band<f64> = Band::new();
+        assert_eq!(dynamic.delay, fixed.delay);
+
+        let xs = white_noise(64, 21);
+        let (low, high) = fixed.analysis(&xs);
+        assert_eq!(dynamic.analysis(&xs), (low.clone(), high.clone()));
+        let (mut expected, mut actual) = (vec![0.; 64], vec![0.; 64]);
+        dynamic.synthesis(&low, &high, &mut expected);
+        fixed.synthesis(&low, &high, &mut actual);
+        assert_eq!(expected, actual);
+    }
+
+    /// Passes samples through unchanged, logging which filter saw them.
+    struct Recorder {
+        name: &'static str,
+        log: Rc<RefCell<Vec<(&'static str, f64)>>>,
+    }
+
+    impl FilterKernel<f64> for Recorder {
+        fn consume(&mut self, x: f64) -> f64 {
+            self.log.borrow_mut().push((self.name, x));
+            x
+        }
+
+        fn reset(&mut self) {
+            self.log.borrow_mut().push((self.name, f64::NAN));
+        }
+
+        fn len(&self) -> usize {
+            1
+        }
+    }
+
+    #[test]
+    fn test_band_kernel_call_order() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let kernel = |name| Recorder {
+            name,
+            log: log.clone(),
+        };
+        let mut band = Band::from_kernels(
+            kernel("analysis low"),
+            kernel("analysis high"),
+            kernel("synthesis low"),
+            kernel("synthesis high"),
+        );
+        band.reset();
+        log.borrow_mut().clear();
+
+        let (low, high) = band.analysis(&[1., 2., 3., 4.]);
+        assert_eq!((vec![1., 3.], vec![1., 3.]), (low.clone(), high.clone()));
+        let mut out = [0.; 4];
+        band.synthesis(&low, &high, &mut out);
+        assert_eq!([2., 0., 6., 0.], out);
+
+        let expected = [
+            ("analysis low", 1.),
+            ("analysis low", 2.),
+            ("analysis low", 3.),
+            ("analysis low", 4.),
+            ("analysis high", 1.),
+            ("analysis high", 2.),
+            ("analysis high", 3.),
+            ("analysis high", 4.),
+            ("synthesis low", 1.),
+            ("synthesis high", 1.),
+            ("synthesis low", 0.),
+            ("synthesis high", 0.),
+            ("synthesis low", 3.),
+            ("synthesis high", 3.),
+            ("synthesis low", 0.),
+            ("synthesis high", 0.),
+        ];
+        assert_eq!(expected.as_slice(), log.borrow().as_slice());
+    }
+
+    #[test]
+    fn test_dyn_bands_reconstruct() {
+        let mut fixed = Bands::<f64, 4>::daubechies4();
+        let mut dynamic = DynBands::with_wavelet(4, Wavelet::Daubechies(2)).unwrap();
+        assert_eq!(4, dynamic.depth());
+        assert_eq!(fixed.delay(), dynamic.delay());
+
+        let input = white_noise(1024, 25);
+        let mut expected = input.clone();
+        fixed.process(expected.as_mut_slice(), |_d, _c| {});
+        let mut actual = input.clone();
+        let mut levels = Vec::new();
+        dynamic.process(actual.as_mut_slice(), |_d, c| levels.push(c));
+        assert_eq!(expected, actual);
+        assert_eq!(vec![4, 3, 2, 1, 0], levels);
+
+        let mut haar = DynBands::<f64>::new(4);
+        assert_eq!(15, haar.delay());
+        let mut data = input.clone();
+        haar.process(data.as_mut_slice(), |_d, _c| {});
+        for (y, x) in data[15..].iter().zip(input.iter()) {
+            assert!((y - x).abs() < 1e-12, "{y} != {x}");
+        }
+    }
+
+    #[test]
+    fn test_analyze_synthesize() {
+        let input = white_noise(256, 28);
+        let mut bands = Bands::<f64, 3>::daubechies4();
+        let coeffs = bands.analyze(&input);
+        assert_eq!(3, coeffs.details.len());
+        assert_eq!(
+            vec![128, 64, 32],
+            coeffs.details.iter().map(Vec::len).collect::<Vec<_>>()
+        );
+        assert_eq!(32, coeffs.approximation.len());
+
+        let mut output = vec![0.; 256];
+        bands.synthesize(&coeffs, &mut output);
+        let delay = bands.delay();
+        for (y, x) in output[delay..].iter().zip(input.iter()) {
+            assert!((y - x).abs() < 1e-12, "{y} != {x}");
+        }
+
+        // one coefficient of the middle level only touches the samples its
+        // synthesis filters reach
+        let mut modified = Bands::<f64, 3>::daubechies4();
+        let mut changed = modified.analyze(&input);
+        changed.details[1][20] += 1.;
+        let mut altered = vec![0.; 256];
+        modified.synthesize(&changed, &mut altered);
+        let touched: Vec<usize> = (0..256)
+            .filter(|&n| (altered[n] - output[n]).abs() > 1e-12)
+            .collect();
+        assert!(!touched.is_empty());
+        assert!(
+            touched.iter().all(|&n| (80..80 + 16 + 2 * 21).contains(&n)),
+            "{touched:?}"
+        );
+    }
+
+    #[test]
+    fn test_process_with_gains() {
+        let input = white_noise(512, 30);
+        let mut unity = Bands::<f64, 3>::daubechies4();
+        let mut data = input.clone();
+        unity.process_with_gains(data.as_mut_slice(), &[1.; 4]);
+        let delay = unity.delay();
+        for (y, x) in data[delay..].iter().zip(input.iter()) {
+            assert!((y - x).abs() < 1e-12, "{y} != {x}");
+        }
+
+        // a DC offset lives entirely in the approximation
+        let mut highpass = Bands::<f64, 3>::new();
+        let mut data: Vec<f64> = input.iter().map(|x| x + 5.).collect();
+        highpass.process_with_gains(data.as_mut_slice(), &[1., 1., 1., 0.]);
+        let tail = &data[64..];
+        let mean = tail.iter().sum::<f64>() / tail.len() as f64;
+        assert!(mean.abs() < 0.1, "mean {mean}");
+
+        let mut dynamic = DynBands::<f64>::new(2);
+        let mut data = vec![5.; 64];
+        dynamic.process_with_gains(data.as_mut_slice(), &[1., 1., 0.]);
+        assert!(data[8..].iter().all(|y| y.abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_denoise_step() {
+        // a step with sparse impulses riding on it
+        let len = 512;
+        let clean: Vec<f64> = (0..len).map(|n| if n < 201 { 0. } else { 1. }).collect();
+        let noisy: Vec<f64> = clean
+            .iter()
+            .enumerate()
+            .map(|(n, x)| match n % 23 {
+                0 => x + 0.2,
+                11 => x - 0.2,
+                _ => *x,
+            })
+            .collect();
+
+        let error = |ys: &[f64], delay: usize| -> f64 {
+            ys[delay..]
+                .iter()
+                .zip(clean.iter())
+                .map(|(y, x)| (y - x) * (y - x))
+                .sum()
+        };
+
+        let mut bands = Bands::<f64, 3>::new();
+        let mut data = noisy.clone();
+        bands.denoise(data.as_mut_slice(), 0.15);
+        let delay = bands.delay();
+        assert!(error(&data, delay) < error(&noisy, 0) / 4.);
+
+        // a threshold of zero changes nothing
+        let mut dynamic = DynBands::<f64>::new(3);
+        let mut data = noisy.clone();
+        dynamic.denoise(data.as_mut_slice(), 0.);
+        for (y, x) in data[delay..].iter().zip(noisy.iter()) {
+            assert!((y - x).abs() < 1e-12, "{y} != {x}");
+        }
+    }
+
+    #[test]
+    fn test_band_energies() {
+        let mut bands = Bands::<f64, 3>::daubechies4();
+        // let the start-up transient pass first
+        bands.band_energies(&[1.; 64]);
+        let energies = bands.band_energies(&[1.; 256]);
+        assert_eq!(4, energies.len());
+        assert!((energies[3] - 32.).abs() < 1e-9, "{}", energies[3]);
+        assert!(energies[..3].iter().all(|e| *e < 1e-20), "{energies:?}");
+
+        let mut dynamic = DynBands::<f64>::new(2);
+        dynamic.band_energies(&[1.; 8]);
+        assert_eq!(vec![0., 0., 16.], dynamic.band_energies(&[1.; 64]));
+    }
+
+    fn check_haar_delay<const N: usize>() {
+        let mut bands = Bands::<f64, N>::new();
+        let delay = bands.delay();
+        assert_eq!((1 << N) - 1, delay);
+
+        let mut data = vec![1.; 256];
+        bands.process(data.as_mut_slice(), |_d, _c| {});
+        assert!(data[..delay].iter().all(|y| *y < 1.), "N = {N}: {data:?}");
+        assert!(data[delay..].iter().all(|y| *y == 1.), "N = {N}: {data:?}");
+    }
+
+    #[test]
+    fn test_haar_delay_by_depth() {
+        check_haar_delay::<1>();
+        check_haar_delay::<2>();
+        check_haar_delay::<3>();
+        check_haar_delay::<4>();
+        check_haar_delay::<5>();
+    }
+
+    fn check_impulse_peak<const N: usize>() {
+        for mut bands in [Bands::<f64, N>::new(), Bands::daubechies4()] {
+            let delay = bands.group_delay().unwrap();
+            assert_eq!(bands.delay() as f64, delay);
+            assert_eq!(delay, bands.group_delay_at(1.));
+
+            let mut data = vec![0.; 1024];
+            data[100] = 1.;
+            bands.process(data.as_mut_slice(), |_d, _c| {});
+            let peak = data
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+                .unwrap()
+                .0;
+            assert_eq!(100 + delay as usize, peak, "N = {N}");
+            assert!((data[peak] - 1.).abs() < 1e-12);
+        }
+
+        // the allpass bank disperses an impulse, so compare the phase slope
+        // of its response at a test frequency instead
+        let mut bands = Bands::<f64, N, AllpassQmf<f64>>::allpass();
+        assert_eq!(None, bands.group_delay());
+        let omega = 0.3;
+        let delta = 1e-5;
+        let step =
+            measured_phase(&mut bands, omega + delta) - measured_phase(&mut bands, omega - delta);
+        let step = step - TAU * (step / TAU).round();
+        let (measured, expected) = (-step / (2. * delta), bands.group_delay_at(omega));
+        assert!(
+            (measured - expected).abs() < 1e-6 * expected,
+            "N = {N}: {measured} != {expected}"
+        );
+
+        let dynamic = DynBands::from_bands(vec![AllpassQmf::<f64>::new(); N]);
+        assert_eq!(expected, dynamic.group_delay_at(omega));
+        let haar = DynBands::<f64>::new(N);
+        assert_eq!(Some(((1 << N) - 1) as f64), haar.group_delay());
+    }
+
+    // the phase of the response to `e^{jωn}`, read off the last sample of the
+    // responses to its real and imaginary part once the bank has settled
+    fn measured_phase<B: QmfBand<f64>, const N: usize>(
+        bands: &mut Bands<f64, N, B>,
+        omega: f64,
+    ) -> f64 {
+        let len = 1 << 14;
+        let mut respond = |signal: fn(f64) -> f64| {
+            bands.reset();
+            let mut data: Vec<f64> = (0..len).map(|n| signal(omega * n as f64)).collect();
+            bands.process(data.as_mut_slice(), |_d, _c| {});
+            data[len - 1]
+        };
+        let (re, im) = (respond(f64::cos), respond(f64::sin));
+        im.atan2(re) - omega * (len - 1) as f64
+    }
+
+    #[test]
+    fn test_impulse_peak_by_depth() {
+        check_impulse_peak::<1>();
+        check_impulse_peak::<2>();
+        check_impulse_peak::<3>();
+        check_impulse_peak::<4>();
+        check_impulse_peak::<5>();
+    }
+
+    #[test]
+    fn test_odd_block_lengths() {
+        for len in [100, 127] {
+            let input = white_noise(4 * len, len as u64);
+            let mut bands = Bands::<f64, 3>::daubechies4();
+            let delay = bands.delay();
+            let mut output = input.clone();
+            for block in output.chunks_mut(len) {
+                bands.process(block, |_d, _c| {});
+            }
+            for (y, x) in output[delay..].iter().zip(input.iter()) {
+                assert!((y - x).abs() < 1e-12, "{len}: {y} != {x}");
+            }
+
+            // a single block too
+            let mut bands = Bands::<f64, 3>::new();
+            let mut block = input[..len].to_vec();
+            bands.process(block.as_mut_slice(), |_d, _c| {});
+            for (y, x) in block[7..].iter().zip(input.iter()) {
+                assert!((y - x).abs() < 1e-12, "{len}: {y} != {x}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_reset_isolates_streams() {
+        let mut bands = Bands::<f64, 3>::daubechies4();
+        let mut dynamic = DynBands::<f64>::new(4);
+        let mut data = white_noise(333, 39);
+        bands.process(data.as_mut_slice(), |_d, _c| {});
+        dynamic.process(data.as_mut_slice(), |_d, _c| {});
+        bands.reset();
+        dynamic.reset();
+
+        let mut silence = vec![0.; 256];
+        bands.process(silence.as_mut_slice(), |_d, _c| {});
+        dynamic.process(silence.as_mut_slice(), |_d, _c| {});
+        assert_eq!(vec![0.; 256], silence);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_checkpoint() {
+        use crate::test_util::tokens::{from_tokens, to_tokens};
+
+        let input = white_noise(600, 42);
+        let mut uninterrupted = Bands::<f64, 3>::daubechies4();
+        let mut expected = input.clone();
+        for block in expected.chunks_mut(150) {
+            uninterrupted.process(block, |_d, _c| {});
+        }
+
+        // stop after a block that is no multiple of 2^3, leaving samplers mid-phase
+        let mut bands = Bands::<f64, 3>::daubechies4();
+        let mut actual = input.clone();
+        bands.process(&mut actual[..150], |_d, _c| {});
+        let mut restored: Bands<f64, 3> = from_tokens(&to_tokens(&bands));
+        for block in actual[150..].chunks_mut(150) {
+            restored.process(block, |_d, _c| {});
+        }
+        assert_eq!(expected, actual);
+
+        let mut haar: HaarFilter<f64> = HaarFilter::new(0.5, 0.5);
+        haar.consume(3.);
+        let mut copy: HaarFilter<f64> = from_tokens(&to_tokens(&haar));
+        assert_eq!(haar.consume(1.), copy.consume(1.));
+
+        let dynamic = DynBands::<f64>::new(2);
+        let copy: DynBands<f64> = from_tokens(&to_tokens(&dynamic));
+        assert_eq!(dynamic.delay(), copy.delay());
+    }
+
+    #[test]
+    fn test_multi_bands_stereo() {
+        let left = white_noise(300, 46);
+        let right: Vec<f64> = (0..300).map(|n| (n as f64 * 0.1).sin()).collect();
+        let mut stereo: Vec<f64> = left
+            .iter()
+            .zip(right.iter())
+            .flat_map(|(&l, &r)| [l, r])
+            .collect();
+
+        let mut bands = MultiBands::<f64, 3, 2>::default();
+        let delay = bands.delay();
+        assert_eq!(7, delay);
+        let mut seen = [0; 2];
+        for frames in stereo.chunks_mut(2 * 75) {
+            bands.process(frames, |_band, level, channel| {
+                if level == 3 {
+                    seen[channel] += 1;
+                }
+            });
+        }
+        assert_eq!([4, 4], seen);
+        for (n, frame) in stereo.chunks(2).enumerate().skip(delay) {
+            assert!((frame[0] - left[n - delay]).abs() < 1e-12);
+            assert!((frame[1] - right[n - delay]).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_process_in_matches_process() {
+        let input = white_noise(1000, 48);
+        let mut allocating = Bands::<f64, 4>::daubechies4();
+        let mut expected = input.clone();
+        let mut in_place = Bands::<f64, 4>::daubechies4();
+        let mut actual = input.clone();
+        let mut dynamic = DynBands::<f64>::new(3);
+        let mut dynamic_expected = input.clone();
+        let mut dynamic_actual = input.clone();
+        let mut dynamic_reference = DynBands::<f64>::new(3);
+
+        let mut scratch = vec![0.; in_place.scratch_len(333)];
+        assert!(scratch.len() >= dynamic.scratch_len(333));
+        let gains = [0.5, 2., 1., 0., 3.];
+        let mut start = 0;
+        for end in [1, 334, 667, 1000] {
+            allocating.process(&mut expected[start..end], |band, level| {
+                band.iter_mut().for_each(|x| *x *= gains[level]);
+            });
+            dynamic_reference.process(&mut dynamic_expected[start..end], |band, level| {
+                band.iter_mut().for_each(|x| *x *= gains[level]);
+            });
+            let ((), allocations) = allocations(|| {
+                in_place.process_in(&mut actual[start..end], &mut scratch, |band, level| {
+                    band.iter_mut().for_each(|x| *x *= gains[level]);
+                });
+                dynamic.process_in(
+                    &mut dynamic_actual[start..end],
+                    &mut scratch,
+                    |band, level| {
+                        band.iter_mut().for_each(|x| *x *= gains[level]);
+                    },
+                );
+            });
+            assert_eq!(0, allocations);
+            start = end;
+        }
+        assert_eq!(expected, actual);
+        assert_eq!(dynamic_expected, dynamic_actual);
+    }
+
+    #[test]
+    fn test_bands_from_kernels() {
+        let input = white_noise(300, 49);
+        let mut expected = input.clone();
+        Bands::<f64, 3>::new().process(expected.as_mut_slice(), |_d, _c| {});
+
+        let mut fixed = Bands::<f64, 3, Band<f64, HaarFilter<f64>>>::from_kernels(
+            HaarFilter::new(0.5, 0.5),
+            HaarFilter::new(-0.5, 0.5),
+            HaarFilter::new(1, 1),
+            HaarFilter::new(1, -1),
+        );
+        let mut actual = input.clone();
+        fixed.process(actual.as_mut_slice(), |_d, _c| {});
+        assert_eq!(expected, actual);
+
+        // doubling the analysis doubles what a single level gives back
+        let mut doubled = Bands::<f64, 1, Band<f64, HaarFilter<f64>>>::from_kernels(
+            HaarFilter::new(1, 1),
+            HaarFilter::new(-1, 1),
+            HaarFilter::new(1, 1),
+            HaarFilter::new(1, -1),
+        );
+        let mut actual = input.clone();
+        doubled.process(actual.as_mut_slice(), |_d, _c| {});
+        for (y, x) in actual[1..].iter().zip(input.iter()) {
+            assert!((y - 2. * x).abs() < 1e-12, "{y} != 2 * {x}");
+        }
+
+        // while the orthonormal scaling by √½ on both sides keeps unit gain
+        let r = core::f64::consts::FRAC_1_SQRT_2;
+        let mut orthonormal = Bands::<f64, 3, Band<f64, HaarFilter<f64>>>::from_kernels(
+            HaarFilter::new(r, r),
+            HaarFilter::new(-r, r),
+            HaarFilter::new(r, r),
+            HaarFilter::new(r, -r),
+        );
+        let mut actual = input.clone();
+        orthonormal.process(actual.as_mut_slice(), |_d, _c| {});
+        for (y, x) in actual.iter().zip(expected.iter()) {
+            assert!((y - x).abs() < 1e-12, "{y} != {x}");
+        }
+    }
+
+    #[test]
+    fn test_set_filters_ramps() {
+        let mut bands = Bands::<f64, 2>::new();
+        let mut dynamic = DynBands::<f64>::new(2);
+        let mut data = vec![1.; 400];
+        let mut copy = data.clone();
+        let (first, second) = data.split_at_mut(100);
+        bands.process(first, |_d, _c| {});
+        dynamic.process(&mut copy[..100], |_d, _c| {});
+
+        // twice the analysis gain passes DC at 2², reached within 64 samples
+        let doubled = FilterSet::new(&[1., 1.], &[-1., 1.], &[1., 1.], &[1., -1.]).unwrap();
+        bands.set_filters(&doubled, 64);
+        dynamic.set_filters(&doubled, 64);
+        bands.process(second, |_d, _c| {});
+        dynamic.process(&mut copy[100..], |_d, _c| {});
+        assert_eq!(data, copy);
+        assert!(data[3..].windows(2).all(|w| (w[1] - w[0]).abs() < 0.125));
+        assert!(data[300..].iter().all(|y| (y - 4.).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_normalization() {
+        let mut input = white_noise(256, 29);
+        // trailing silence flushes every coefficient out of the bank
+        input.extend([0.; 64]);
+        let energy: f64 = input.iter().map(|x| x * x).sum();
+        for normalization in [Normalization::Rational, Normalization::Orthonormal] {
+            let mut bands = Bands::<f64, 3>::with_normalization(normalization);
+            let mut data = input.clone();
+            bands.process(data.as_mut_slice(), |_d, _c| {});
+            for (y, x) in data[bands.delay()..].iter().zip(input.iter()) {
+                assert!((y - x).abs() < 1e-12, "{y} != {x}");
+            }
+        }
+
+        let mut bands = Bands::<f64, 3>::with_normalization(Normalization::Orthonormal);
+        let total: f64 = bands.band_energies(&input).iter().sum();
+        assert!(
+            (total - energy).abs() < 1e-9 * energy,
+            "{total} != {energy}"
+        );
+
+        let filters = Wavelet::daubechies(4)
+            .unwrap()
+            .normalized(Normalization::Orthonormal);
+        let mut dynamic = DynBands::from_filter_set(3, &filters);
+        let total: f64 = dynamic.band_energies(&input).iter().sum();
+        assert!(
+            (total - energy).abs() < 1e-9 * energy,
+            "{total} != {energy}"
+        );
+    }
+
+    #[test]
+    fn test_reconstruction_error() {
+        let input = white_noise(512, 31);
+        assert!(Bands::<f64, 1>::new().reconstruction_error(&input) < 1e-12);
+        assert!(Bands::<f64, 2>::new().reconstruction_error(&input) < 1e-12);
+        assert!(Bands::<f64, 3>::new().reconstruction_error(&input) < 1e-12);
+        assert!(Bands::<f64, 4>::new().reconstruction_error(&input) < 1e-12);
+        for depth in 1..=4 {
+            assert!(DynBands::<f64>::new(depth).reconstruction_error(&input) < 1e-12);
+        }
+
+        // a mismatched synthesis highpass leaves the details in the output
+        let mut broken =
+            Bands::<f64, 2>::with_filters(&[0.5, 0.5], &[-0.5, 0.5], &[1., 1.], &[1., 1.]).unwrap();
+        assert!(broken.reconstruction_error(&input) > 0.1);
+    }
+
+    #[test]
+    fn test_with_normalized_filters() {
+        let input = white_noise(256, 37);
+        // analysis gains off by 0.6 and 1.6
+        let (low, high) = ([0.3, 0.3], [-0.8, 0.8]);
+        let mut bands =
+            Bands::<f64, 3>::with_normalized_filters(&low, &high, &[1., 1.], &[1., -1.]).unwrap();
+        assert!(bands.reconstruction_error(&input) < 1e-12);
+
+        let mut unscaled =
+            Bands::<f64, 3>::with_filters(&low, &high, &[1., 1.], &[1., -1.]).unwrap();
+        assert!(unscaled.reconstruction_error(&input) > 0.1);
+
+        assert!(matches!(
+            Bands::<f64, 1>::with_normalized_filters(&[0.5, -0.5], &high, &[1., 1.], &[1., -1.]),
+            Err(QmfError::ZeroGain)
+        ));
+    }
+
+    fn lengths(coeffs: &WaveletCoeffs<f64>) -> (Vec<usize>, usize) {
+        let details = coeffs.details.iter().map(Vec::len).collect();
+        (details, coeffs.approximation.len())
+    }
+
+    #[test]
+    fn test_subband_lengths() {
+        const LENGTHS: ([usize; 3], usize) = Bands::<f64, 3>::subband_lengths(13);
+        assert_eq!(([7, 4, 2], 2), LENGTHS);
+
+        for len in [0, 1, 2, 3, 5, 7, 8, 9, 63, 64, 100, 257] {
+            let input = white_noise(len, 41);
+            let mut bands = Bands::<f64, 4>::daubechies4();
+            let (details, approximation) = Bands::<f64, 4>::subband_lengths(len);
+            assert_eq!(
+                (details.to_vec(), approximation),
+                lengths(&bands.analyze(&input)),
+                "{len}"
+            );
+
+            let mut dynamic = DynBands::<f64>::new(4);
+            assert_eq!(
+                dynamic.subband_lengths(len),
+                lengths(&dynamic.analyze(&input)),
+                "{len}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalize_reconstruction() {
+        let (low, high) = ([0.3, 0.3], [-0.5, 0.5]);
+        let mut bands = Bands::<f64, 3>::with_filters(&low, &high, &[1., 1.], &[1., -1.]).unwrap();
+        let mut data = vec![1.; 128];
+        bands.process(&mut data, |_d, _c| {});
+        // 0.6 per level, past a start-up transient that no longer cancels
+        assert!(data[bands.delay() + 1..]
+            .iter()
+            .all(|y| (y - 0.216).abs() < 1e-12));
+
+        bands.normalize_reconstruction().unwrap();
+        let mut data = vec![1.; 128];
+        bands.reset();
+        bands.process(&mut data, |_d, _c| {});
+        assert!(data[bands.delay()..].iter().all(|y| (y - 1.).abs() < 1e-12));
+
+        let mut dynamic = DynBands::from_filter_set(
+            3,
+            &FilterSet::new(&low, &high, &[1., 1.], &[1., -1.]).unwrap(),
+        );
+        dynamic.normalize_reconstruction().unwrap();
+        assert!(dynamic.reconstruction_error(&white_noise(256, 59)) < 1e-12);
+    }
+
+    #[test]
+    fn test_band_daubechies4() {
+        let input = white_noise(256, 61);
+        let mut bands = Bands::from_bands([Band::daubechies4(), Band::daubechies4()]);
+        assert!(bands.reconstruction_error(&input) < 1e-12);
+
+        // past the start-up transient a ramp leaves no detail with db4
+        let ramp: Vec<f64> = (0..128).map(|n| n as f64 / 16.).collect();
+        let (_, db4) = Band::daubechies4().analysis(&ramp);
+        let (_, haar) = Band::new().analysis(&ramp);
+        let energy = |details: &[f64]| details[4..].iter().map(|d| d * d).sum::<f64>();
+        assert!(energy(&db4) < 1e-20, "{}", energy(&db4));
+        assert!(energy(&haar) > 0.05, "{}", energy(&haar));
+    }
+
+    #[test]
+    fn test_filter_sets() {
+        let filters = Wavelet::daubechies(3).unwrap();
+        let bands = Bands::<f64, 2>::from_filter_set(&filters);
+        assert_eq!([filters.clone(), filters.clone()], bands.filter_sets());
+        assert_eq!(FilterSet::haar(), Band::<f64>::new().filter_set());
+
+        let dynamic = DynBands::<f64>::from_filter_set(3, &filters);
+        assert_eq!(vec![filters; 3], dynamic.filter_sets());
+    }
+
+    #[test]
+    fn test_streaming_bands() {
+        let input = white_noise(17 + 31 + 80 + 17, 71);
+        // any closure acting sample by sample sees the same coefficients
+        let shrink = |band: &mut [f64], level: usize| {
+            if level < 3 {
+                band.iter_mut().for_each(|d| *d *= 0.5);
+            }
+        };
+        let mut one_shot = input.clone();
+        Bands::<f64, 3>::daubechies4().process(&mut one_shot, shrink);
+
+        let mut streaming = StreamingBands::from_bands(Bands::<f64, 3>::daubechies4());
+        let mut output = Vec::new();
+        let mut start = 0;
+        for len in [17, 31, 80, 17] {
+            output.extend_from_slice(streaming.push(&input[start..start + len], shrink));
+            start += len;
+            assert!(output.len().is_multiple_of(8));
+            assert_eq!(start - output.len(), streaming.pending());
+        }
+        assert_eq!(one_shot[..output.len()], output);
+
+        streaming.reset();
+        assert_eq!(0, streaming.pending());
+        assert!(streaming.push(&[1.; 7], |_d, _c| {}).is_empty());
+    }
+
+    #[test]
+    fn test_fixed_haar_band() {
+        let ramp: Vec<i32> = (0..256).map(|n| 1000 * n - 77_777).collect();
+        let mut fixed = FixedHaarBand::new();
+        let (low, high) = fixed.analysis(&ramp);
+        let mut out = vec![0; ramp.len()];
+        fixed.synthesis(&low, &high, &mut out);
+
+        let mut float = Band::<f32>::new();
+        let input: Vec<f32> = ramp.iter().map(|&x| x as f32).collect();
+        let (low, high) = float.analysis(&input);
+        let mut expected = vec![0.; input.len()];
+        float.synthesis(&low, &high, &mut expected);
+
+        let delay = fixed.delay();
+        for ((&y, &e), &x) in out[delay..].iter().zip(&expected[delay..]).zip(&ramp) {
+            assert!((y - x).abs() <= 1, "{y} != {x}");
+            assert!((y as f32 - e).abs() <= 1., "{y} != {e}");
+        }
+
+        // full scale input comes back without wrapping around
+        let loud = [
+            i32::MAX,
+            i32::MAX - 1,
+            i32::MIN,
+            i32::MAX,
+            i32::MAX,
+            i32::MAX,
+        ];
+        fixed.reset();
+        let (low, high) = fixed.analysis(&loud);
+        let mut out = [0; 6];
+        fixed.synthesis(&low, &high, &mut out);
+        for (&y, &x) in out[1..].iter().zip(&loud) {
+            assert!((y as i64 - x as i64).abs() <= 1, "{y} != {x}");
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_multi_bands_process_par() {
+        let inputs: Vec<Vec<f64>> = (0..8).map(|c| white_noise(300, 80 + c)).collect();
+        let shrink = |band: &mut [f64], level: usize, channel: usize| {
+            if level < 3 {
+                let gain = channel as f64 / 8.;
+                band.iter_mut().for_each(|d| *d *= gain);
+            }
+        };
+
+        let mut serial = inputs.clone();
+        for (channel, buffer) in serial.iter_mut().enumerate() {
+            let mut bands = Bands::<f64, 3>::daubechies4();
+            bands.process(buffer, |band, level| shrink(band, level, channel));
+        }
+
+        let mut multi = MultiBands::<f64, 3, 8>::from_filter_set(&Wavelet::daubechies(2).unwrap());
+        let mut parallel = inputs.clone();
+        let mut buffers: Vec<&mut [f64]> = parallel.iter_mut().map(Vec::as_mut_slice).collect();
+        multi.process_par(&mut buffers, shrink);
+        assert_eq!(serial, parallel);
+    }
+
+    #[test]
+    fn test_denoise_auto() {
+        let clean: Vec<f64> = (0..2048).map(|n| [0., 2., -1., 1.][n / 512]).collect();
+        let noisy: Vec<f64> = clean
+            .iter()
+            .zip(gaussian_noise(clean.len(), 89))
+            .map(|(x, e)| x + 0.2 * e)
+            .collect();
+        let mse = |ys: &[f64], delay: usize| -> f64 {
+            let errors = ys[delay..]
+                .iter()
+                .zip(&clean)
+                .map(|(y, x)| (y - x) * (y - x));
+            errors.sum::<f64>() / (ys.len() - delay) as f64
+        };
+
+        let mut bands = Bands::<f64, 4>::new();
+        let mut data = noisy.clone();
+        bands.denoise_auto(&mut data);
+        let delay = bands.delay();
+        assert!(
+            mse(&data, delay) < mse(&noisy, 0) / 2.,
+            "{} {}",
+            mse(&data, delay),
+            mse(&noisy, 0)
+        );
+
+        let mut dynamic = DynBands::<f64>::new(4);
+        let mut same = noisy.clone();
+        dynamic.denoise_auto(&mut same);
+        assert_eq!(data, same);
+    }
+
+    #[test]
+    fn test_is_aligned() {
+        let mut bands = Bands::<f64, 3>::new();
+        let mut dynamic = DynBands::<f64>::new(3);
+        assert!(bands.is_aligned() && dynamic.is_aligned());
+        let mut data = white_noise(16, 3);
+        let mut total = 0;
+        for len in [8, 3, 5, 16, 4] {
+            bands.process(&mut data[..len], |_, _| {});
+            dynamic.process(&mut data[..len], |_, _| {});
+            total += len;
+            assert_eq!(total % 8 == 0, bands.is_aligned(), "{total}");
+            assert_eq!(bands.is_aligned(), dynamic.is_aligned());
+        }
+        bands.reset();
+        assert!(bands.is_aligned());
+    }
+
+    #[test]
+    fn test_analysis_iter() {
+        let data = white_noise(37, 8);
+        let mut band = Band::<f64>::daubechies4();
+        let mut lazy = band.clone();
+        for block in [&data[..], &data[..5], &data[5..16]] {
+            let (low, high) = band.analysis(block);
+            let (lazy_low, lazy_high): (Vec<_>, Vec<_>) =
+                lazy.analysis_iter(block.iter().copied()).unzip();
+            assert_eq!(low, lazy_low);
+            assert_eq!(high, lazy_high);
+        }
+    }
+
+    #[test]
+    fn test_fixed_band_matches_band() {
+        let data = white_noise(24, 13);
+        let mut band = Band::<f64>::new();
+        let mut fixed = FixedBand::<f64, 8>::new();
+        assert_eq!(band.delay, fixed.delay());
+        for block in data.chunks_exact(8) {
+            let (low, high) = band.analysis(block);
+            let block: &[f64; 8] = block.try_into().unwrap();
+            let ((fixed_low, fixed_high), count) = allocations(|| fixed.analysis::<4>(block));
+            assert_eq!(
+                (&low[..], &high[..], 0),
+                (&fixed_low[..], &fixed_high[..], count)
+            );
+
+            let mut out = [0.; 8];
+            band.synthesis(&low, &high, &mut out);
+            let (fixed_out, count) = allocations(|| fixed.synthesis(&fixed_low, &fixed_high));
+            assert_eq!((out, 0), (fixed_out, count));
+        }
+
+        let filters = Wavelet::daubechies::<f64>(2).unwrap();
+        let mut band = Band::from_filter_set(&filters);
+        let taps =
+            |taps: &[f64]| FirFilter::<f64, 4>::from_taps(<[f64; 4]>::try_from(taps).unwrap());
+        let mut fixed = FixedBand::<f64, 8, 4>::from_kernels(
+            taps(filters.analysis_low()),
+            taps(filters.analysis_high()),
+            taps(filters.synthesis_low()),
+            taps(filters.synthesis_high()),
+        );
+        for block in data.chunks_exact(8) {
+            let (low, _) = band.analysis(block);
+            let (fixed_low, _): ([f64; 4], _) = fixed.analysis(block.try_into().unwrap());
+            assert_eq!(low, fixed_low);
+        }
+    }
+
+    #[test]
+    fn test_analysis_matches_filter_then_downsample() {
+        let filters = Wavelet::daubechies::<f64>(3).unwrap();
+        let mut band = Band::from_filter_set(&filters);
+        let mut low_filter = DynFirFilter::from_taps(filters.analysis_low());
+        let mut high_filter = DynFirFilter::from_taps(filters.analysis_high());
+        let (mut low_sampler, mut high_sampler) =
+            (DownSampler::new(2).unwrap(), DownSampler::new(2).unwrap());
+        let data = white_noise(41, 12);
+        for block in [&data[..9], &data[9..30], &data[30..]] {
+            let lows = block.iter().map(|&x| low_filter.consume(x));
+            let highs = block.iter().map(|&x| high_filter.consume(x));
+            let expected = (
+                low_sampler.iter(lows).collect(),
+                high_sampler.iter(highs).collect(),
+            );
+            assert_eq!(expected, band.analysis(block));
+        }
+    }
+
+    #[test]
+    fn test_synthesis_matches_upsample_then_filter() {
+        let filters = Wavelet::daubechies::<f64>(4).unwrap();
+        let mut band = Band::from_filter_set(&filters);
+        let mut low_filter = DynFirFilter::from_taps(filters.synthesis_low());
+        let mut high_filter = DynFirFilter::from_taps(filters.synthesis_high());
+        let mut low_sampler = UpSampler::with_zero(2).unwrap();
+        let mut high_sampler = UpSampler::with_zero(2).unwrap();
+        let (low, high) = (white_noise(20, 13), white_noise(20, 14));
+        // odd output lengths leave a stuffed zero owed to the next block
+        for (range, len) in [(0..5, 9), (5..6, 3), (6..20, 28)] {
+            let mut expected = vec![0.; len];
+            for (o, (l, h)) in expected.iter_mut().zip(core::iter::zip(
+                low_sampler.iter(low[range.clone()].iter().copied()),
+                high_sampler.iter(high[range.clone()].iter().copied()),
+            )) {
+                *o = low_filter.consume(l) + high_filter.consume(h);
+            }
+            let mut out = vec![0.; len];
+            band.synthesis(&low[range.clone()], &high[range], &mut out);
+            assert_eq!(expected, out);
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "output length does not match the upsampled branches")]
+    fn test_synthesis_short_output() {
+        let mut band = Band::<f64>::new();
+        let (low, high) = band.analysis(&[1.; 8]);
+        // two samples short, so the last input pair would be dropped
+        band.synthesis(&low, &high, &mut [0.; 6]);
     }
 }
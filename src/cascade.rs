@@ -0,0 +1,207 @@
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::fir::{DynFirFilter, FilterKernel};
+
+/// Filters run in series, the output of each stage feeding the next, e.g. a
+/// DC blocker in front of a [`Band`](crate::Band) branch.
+///
+/// Stages of different types can be mixed as `Cascade<T, Box<dyn
+/// FilterKernel<T>>>`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cascade<T, F = DynFirFilter<T>> {
+    stages: Vec<F>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    _sample: core::marker::PhantomData<T>,
+}
+
+impl<T, F> Cascade<T, F>
+where
+    F: FilterKernel<T>,
+{
+    /// An empty cascade, which passes its input through.
+    pub fn new() -> Self {
+        Self::from_stages(Vec::new())
+    }
+
+    pub fn from_stages(stages: Vec<F>) -> Self {
+        Self {
+            stages,
+            _sample: core::marker::PhantomData,
+        }
+    }
+
+    /// Appends a stage after the existing ones.
+    pub fn push(&mut self, stage: F) {
+        self.stages.push(stage);
+    }
+
+    /// Number of stages. The length of the combined impulse response is
+    /// [`FilterKernel::len`].
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.stages.clear();
+    }
+
+    pub fn stages(&self) -> &[F] {
+        &self.stages
+    }
+
+    pub fn consume(&mut self, x: T) -> T {
+        self.stages.iter_mut().fold(x, |x, stage| stage.consume(x))
+    }
+
+    pub fn consume_in_place(&mut self, xs: &mut [T])
+    where
+        T: Copy,
+    {
+        for x in xs.iter_mut() {
+            *x = self.consume(*x);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.stages.iter_mut().for_each(F::reset);
+    }
+}
+
+impl<T, F> Default for Cascade<T, F>
+where
+    F: FilterKernel<T>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, F> FilterKernel<T> for Cascade<T, F>
+where
+    F: FilterKernel<T>,
+{
+    fn consume(&mut self, x: T) -> T {
+        Cascade::consume(self, x)
+    }
+
+    fn reset(&mut self) {
+        Cascade::reset(self)
+    }
+
+    /// The impulse responses convolved, one sample shorter per stage than
+    /// their total length.
+    fn len(&self) -> usize {
+        self.stages.iter().fold(1, |len, stage| {
+            len.saturating_add(FilterKernel::len(stage).saturating_sub(1))
+        })
+    }
+}
+
+impl<T, K> FilterKernel<T> for Box<K>
+where
+    K: FilterKernel<T> + ?Sized,
+{
+    fn consume(&mut self, x: T) -> T {
+        (**self).consume(x)
+    }
+
+    fn skip(&mut self, x: T) {
+        (**self).skip(x)
+    }
+
+    fn consume_upsampled(&mut self, x: T, phase: usize, factor: usize) -> T {
+        (**self).consume_upsampled(x, phase, factor)
+    }
+
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, vec};
+
+    use super::Cascade;
+    use crate::{
+        bands::{Band, QmfBand},
+        fir::{DelayLine, DynFirFilter, FilterKernel, FirFilter},
+        haar::HaarFilter,
+        test_util::white_noise,
+    };
+
+    #[test]
+    fn test_cascade_convolves() {
+        let mut cascade = Cascade::from_stages(vec![
+            HaarFilter::<f64>::new(0.5, 0.5),
+            HaarFilter::new(0.5, 0.5),
+        ]);
+        let mut kernel = FirFilter::from_taps([0.25, 0.5, 0.25]);
+        assert_eq!(2, cascade.len());
+        assert_eq!(3, FilterKernel::len(&cascade));
+
+        for x in white_noise(200, 43) {
+            let (y, expected) = (cascade.consume(x), kernel.consume(x));
+            assert!((y - expected).abs() < 1e-15, "{y} != {expected}");
+        }
+    }
+
+    #[test]
+    fn test_cascade_reset() {
+        let mut cascade: Cascade<f64, Box<dyn FilterKernel<f64>>> = Cascade::new();
+        assert_eq!(7., cascade.consume(7.));
+        cascade.push(Box::new(HaarFilter::<f64>::new(0.5, 0.5)));
+        cascade.push(Box::new(DelayLine::new(3)));
+        assert_eq!(5, FilterKernel::len(&cascade));
+
+        let mut data = white_noise(16, 47);
+        cascade.consume_in_place(&mut data);
+        cascade.reset();
+        // every stage starts from silence again
+        let response: Vec<_> = [1., 0., 0., 0., 0., 0.]
+            .into_iter()
+            .map(|x| cascade.consume(x))
+            .collect();
+        assert_eq!(vec![0., 0., 0., 0.5, 0.5, 0.], response);
+
+        cascade.clear();
+        assert!(cascade.is_empty());
+    }
+
+    #[test]
+    fn test_band_with_cascades() {
+        // an identity stage behind every filter leaves the band as it was
+        let stages = |taps: &[f64], post: &[f64]| {
+            Cascade::from_stages(vec![
+                DynFirFilter::from_taps(taps),
+                DynFirFilter::from_taps(post),
+            ])
+        };
+        let mut band = Band::from_kernels(
+            stages(&[0.5, 0.5], &[1.]),
+            stages(&[-0.5, 0.5], &[1.]),
+            stages(&[1., 1.], &[1.]),
+            stages(&[1., -1.], &[1.]),
+        );
+        let mut plain = Band::<f64>::new();
+        assert_eq!(plain.delay(), band.delay());
+
+        let input = white_noise(128, 53);
+        let (lows, highs) = band.analysis(&input);
+        let mut out = vec![0.; input.len()];
+        band.synthesis(&lows, &highs, &mut out);
+        let (lows, highs) = plain.analysis(&input);
+        let mut expected = vec![0.; input.len()];
+        plain.synthesis(&lows, &highs, &mut expected);
+        assert_eq!(expected, out);
+    }
+}
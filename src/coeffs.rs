@@ -0,0 +1,64 @@
+use alloc::vec::Vec;
+use num_traits::Float;
+
+/// The subbands of a multi-level decomposition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaveletCoeffs<T> {
+    /// Highpass output of every level, the finest (level 0) first.
+    pub details: Vec<Vec<T>>,
+    /// Lowpass output of the deepest level.
+    pub approximation: Vec<T>,
+}
+
+impl<T> WaveletCoeffs<T> {
+    /// Number of coefficients over all bands.
+    pub fn total_len(&self) -> usize {
+        self.details.iter().map(Vec::len).sum::<usize>() + self.approximation.len()
+    }
+}
+
+impl<T: Float> WaveletCoeffs<T> {
+    /// Shannon entropy `-Σ p·log2(p)` in bits of the coefficient energies
+    /// `p = c² / Σc²` over all bands. Low when a few coefficients carry the
+    /// signal, up to `log2(total_len)` when all carry the same; zero if there
+    /// is no energy at all.
+    pub fn entropy(&self) -> T {
+        let coeffs = || self.details.iter().flatten().chain(&self.approximation);
+        let energy = coeffs().fold(T::zero(), |acc, &c| acc + c * c);
+        if energy == T::zero() {
+            return T::zero();
+        }
+        coeffs()
+            .map(|&c| c * c / energy)
+            .filter(|&p| p > T::zero())
+            .fold(T::zero(), |acc, p| acc - p * p.log2())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WaveletCoeffs;
+    use crate::{bands::Bands, test_util::white_noise};
+
+    #[test]
+    fn test_entropy() {
+        // blocks of 2^4 are what Haar at depth 4 represents most sparsely
+        let steps: Vec<f64> = (0..256).map(|n| [1., -2., 0.5, 3.][(n / 16) % 4]).collect();
+        let noise = white_noise(256, 17);
+        let sparse = Bands::<f64, 4>::new().analyze(&steps).entropy();
+        let spread = Bands::<f64, 4>::new().analyze(&noise).entropy();
+        assert!(sparse + 1. < spread, "{sparse} {spread}");
+        assert!(spread < 8.);
+
+        let uniform = WaveletCoeffs::<f64> {
+            details: vec![vec![1., -1.]],
+            approximation: vec![1., 1.],
+        };
+        assert!((uniform.entropy() - 2.).abs() < 1e-12);
+        let silent = WaveletCoeffs::<f64> {
+            details: vec![vec![0.]],
+            approximation: vec![0.],
+        };
+        assert_eq!(0., silent.entropy());
+    }
+}
@@ -0,0 +1,184 @@
+use alloc::vec::Vec;
+use num_traits::Float;
+
+use crate::coeffs::WaveletCoeffs;
+
+/// [`WaveletCoeffs`] quantized to integer multiples of a step size per band.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantizedCoeffs<T> {
+    /// Quantization indices of every detail band, the finest first.
+    pub details: Vec<Vec<i32>>,
+    pub approximation: Vec<i32>,
+    /// Step sizes of the detail bands followed by that of the approximation.
+    pub steps: Vec<T>,
+}
+
+impl<T> QuantizedCoeffs<T> {
+    fn indices(&self) -> impl Iterator<Item = &i32> {
+        self.details.iter().flatten().chain(&self.approximation)
+    }
+
+    /// Number of indices over all bands.
+    pub fn total_len(&self) -> usize {
+        self.indices().count()
+    }
+
+    pub fn nonzero_count(&self) -> usize {
+        self.indices().filter(|&&q| q != 0).count()
+    }
+
+    /// Fraction of the indices that are zero, `0` for no coefficients at all.
+    pub fn sparsity(&self) -> f64 {
+        match self.total_len() {
+            0 => 0.,
+            len => (len - self.nonzero_count()) as f64 / len as f64,
+        }
+    }
+}
+
+/// Rounds every coefficient to the nearest multiple of the step size of its
+/// band, keeping the index. `steps` holds one positive step per detail band,
+/// the finest first, and one for the approximation last, in the order of
+/// [`Bands::band_energies`](crate::Bands::band_energies). Indices beyond the
+/// range of `i32` saturate.
+///
+/// Panics unless there is a positive step for every band.
+pub fn quantize<T: Float>(coeffs: &WaveletCoeffs<T>, steps: &[T]) -> QuantizedCoeffs<T> {
+    assert_eq!(
+        coeffs.details.len() + 1,
+        steps.len(),
+        "one step per band and the approximation"
+    );
+    assert!(
+        steps.iter().all(|&step| step > T::zero()),
+        "steps must be positive"
+    );
+    let (&approximation_step, detail_steps) = steps.split_last().unwrap();
+    QuantizedCoeffs {
+        details: coeffs
+            .details
+            .iter()
+            .zip(detail_steps)
+            .map(|(band, &step)| quantize_band(band, step))
+            .collect(),
+        approximation: quantize_band(&coeffs.approximation, approximation_step),
+        steps: steps.to_vec(),
+    }
+}
+
+/// Inverse of [`quantize`], up to the rounding.
+pub fn dequantize<T: Float>(quantized: &QuantizedCoeffs<T>) -> WaveletCoeffs<T> {
+    let (&approximation_step, detail_steps) = quantized.steps.split_last().unwrap();
+    WaveletCoeffs {
+        details: quantized
+            .details
+            .iter()
+            .zip(detail_steps)
+            .map(|(band, &step)| dequantize_band(band, step))
+            .collect(),
+        approximation: dequantize_band(&quantized.approximation, approximation_step),
+    }
+}
+
+fn quantize_band<T: Float>(band: &[T], step: T) -> Vec<i32> {
+    let (min, max) = (T::from(i32::MIN).unwrap(), T::from(i32::MAX).unwrap());
+    band.iter()
+        .map(|&x| (x / step).round().max(min).min(max).to_i32().unwrap_or(0))
+        .collect()
+}
+
+fn dequantize_band<T: Float>(band: &[i32], step: T) -> Vec<T> {
+    band.iter().map(|&q| T::from(q).unwrap() * step).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{collections::BTreeSet, vec::Vec};
+
+    use super::{dequantize, quantize};
+    use crate::{bands::Bands, coeffs::WaveletCoeffs, denoise::hard_threshold, metrics::snr};
+
+    #[test]
+    fn test_quantize_round_trip() {
+        let coeffs = WaveletCoeffs {
+            details: vec![vec![0.26, -0.74, 1e12], vec![3.]],
+            approximation: vec![10., 10.9],
+        };
+        let quantized = quantize(&coeffs, &[0.5, 2., 1.]);
+        assert_eq!(vec![vec![1, -1, i32::MAX], vec![2]], quantized.details);
+        assert_eq!(vec![10, 11], quantized.approximation);
+
+        let restored = dequantize(&quantized);
+        assert_eq!(vec![0.5, -0.5, i32::MAX as f64 * 0.5], restored.details[0]);
+        assert_eq!(vec![4.], restored.details[1]);
+        assert_eq!(vec![10., 11.], restored.approximation);
+    }
+
+    #[test]
+    fn test_compression_snr() {
+        let input: Vec<f64> = (0..1024).map(|n| (n as f64 * 0.05).sin()).collect();
+        let mut last_snr = f64::INFINITY;
+        let mut last_levels = usize::MAX;
+        for step in [1e-3, 1e-2, 1e-1] {
+            let mut bands = Bands::<f64, 3>::daubechies4();
+            let quantized = bands.compress(&input, &[step; 4]);
+            let mut out = vec![0.; input.len()];
+            bands.decompress(&quantized, &mut out);
+
+            let delay = bands.delay();
+            let snr = snr(&input[..input.len() - delay], &out[delay..]);
+            let levels = quantized
+                .details
+                .iter()
+                .flatten()
+                .chain(&quantized.approximation)
+                .collect::<BTreeSet<_>>()
+                .len();
+            assert!(
+                snr < last_snr && levels < last_levels,
+                "{step}: {snr} dB, {levels}"
+            );
+            last_snr = snr;
+            last_levels = levels;
+        }
+        // a step of 0.1 still leaves a recognizable sine
+        assert!(last_snr > 10., "{last_snr}");
+    }
+
+    #[test]
+    fn test_sparsity() {
+        // a slow sine with a single jump leaves few details worth keeping
+        let input: Vec<f64> = (0..1024)
+            .map(|n| (n as f64 * 0.01).sin() + if n < 300 { 0. } else { 1. })
+            .collect();
+        let mut bands = Bands::<f64, 4>::daubechies4();
+        let mut coeffs = bands.analyze(&input);
+        assert_eq!(
+            1024 / 2 + 1024 / 4 + 1024 / 8 + 2 * (1024 / 16),
+            coeffs.total_len()
+        );
+        for d in coeffs.details.iter_mut().flatten() {
+            *d = hard_threshold(*d, 0.05);
+        }
+
+        let quantized = quantize(&coeffs, &[0.01; 5]);
+        assert_eq!(coeffs.total_len(), quantized.total_len());
+        assert!(
+            quantized.nonzero_count() < 100,
+            "{}",
+            quantized.nonzero_count()
+        );
+        assert!(quantized.sparsity() > 0.9, "{}", quantized.sparsity());
+        assert_eq!(
+            0.,
+            quantize(
+                &WaveletCoeffs::<f64> {
+                    details: vec![],
+                    approximation: vec![]
+                },
+                &[1.]
+            )
+            .sparsity()
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use alloc::vec::Vec;
+use num_traits::Float;
+
+/// Shrinks `x` toward zero by `lambda`, zeroing anything within `lambda` of
+/// zero: `sign(x) · max(|x| - lambda, 0)`.
+pub fn soft_threshold<T: Float>(x: T, lambda: T) -> T {
+    if x > lambda {
+        x - lambda
+    } else if x < -lambda {
+        x + lambda
+    } else {
+        T::zero()
+    }
+}
+
+/// Zeroes `x` if it lies within `lambda` of zero and keeps it untouched
+/// otherwise.
+pub fn hard_threshold<T: Float>(x: T, lambda: T) -> T {
+    if x.abs() > lambda {
+        x
+    } else {
+        T::zero()
+    }
+}
+
+/// The universal (VisuShrink) threshold `σ·√(2 ln n)` for the `n` samples of
+/// `detail`, the finest detail band, with the noise level `σ` estimated from
+/// the median absolute deviation as `median(|d|) / 0.6745`. Zero for an
+/// empty band.
+pub fn universal_threshold<T: Float>(detail: &[T]) -> T {
+    let mut magnitudes: Vec<T> = detail.iter().map(|d| d.abs()).collect();
+    if magnitudes.is_empty() {
+        return T::zero();
+    }
+    magnitudes.sort_by(|a, b| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal));
+    let mid = magnitudes.len() / 2;
+    let median = if magnitudes.len().is_multiple_of(2) {
+        (magnitudes[mid - 1] + magnitudes[mid]) / T::from(2).unwrap()
+    } else {
+        magnitudes[mid]
+    };
+    let sigma = median / T::from(0.6745).unwrap();
+    let n = T::from(detail.len()).unwrap();
+    sigma * (T::from(2).unwrap() * n.ln()).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hard_threshold, soft_threshold, universal_threshold};
+    use crate::test_util::gaussian_noise;
+
+    #[test]
+    fn test_thresholds() {
+        assert_eq!(0.5, soft_threshold(1.5, 1.));
+        assert_eq!(-0.5, soft_threshold(-1.5, 1.));
+        assert_eq!(0., soft_threshold(0.7, 1.));
+        assert_eq!(0., soft_threshold(-1., 1.));
+
+        assert_eq!(1.5, hard_threshold(1.5, 1.));
+        assert_eq!(-1.5, hard_threshold(-1.5, 1.));
+        assert_eq!(0., hard_threshold(-0.7, 1.));
+    }
+
+    #[test]
+    fn test_universal_threshold() {
+        assert_eq!(0., universal_threshold::<f64>(&[]));
+        // median 2, over an odd and an even number of samples
+        let lambda = 2. / 0.6745 * (2. * 5f64.ln()).sqrt();
+        assert!((universal_threshold(&[-3., 1., 2., -0.5, 9.]) - lambda).abs() < 1e-12);
+        let lambda = 2. / 0.6745 * (2. * 4f64.ln()).sqrt();
+        assert!((universal_threshold(&[1., -3., 2.5, -1.5]) - lambda).abs() < 1e-12);
+
+        // recovers the noise level of Gaussian noise
+        let noise: Vec<f64> = gaussian_noise(4096, 5).iter().map(|x| 0.3 * x).collect();
+        let sigma = universal_threshold(&noise) / (2. * 4096f64.ln()).sqrt();
+        assert!((sigma - 0.3).abs() < 0.02, "{sigma}");
+    }
+}
@@ -0,0 +1,450 @@
+use alloc::vec::Vec;
+use core::f64::consts::PI;
+use num_traits::Float;
+
+use crate::window;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Window {
+    Hamming,
+    Blackman,
+    /// Kaiser window; a larger `beta` trades a wider transition band for more
+    /// stopband attenuation.
+    Kaiser {
+        beta: f64,
+    },
+}
+
+impl Window {
+    /// The window value at `n` of a `len` point window.
+    fn at(&self, n: usize, len: usize) -> f64 {
+        if len == 1 {
+            return 1.;
+        }
+        let x = n as f64 / (len - 1) as f64;
+        match *self {
+            Window::Hamming => window::hamming_at(x),
+            Window::Blackman => window::blackman_at(x),
+            Window::Kaiser { beta } => {
+                let r = 2. * x - 1.;
+                bessel_i0(beta * Float::sqrt(1. - r * r)) / bessel_i0(beta)
+            }
+        }
+    }
+}
+
+// power series of the zeroth order modified Bessel function of the first kind
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.;
+    let mut term = 1.;
+    let mut k = 1.;
+    while term > sum * 1e-17 {
+        let half = x / (2. * k);
+        term *= half * half;
+        sum += term;
+        k += 1.;
+    }
+    sum
+}
+
+/// A windowed-sinc half-band lowpass with its cutoff at a quarter of the
+/// sample rate, for use as a QMF prototype. The taps sum to one, and every
+/// other tap apart from the center one is zero.
+///
+/// Panics unless `num_taps` is odd.
+pub fn halfband(num_taps: usize, window: Window) -> Vec<f64> {
+    assert!(
+        num_taps % 2 == 1,
+        "a half-band filter needs an odd number of taps"
+    );
+    let center = num_taps / 2;
+    let mut taps: Vec<f64> = (0..num_taps)
+        .map(|n| {
+            let k = n as isize - center as isize;
+            let sinc = match k {
+                0 => 0.5,
+                k if k % 2 == 0 => 0.,
+                k => Float::sin(PI * k as f64 / 2.) / (PI * k as f64),
+            };
+            sinc * window.at(n, num_taps)
+        })
+        .collect();
+    let sum: f64 = taps.iter().sum();
+    taps.iter_mut().for_each(|h| *h /= sum);
+    taps
+}
+
+/// A windowed-sinc lowpass with its cutoff at `cutoff` times the sample
+/// rate, which is below one half. The taps sum to one and are symmetric, for
+/// a delay of `(num_taps - 1) / 2` samples.
+///
+/// Panics unless `num_taps` is nonzero and `cutoff` lies in `(0, 0.5]`.
+pub fn lowpass(num_taps: usize, cutoff: f64, window: Window) -> Vec<f64> {
+    assert!(num_taps > 0, "a lowpass needs taps");
+    assert!(
+        cutoff > 0. && cutoff <= 0.5,
+        "the cutoff must lie in (0, 0.5]"
+    );
+    let center = (num_taps - 1) as f64 / 2.;
+    let mut taps: Vec<f64> = (0..num_taps)
+        .map(|n| {
+            // from the first half only, so the taps are exactly symmetric
+            let n = n.min(num_taps - 1 - n);
+            let t = center - n as f64;
+            let sinc = if t == 0. {
+                2. * cutoff
+            } else {
+                Float::sin(2. * PI * cutoff * t) / (PI * t)
+            };
+            sinc * window.at(n, num_taps)
+        })
+        .collect();
+    let sum: f64 = taps.iter().sum();
+    taps.iter_mut().for_each(|h| *h /= sum);
+    taps
+}
+
+/// A windowed FIR Hilbert transformer, `2 / (πk)` at odd offsets `k` from
+/// the center tap and zero elsewhere. It shifts the phase of everything but
+/// DC and Nyquist by -90° while delaying by `num_taps / 2` samples.
+///
+/// Panics unless `num_taps` is odd.
+pub fn hilbert(num_taps: usize, window: Window) -> Vec<f64> {
+    assert!(
+        num_taps % 2 == 1,
+        "a Hilbert transformer needs an odd number of taps"
+    );
+    let center = num_taps / 2;
+    (0..num_taps)
+        .map(|n| {
+            let k = n as isize - center as isize;
+            if k % 2 == 0 {
+                0.
+            } else {
+                2. / (PI * k as f64) * window.at(n, num_taps)
+            }
+        })
+        .collect()
+}
+
+/// The highpass mirror of a lowpass, `h[n]·(-1)^n`, i.e. its response shifted
+/// by half the sample rate.
+pub fn modulate<T: Float>(lowpass: &[T]) -> Vec<T> {
+    lowpass
+        .iter()
+        .enumerate()
+        .map(|(n, &h)| if n % 2 == 0 { h } else { -h })
+        .collect()
+}
+
+/// The conjugate quadrature highpass of an orthogonal lowpass,
+/// `h1[n] = (-1)^(n+1)·h0[L-1-n]`.
+pub fn qmf<T: Float>(lowpass: &[T]) -> Vec<T> {
+    lowpass
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(n, &h)| if n % 2 == 1 { h } else { -h })
+        .collect()
+}
+
+/// The synthesis filters `g0[n] = c·(-1)^n·h1[n]` and `g1[n] = -c·(-1)^n·h0[n]`
+/// that cancel the aliasing of an analysis pair, with `c` chosen so that the
+/// round trip through a [`Band`](crate::Band) has unit gain.
+///
+/// Panics if either analysis filter is empty.
+pub fn synthesis_pair<T: Float>(analysis_low: &[T], analysis_high: &[T]) -> (Vec<T>, Vec<T>) {
+    assert!(
+        !analysis_low.is_empty() && !analysis_high.is_empty(),
+        "the analysis filters need taps"
+    );
+    let low = modulate(analysis_high);
+    let high: Vec<T> = modulate(analysis_low).into_iter().map(|h| -h).collect();
+
+    // the distortion term of a perfect reconstruction pair has a single
+    // nonzero coefficient, the gain to undo
+    let (distortion, _) = bank_terms(analysis_low, analysis_high, &low, &high);
+    let gain = distortion.iter().fold(
+        T::zero(),
+        |peak, &d| if d.abs() > peak.abs() { d } else { peak },
+    );
+    let scale = |taps: Vec<T>| taps.into_iter().map(|h| h / gain).collect();
+    (scale(low), scale(high))
+}
+
+/// A 3-tap FIR `[-b, 1 + 2b, -b]` that flattens the passband droop of a
+/// [`CicDecimator`](crate::CicDecimator) of this `order` and `factor`, run
+/// at its output rate. It cancels the droop to second order in frequency,
+/// which is enough well into the passband; it boosts towards Nyquist, so
+/// the next stage should filter that off.
+pub fn cic_compensator(order: usize, factor: usize) -> [f64; 3] {
+    let r = factor as f64;
+    // the droop starts as 1 - N·(1 - 1/R²)·ω²/24, and the taps add b·ω²
+    let b = order as f64 * (1. - 1. / (r * r)) / 24.;
+    [-b, 1. + 2. * b, -b]
+}
+
+/// How far a two-channel filter bank is from perfect reconstruction, as
+/// returned by [`check_perfect_reconstruction`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrReport<T> {
+    /// Both errors are within the tolerance.
+    pub passed: bool,
+    /// Samples the round trip lags behind, where its distortion term peaks.
+    pub delay: usize,
+    /// Largest deviation of a distortion coefficient from the unit delay
+    /// `z^-delay`.
+    pub distortion_error: T,
+    /// Largest aliasing coefficient.
+    pub aliasing_error: T,
+}
+
+/// Checks whether the analysis filters `h0`, `h1` and synthesis filters `g0`,
+/// `g1` form a perfect reconstruction bank, by expanding the distortion term
+/// `(H0(z)G0(z) + H1(z)G1(z)) / 2` and the aliasing term
+/// `(H0(-z)G0(z) + H1(-z)G1(z)) / 2`. They pass if the first is a unit delay
+/// and the second vanishes, up to `tolerance` on every coefficient.
+pub fn check_perfect_reconstruction<T: Float>(
+    h0: &[T],
+    h1: &[T],
+    g0: &[T],
+    g1: &[T],
+    tolerance: T,
+) -> PrReport<T> {
+    let (distortion, aliasing) = bank_terms(h0, h1, g0, g1);
+    let delay = distortion
+        .iter()
+        .enumerate()
+        .fold((0, T::zero()), |best, (k, &d)| {
+            if d.abs() > best.1 {
+                (k, d.abs())
+            } else {
+                best
+            }
+        })
+        .0;
+    let worst = |errors: &mut dyn Iterator<Item = T>| errors.fold(T::zero(), T::max);
+    let distortion_error = worst(&mut distortion.iter().enumerate().map(|(k, &d)| {
+        let target = if k == delay { T::one() } else { T::zero() };
+        (d - target).abs()
+    }));
+    let aliasing_error = worst(&mut aliasing.iter().map(|a| a.abs()));
+    PrReport {
+        passed: distortion_error <= tolerance && aliasing_error <= tolerance,
+        delay,
+        distortion_error,
+        aliasing_error,
+    }
+}
+
+// coefficients of the distortion and aliasing terms of a two-channel bank
+fn bank_terms<T: Float>(h0: &[T], h1: &[T], g0: &[T], g1: &[T]) -> (Vec<T>, Vec<T>) {
+    let product = |a: &[T], b: &[T]| -> Vec<T> {
+        let mut out = alloc::vec![T::zero(); (a.len() + b.len()).saturating_sub(1)];
+        convolve(a, b, &mut out);
+        out
+    };
+    let half = T::from(0.5).unwrap();
+    let sum = |a: Vec<T>, b: Vec<T>| -> Vec<T> {
+        let mut sum = alloc::vec![T::zero(); a.len().max(b.len())];
+        for (k, x) in a.into_iter().enumerate().chain(b.into_iter().enumerate()) {
+            sum[k] = sum[k] + x * half;
+        }
+        sum
+    };
+    (
+        sum(product(h0, g0), product(h1, g1)),
+        sum(product(&modulate(h0), g0), product(&modulate(h1), g1)),
+    )
+}
+
+// `out` holds `a.len() + b.len() - 1` zeros on entry
+pub(crate) fn convolve<T: Float>(a: &[T], b: &[T], out: &mut [T]) {
+    for (i, &x) in a.iter().enumerate() {
+        for (y, &h) in out[i..].iter_mut().zip(b) {
+            *y = *y + x * h;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_perfect_reconstruction, cic_compensator, halfband, hilbert, lowpass, modulate, qmf,
+        synthesis_pair, Window,
+    };
+    use crate::{
+        bands::{Band, QmfBand},
+        fir::DynFirFilter,
+        wavelet::{FilterSet, Wavelet},
+    };
+    use core::f64::consts::PI;
+
+    // worst response in dB over [from, π]
+    fn stopband(taps: &[f64], from: f64) -> f64 {
+        let filter = DynFirFilter::<f64>::from_taps(taps);
+        (0..=200)
+            .map(|i| from + (PI - from) * i as f64 / 200.)
+            .map(|omega| 20. * filter.frequency_response(omega).0.log10())
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    #[test]
+    fn test_halfband_property() {
+        for window in [
+            Window::Hamming,
+            Window::Blackman,
+            Window::Kaiser { beta: 8. },
+        ] {
+            let taps = halfband(31, window);
+            assert_eq!(31, taps.len());
+            assert!((taps.iter().sum::<f64>() - 1.).abs() < 1e-12);
+            for (n, h) in taps.iter().enumerate() {
+                if n != 15 && (n as isize - 15) % 2 == 0 {
+                    assert!(h.abs() < 1e-12, "tap {n} is {h}");
+                }
+            }
+            // symmetric about the center
+            for n in 0..15 {
+                assert!((taps[n] - taps[30 - n]).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_halfband_stopband() {
+        for (window, attenuation) in [
+            (Window::Hamming, -50.),
+            (Window::Blackman, -70.),
+            (Window::Kaiser { beta: 8. }, -75.),
+        ] {
+            let taps = halfband(63, window);
+            let worst = stopband(&taps, 0.65 * PI);
+            assert!(worst < attenuation, "{window:?}: {worst} dB");
+
+            // the mirrored highpass rejects the low half instead
+            let highpass = modulate(&taps);
+            let filter = DynFirFilter::<f64>::from_taps(&highpass);
+            assert!(filter.dc_gain().abs() < 1e-3);
+            assert!((filter.nyquist_gain() - 1.).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_lowpass() {
+        let taps = lowpass(63, 0.1, Window::Kaiser { beta: 8. });
+        assert!((taps.iter().sum::<f64>() - 1.).abs() < 1e-12);
+        let filter = DynFirFilter::<f64>::from_taps(&taps);
+        assert!((filter.magnitude_at(0.05) - 1.).abs() < 1e-3);
+        let worst = stopband(&taps, 0.32 * PI);
+        assert!(worst < -75., "{worst} dB");
+        assert_eq!(Some(31.), filter.group_delay());
+    }
+
+    #[test]
+    fn test_qmf_reproduces_haar() {
+        let haar = FilterSet::<f64>::haar();
+        let high = qmf(haar.analysis_low());
+        assert_eq!(haar.analysis_high(), high.as_slice());
+        let (g0, g1) = synthesis_pair(haar.analysis_low(), &high);
+        assert_eq!(haar.synthesis_low(), g0.as_slice());
+        assert_eq!(haar.synthesis_high(), g1.as_slice());
+    }
+
+    #[test]
+    fn test_synthesis_pair_matches_tables() {
+        for wavelet in [Wavelet::Daubechies(4), Wavelet::LeGall53, Wavelet::Cdf97] {
+            let filters = wavelet.filter_set::<f64>().unwrap();
+            let (g0, g1) = synthesis_pair(filters.analysis_low(), filters.analysis_high());
+            if let Wavelet::Daubechies(_) = wavelet {
+                assert_eq!(
+                    filters.analysis_high(),
+                    qmf(filters.analysis_low()).as_slice()
+                );
+            }
+            for (ours, table) in [
+                (g0, filters.synthesis_low()),
+                (g1, filters.synthesis_high()),
+            ] {
+                assert_eq!(table.len(), ours.len(), "{wavelet:?}");
+                for (a, b) in ours.iter().zip(table.iter()) {
+                    assert!((a - b).abs() < 1e-12, "{wavelet:?}: {a} != {b}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_check_perfect_reconstruction() {
+        let haar = FilterSet::<f64>::haar();
+        let check = |filters: &FilterSet<f64>| {
+            check_perfect_reconstruction(
+                filters.analysis_low(),
+                filters.analysis_high(),
+                filters.synthesis_low(),
+                filters.synthesis_high(),
+                1e-12,
+            )
+        };
+        let report = check(&haar);
+        assert!(report.passed, "{report:?}");
+        assert_eq!(1, report.delay);
+        assert_eq!(0., report.aliasing_error);
+
+        // the reported delay is where a band's impulse response peaks
+        for wavelet in [Wavelet::Daubechies(3), Wavelet::Symlet(5), Wavelet::Cdf97] {
+            let filters = wavelet.filter_set::<f64>().unwrap();
+            let report = check(&filters);
+            assert!(report.passed, "{wavelet:?}: {report:?}");
+            assert_eq!(Band::from_filter_set(&filters).delay(), report.delay);
+        }
+
+        let mut taps = [0.5, 0.5];
+        taps[1] += 1e-3;
+        let perturbed = FilterSet::new(
+            &taps,
+            haar.analysis_high(),
+            haar.synthesis_low(),
+            haar.synthesis_high(),
+        )
+        .unwrap();
+        let report = check(&perturbed);
+        assert!(!report.passed);
+        assert!((report.distortion_error - 5e-4).abs() < 1e-12, "{report:?}");
+        assert!((report.aliasing_error - 5e-4).abs() < 1e-12, "{report:?}");
+    }
+
+    #[test]
+    fn test_hilbert_passband() {
+        let taps = hilbert(63, Window::Kaiser { beta: 6. });
+        let filter = DynFirFilter::<f64>::from_taps(&taps);
+        for i in 1..10 {
+            let omega = PI * (0.1 + 0.08 * i as f64);
+            let (magnitude, phase) = filter.frequency_response(omega);
+            assert!((magnitude - 1.).abs() < 0.01, "{magnitude} at {omega}");
+            // a quarter turn behind the delay of the center tap
+            let lag = (phase + 31. * omega + PI / 2.).rem_euclid(2. * PI);
+            assert!(lag.min(2. * PI - lag) < 1e-9, "{phase} at {omega}");
+        }
+    }
+
+    #[test]
+    fn test_cic_compensator() {
+        let (order, factor) = (3, 64);
+        let droop = |omega: f64| {
+            let r = factor as f64;
+            ((omega / 2.).sin() / (r * (omega / (2. * r)).sin())).powi(order as i32)
+        };
+        let taps = cic_compensator(order, factor);
+        let compensator = DynFirFilter::<f64>::from_taps(&taps);
+        assert!((compensator.dc_gain() - 1.).abs() < 1e-12);
+        for omega in [0.1, 0.2, 0.3].map(|f| f * core::f64::consts::PI) {
+            let (magnitude, _) = compensator.frequency_response(omega);
+            let flattened = droop(omega) * magnitude;
+            assert!(
+                (flattened - 1.).abs() < (droop(omega) - 1.).abs() / 5.,
+                "{omega}"
+            );
+        }
+    }
+}
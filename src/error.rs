@@ -0,0 +1,27 @@
+use core::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QmfError {
+    /// The wavelet family has no filters of the requested order.
+    UnsupportedOrder(usize),
+    /// A filter was given no taps.
+    EmptyFilter,
+    /// The tap at this index is not finite, or does not fit the sample type.
+    InvalidTap(usize),
+    /// A branch of the filter bank blocks its passband, so its gain cannot
+    /// be compensated.
+    ZeroGain,
+}
+
+impl fmt::Display for QmfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QmfError::UnsupportedOrder(order) => write!(f, "unsupported wavelet order {order}"),
+            QmfError::EmptyFilter => write!(f, "filter has no taps"),
+            QmfError::InvalidTap(index) => write!(f, "tap {index} is not a finite sample value"),
+            QmfError::ZeroGain => write!(f, "filter bank branch has zero gain"),
+        }
+    }
+}
+
+impl core::error::Error for QmfError {}
@@ -0,0 +1,1057 @@
+use alloc::{collections::VecDeque, vec::Vec};
+use num_traits::{Float, ToPrimitive};
+
+use crate::{
+    design::{convolve, qmf},
+    error::QmfError,
+};
+
+/// A FIR filter with a length fixed at compile time.
+///
+/// Filters compare by value, but with float taps they are only `PartialEq`:
+///
+/// ```compile_fail
+/// fn assert_eq<T: Eq>() {}
+/// assert_eq::<qmf::HaarFilter<f64>>();
+/// ```
+///
+/// ```
+/// use qmf::HaarFilter;
+/// assert_eq!(HaarFilter::<f64>::new(0.5, 0.5), HaarFilter::new(0.5, 0.5));
+/// assert_ne!(HaarFilter::<f64>::new(0.5, 0.5), HaarFilter::new(0.5, -0.5));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T: serde::Serialize",
+        deserialize = "T: serde::Deserialize<'de>"
+    ))
+)]
+pub struct FirFilter<T, const TAPS: usize>
+where
+    T: Float,
+{
+    // ring buffer of the last `TAPS` inputs; `head` points at the newest one
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
+    pub(crate) history: [T; TAPS],
+    pub(crate) head: usize,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
+    taps: [T; TAPS],
+    // taps being ramped to over the next `remaining` samples
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
+    target: [T; TAPS],
+    pub(crate) remaining: usize,
+}
+
+impl<T, const TAPS: usize> FirFilter<T, TAPS>
+where
+    T: Float,
+{
+    pub fn from_taps<U: ToPrimitive>(taps: [U; TAPS]) -> Self {
+        assert!(TAPS > 0, "a FIR filter needs at least one tap");
+        let taps = taps.map(|h| T::from(h).unwrap());
+        Self {
+            history: [T::zero(); TAPS],
+            head: 0,
+            taps,
+            target: taps,
+            remaining: 0,
+        }
+    }
+
+    pub fn consume(&mut self, x: T) -> T {
+        ramp(&mut self.taps, &self.target, &mut self.remaining);
+        step(&mut self.history, &mut self.head, &self.taps, x)
+    }
+
+    /// Like `consume` without computing the output, see
+    /// [`FilterKernel::skip`].
+    pub fn skip(&mut self, x: T) {
+        ramp(&mut self.taps, &self.target, &mut self.remaining);
+        push(&mut self.history, &mut self.head, x)
+    }
+
+    /// See [`FilterKernel::consume_upsampled`].
+    pub fn consume_upsampled(&mut self, x: T, phase: usize, factor: usize) -> T {
+        ramp(&mut self.taps, &self.target, &mut self.remaining);
+        push(&mut self.history, &mut self.head, x);
+        sparse_output(&self.history, self.head, &self.taps, phase, factor)
+    }
+
+    /// Moves the taps to `taps` in a straight line over the next
+    /// `ramp_samples` calls to `consume`, reaching them on the last one, so
+    /// that retuning a running filter does not click. Zero switches at once.
+    pub fn set_taps(&mut self, taps: [T; TAPS], ramp_samples: usize) {
+        self.target = taps;
+        self.remaining = ramp_samples;
+        if ramp_samples == 0 {
+            self.taps = taps;
+        }
+    }
+
+    /// Rescales the taps, and those of a pending ramp, to a DC gain of
+    /// `target`. Fails with [`QmfError::ZeroGain`] if the filter blocks DC.
+    pub fn normalize_dc(&mut self, target: T) -> Result<(), QmfError> {
+        normalize_dc(&mut self.taps, &mut self.target, self.remaining, target)
+    }
+
+    /// Tapers the taps, and those of a pending ramp, by `window`, e.g. one
+    /// from the [`window`](crate::window) module, and rescales them to a DC
+    /// gain of one. Fails with [`QmfError::ZeroGain`], leaving the taps as
+    /// they were, if the windowed filter blocks DC.
+    pub fn apply_window(&mut self, window: &[T; TAPS]) -> Result<(), QmfError> {
+        apply_window(&mut self.taps, &mut self.target, self.remaining, window)
+    }
+
+    /// Streams a block through the filter, carrying its state across calls
+    /// exactly like repeated `consume`.
+    pub fn consume_slice(&mut self, xs: &[T], out: &mut [T]) {
+        assert_eq!(xs.len(), out.len(), "input and output lengths differ");
+        if TAPS == 2 && self.remaining == 0 {
+            return self.consume_pairs(xs.iter().copied().zip(out.iter_mut()));
+        }
+        for (&x, o) in xs.iter().zip(out.iter_mut()) {
+            *o = self.consume(x);
+        }
+    }
+
+    pub fn consume_in_place(&mut self, xs: &mut [T]) {
+        if TAPS == 2 && self.remaining == 0 {
+            return self.consume_pairs(xs.iter_mut().map(|x| (*x, x)));
+        }
+        for x in xs.iter_mut() {
+            *x = self.consume(*x);
+        }
+    }
+
+    // two fixed taps only need the input before each sample, so the ring
+    // buffer is written once at the end, as `consume` would have left it
+    fn consume_pairs<'a>(&mut self, samples: impl Iterator<Item = (T, &'a mut T)>)
+    where
+        T: 'a,
+    {
+        let (h0, h1) = (self.taps[0], self.taps[1]);
+        let mut older = self.history[(self.head + 1) % TAPS];
+        let mut prev = self.history[self.head];
+        let mut count = 0;
+        for (x, o) in samples {
+            *o = multiply_add(h1, prev, multiply_add(h0, x, T::zero()));
+            (older, prev) = (prev, x);
+            count += 1;
+        }
+        self.head = (self.head + count) % TAPS;
+        self.history[self.head] = prev;
+        self.history[(self.head + 1) % TAPS] = older;
+    }
+
+    /// Clears the delay line, keeping the taps.
+    pub fn reset(&mut self) {
+        self.history = [T::zero(); TAPS];
+        self.head = 0;
+    }
+
+    pub fn taps(&self) -> &[T; TAPS] {
+        &self.taps
+    }
+
+    /// Mutable taps for adjusting the filter while it streams. The history
+    /// is kept, and a pending [`set_taps`](Self::set_taps) ramp is dropped.
+    pub fn taps_mut(&mut self) -> &mut [T; TAPS] {
+        self.remaining = 0;
+        &mut self.taps
+    }
+
+    /// Magnitude and phase of `H(e^{jω}) = Σ h[k]·e^{-jωk}` at the normalized
+    /// angular frequency `omega` in `[0, π]`.
+    pub fn frequency_response(&self, omega: T) -> (T, T) {
+        frequency_response(&self.taps, omega)
+    }
+
+    /// Magnitude at `n_points` frequencies spread evenly from DC to Nyquist,
+    /// as `(frequency, magnitude)` pairs with the frequency in cycles per
+    /// sample, `0` to `0.5`.
+    pub fn magnitude_response(&self, n_points: usize) -> Vec<(T, T)> {
+        magnitude_response(&self.taps, n_points)
+    }
+
+    /// Magnitude at `normalized_freq` cycles per sample.
+    pub fn magnitude_at(&self, normalized_freq: T) -> T {
+        magnitude_at(&self.taps, normalized_freq)
+    }
+
+    /// Whether the taps are symmetric or antisymmetric to within `tolerance`,
+    /// which makes the phase linear. [`group_delay`](Self::group_delay)
+    /// insists on exact symmetry instead.
+    pub fn is_linear_phase(&self, tolerance: T) -> bool {
+        is_linear_phase(&self.taps, tolerance)
+    }
+
+    /// Group delay in samples if the filter has linear phase, i.e. its taps
+    /// are symmetric or antisymmetric, and `None` otherwise.
+    pub fn group_delay(&self) -> Option<T> {
+        group_delay(&self.taps)
+    }
+
+    /// Group delay `-dφ/dω` in samples at the angular frequency `omega`,
+    /// undefined where the response vanishes.
+    pub fn group_delay_at(&self, omega: T) -> T {
+        group_delay_at(&self.taps, omega)
+    }
+
+    /// See [`FilterKernel::impulse_response`].
+    #[must_use]
+    pub fn impulse_response(&self, n: usize) -> Vec<T> {
+        FilterKernel::impulse_response(self, n)
+    }
+
+    /// See [`FilterKernel::step_response`].
+    #[must_use]
+    pub fn step_response(&self, n: usize) -> Vec<T> {
+        FilterKernel::step_response(self, n)
+    }
+
+    /// Response at ω = 0, the sum of the taps.
+    pub fn dc_gain(&self) -> T {
+        dc_gain(&self.taps)
+    }
+
+    /// Response at ω = π, the alternating sum of the taps.
+    pub fn nyquist_gain(&self) -> T {
+        nyquist_gain(&self.taps)
+    }
+
+    /// The taps in reverse order, from silence. For an orthogonal filter
+    /// bank the synthesis filters are the reversed analysis ones.
+    pub fn reversed(&self) -> Self {
+        let mut taps = self.taps;
+        taps.reverse();
+        Self::from_taps(taps)
+    }
+
+    /// The conjugate quadrature mirror `(-1)^(n+1)·h[L-1-n]` of the taps,
+    /// from silence, as [`design::qmf`](crate::design::qmf). From an
+    /// orthogonal lowpass this gives the analysis highpass; the Haar lowpass
+    /// `[0.5, 0.5]` gives `[-0.5, 0.5]`.
+    pub fn qmf_mirror(&self) -> Self {
+        let mut taps = self.taps;
+        taps.copy_from_slice(&qmf(&self.taps));
+        Self::from_taps(taps)
+    }
+
+    /// The filter equivalent to running `self` and `other` in series, whose
+    /// taps are the convolution of theirs. Stable Rust cannot write its
+    /// length `TAPS + M - 1` in the signature, so it is a parameter checked
+    /// at compile time:
+    ///
+    /// ```
+    /// use qmf::HaarFilter;
+    /// let low = HaarFilter::<f64>::new(0.5, 0.5);
+    /// let cascade = low.convolve::<2, 3>(&low);
+    /// assert_eq!(&[0.25, 0.5, 0.25], cascade.taps());
+    /// ```
+    ///
+    /// ```compile_fail
+    /// use qmf::HaarFilter;
+    /// let low = HaarFilter::<f64>::new(0.5, 0.5);
+    /// let cascade = low.convolve::<2, 4>(&low);
+    /// ```
+    pub fn convolve<const M: usize, const OUT: usize>(
+        &self,
+        other: &FirFilter<T, M>,
+    ) -> FirFilter<T, OUT> {
+        const { assert!(OUT + 1 == TAPS + M, "a convolution has TAPS + M - 1 taps") };
+        let mut taps = [T::zero(); OUT];
+        convolve(&self.taps, &other.taps, &mut taps);
+        FirFilter::from_taps(taps)
+    }
+}
+
+/// A FIR filter whose length is chosen at runtime.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DynFirFilter<T>
+where
+    T: Float,
+{
+    history: Vec<T>,
+    head: usize,
+    taps: Vec<T>,
+    // taps being ramped to over the next `remaining` samples
+    target: Vec<T>,
+    remaining: usize,
+}
+
+impl<T> DynFirFilter<T>
+where
+    T: Float,
+{
+    pub fn from_taps<U: ToPrimitive + Copy>(taps: &[U]) -> Self {
+        assert!(!taps.is_empty(), "a FIR filter needs at least one tap");
+        let taps: Vec<T> = taps.iter().map(|&h| T::from(h).unwrap()).collect();
+        Self {
+            history: alloc::vec![T::zero(); taps.len()],
+            head: 0,
+            target: taps.clone(),
+            taps,
+            remaining: 0,
+        }
+    }
+
+    pub fn consume(&mut self, x: T) -> T {
+        ramp(&mut self.taps, &self.target, &mut self.remaining);
+        step(&mut self.history, &mut self.head, &self.taps, x)
+    }
+
+    /// Like `consume` without computing the output, see
+    /// [`FilterKernel::skip`].
+    pub fn skip(&mut self, x: T) {
+        ramp(&mut self.taps, &self.target, &mut self.remaining);
+        push(&mut self.history, &mut self.head, x)
+    }
+
+    /// See [`FilterKernel::consume_upsampled`].
+    pub fn consume_upsampled(&mut self, x: T, phase: usize, factor: usize) -> T {
+        ramp(&mut self.taps, &self.target, &mut self.remaining);
+        push(&mut self.history, &mut self.head, x);
+        sparse_output(&self.history, self.head, &self.taps, phase, factor)
+    }
+
+    /// See [`FirFilter::set_taps`].
+    ///
+    /// Panics unless `taps` is as long as the current taps.
+    pub fn set_taps(&mut self, taps: &[T], ramp_samples: usize) {
+        assert_eq!(self.taps.len(), taps.len(), "the filter length is fixed");
+        self.target.copy_from_slice(taps);
+        self.remaining = ramp_samples;
+        if ramp_samples == 0 {
+            self.taps.copy_from_slice(taps);
+        }
+    }
+
+    /// See [`FirFilter::normalize_dc`].
+    pub fn normalize_dc(&mut self, target: T) -> Result<(), QmfError> {
+        normalize_dc(&mut self.taps, &mut self.target, self.remaining, target)
+    }
+
+    /// See [`FirFilter::apply_window`].
+    ///
+    /// Panics unless `window` is as long as the taps.
+    pub fn apply_window(&mut self, window: &[T]) -> Result<(), QmfError> {
+        assert_eq!(self.taps.len(), window.len(), "one window point per tap");
+        apply_window(&mut self.taps, &mut self.target, self.remaining, window)
+    }
+
+    /// Streams a block through the filter, carrying its state across calls
+    /// exactly like repeated `consume`.
+    pub fn consume_slice(&mut self, xs: &[T], out: &mut [T]) {
+        assert_eq!(xs.len(), out.len(), "input and output lengths differ");
+        for (&x, o) in xs.iter().zip(out.iter_mut()) {
+            *o = self.consume(x);
+        }
+    }
+
+    pub fn consume_in_place(&mut self, xs: &mut [T]) {
+        for x in xs.iter_mut() {
+            *x = self.consume(*x);
+        }
+    }
+
+    /// Clears the delay line, keeping the taps.
+    pub fn reset(&mut self) {
+        self.history.fill(T::zero());
+        self.head = 0;
+    }
+
+    pub fn taps(&self) -> &[T] {
+        &self.taps
+    }
+
+    /// See [`FirFilter::taps_mut`].
+    pub fn taps_mut(&mut self) -> &mut [T] {
+        self.remaining = 0;
+        &mut self.taps
+    }
+
+    /// See [`FirFilter::frequency_response`].
+    pub fn frequency_response(&self, omega: T) -> (T, T) {
+        frequency_response(&self.taps, omega)
+    }
+
+    pub fn magnitude_response(&self, n_points: usize) -> Vec<(T, T)> {
+        magnitude_response(&self.taps, n_points)
+    }
+
+    pub fn magnitude_at(&self, normalized_freq: T) -> T {
+        magnitude_at(&self.taps, normalized_freq)
+    }
+
+    pub fn is_linear_phase(&self, tolerance: T) -> bool {
+        is_linear_phase(&self.taps, tolerance)
+    }
+
+    pub fn group_delay(&self) -> Option<T> {
+        group_delay(&self.taps)
+    }
+
+    pub fn group_delay_at(&self, omega: T) -> T {
+        group_delay_at(&self.taps, omega)
+    }
+
+    #[must_use]
+    pub fn impulse_response(&self, n: usize) -> Vec<T> {
+        FilterKernel::impulse_response(self, n)
+    }
+
+    #[must_use]
+    pub fn step_response(&self, n: usize) -> Vec<T> {
+        FilterKernel::step_response(self, n)
+    }
+
+    pub fn dc_gain(&self) -> T {
+        dc_gain(&self.taps)
+    }
+
+    pub fn nyquist_gain(&self) -> T {
+        nyquist_gain(&self.taps)
+    }
+
+    /// See [`FirFilter::reversed`].
+    pub fn reversed(&self) -> Self {
+        let taps: Vec<T> = self.taps.iter().rev().copied().collect();
+        Self::from_taps(&taps)
+    }
+
+    /// See [`FirFilter::qmf_mirror`].
+    pub fn qmf_mirror(&self) -> Self {
+        Self::from_taps(&qmf(&self.taps))
+    }
+
+    /// See [`FirFilter::convolve`].
+    pub fn convolve(&self, other: &DynFirFilter<T>) -> DynFirFilter<T> {
+        let mut taps = alloc::vec![T::zero(); self.taps.len() + other.taps.len() - 1];
+        convolve(&self.taps, &other.taps, &mut taps);
+        DynFirFilter::from_taps(&taps)
+    }
+}
+
+/// The per-sample interface a [`Bands`](crate::Bands) level needs from each of
+/// its four filters.
+pub trait FilterKernel<T> {
+    fn consume(&mut self, x: T) -> T;
+
+    /// Feeds `x` when its output is not needed, as for the samples a
+    /// decimator drops. The FIR filters only advance their delay line.
+    fn skip(&mut self, x: T) {
+        self.consume(x);
+    }
+
+    /// Like `consume` for a stream upsampled by `factor` with zeros, where
+    /// `x` comes `phase` samples after the latest real sample. The FIR
+    /// filters only multiply the taps that meet real samples, which gives
+    /// the same output as long as the whole history followed that pattern.
+    fn consume_upsampled(&mut self, x: T, phase: usize, factor: usize) -> T {
+        let _ = (phase, factor);
+        self.consume(x)
+    }
+
+    /// Clears the filter state, keeping the coefficients.
+    fn reset(&mut self);
+
+    /// Length of the impulse response, or a bound on where it has died out.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The first `n` samples of the response to a unit impulse, run on a
+    /// reset copy so the filter itself is left untouched.
+    #[must_use]
+    fn impulse_response(&self, n: usize) -> Vec<T>
+    where
+        Self: Clone,
+        T: Float,
+    {
+        response(self, n, |k| if k == 0 { T::one() } else { T::zero() })
+    }
+
+    /// The first `n` samples of the response to a unit step, which settles
+    /// at the DC gain.
+    #[must_use]
+    fn step_response(&self, n: usize) -> Vec<T>
+    where
+        Self: Clone,
+        T: Float,
+    {
+        response(self, n, |_| T::one())
+    }
+}
+
+fn response<T, K>(kernel: &K, n: usize, input: impl Fn(usize) -> T) -> Vec<T>
+where
+    K: FilterKernel<T> + Clone,
+{
+    let mut kernel = kernel.clone();
+    kernel.reset();
+    (0..n).map(|k| kernel.consume(input(k))).collect()
+}
+
+impl<T, const TAPS: usize> FilterKernel<T> for FirFilter<T, TAPS>
+where
+    T: Float,
+{
+    fn consume(&mut self, x: T) -> T {
+        FirFilter::consume(self, x)
+    }
+
+    fn skip(&mut self, x: T) {
+        FirFilter::skip(self, x)
+    }
+
+    fn consume_upsampled(&mut self, x: T, phase: usize, factor: usize) -> T {
+        FirFilter::consume_upsampled(self, x, phase, factor)
+    }
+
+    fn reset(&mut self) {
+        FirFilter::reset(self)
+    }
+
+    fn len(&self) -> usize {
+        TAPS
+    }
+}
+
+impl<T> FilterKernel<T> for DynFirFilter<T>
+where
+    T: Float,
+{
+    fn consume(&mut self, x: T) -> T {
+        DynFirFilter::consume(self, x)
+    }
+
+    fn skip(&mut self, x: T) {
+        DynFirFilter::skip(self, x)
+    }
+
+    fn consume_upsampled(&mut self, x: T, phase: usize, factor: usize) -> T {
+        DynFirFilter::consume_upsampled(self, x, phase, factor)
+    }
+
+    fn reset(&mut self) {
+        DynFirFilter::reset(self)
+    }
+
+    fn len(&self) -> usize {
+        self.taps.len()
+    }
+}
+
+/// Delays its input by a fixed number of samples.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DelayLine<T> {
+    buffer: VecDeque<T>,
+}
+
+impl<T> DelayLine<T>
+where
+    T: Float,
+{
+    pub fn new(len: usize) -> Self {
+        Self {
+            buffer: VecDeque::from(alloc::vec![T::zero(); len]),
+        }
+    }
+
+    pub fn consume(&mut self, x: T) -> T {
+        // popping first keeps the buffer from ever growing
+        let Some(y) = self.buffer.pop_front() else {
+            return x;
+        };
+        self.buffer.push_back(x);
+        y
+    }
+
+    pub fn reset(&mut self) {
+        self.buffer.iter_mut().for_each(|x| *x = T::zero());
+    }
+}
+
+impl<T> FilterKernel<T> for DelayLine<T>
+where
+    T: Float,
+{
+    fn consume(&mut self, x: T) -> T {
+        DelayLine::consume(self, x)
+    }
+
+    fn reset(&mut self) {
+        DelayLine::reset(self)
+    }
+
+    fn len(&self) -> usize {
+        self.buffer.len() + 1
+    }
+}
+
+// the ramp target only counts while a ramp is pending
+fn normalize_dc<T: Float>(
+    taps: &mut [T],
+    ramp: &mut [T],
+    remaining: usize,
+    target: T,
+) -> Result<(), QmfError> {
+    let gain = dc_gain(taps);
+    let ramp_gain = if remaining > 0 { dc_gain(ramp) } else { gain };
+    if !gain.is_normal() || !ramp_gain.is_normal() {
+        return Err(QmfError::ZeroGain);
+    }
+    let (scale, ramp_scale) = (target / gain, target / ramp_gain);
+    taps.iter_mut().for_each(|h| *h = *h * scale);
+    if remaining > 0 {
+        ramp.iter_mut().for_each(|h| *h = *h * ramp_scale);
+    } else {
+        ramp.copy_from_slice(taps);
+    }
+    Ok(())
+}
+
+fn apply_window<T: Float>(
+    taps: &mut [T],
+    ramp: &mut [T],
+    remaining: usize,
+    window: &[T],
+) -> Result<(), QmfError> {
+    // checked before touching the taps, so a failure leaves them as they were
+    let windowed_gain = |taps: &[T]| {
+        taps.iter()
+            .zip(window)
+            .fold(T::zero(), |acc, (&h, &w)| acc + h * w)
+    };
+    if !windowed_gain(taps).is_normal() || (remaining > 0 && !windowed_gain(ramp).is_normal()) {
+        return Err(QmfError::ZeroGain);
+    }
+    taps.iter_mut().zip(window).for_each(|(h, &w)| *h = *h * w);
+    if remaining > 0 {
+        ramp.iter_mut().zip(window).for_each(|(h, &w)| *h = *h * w);
+    }
+    normalize_dc(taps, ramp, remaining, T::one())
+}
+
+// one step of the linear ramp from the current taps to the target
+fn ramp<T: Float>(taps: &mut [T], target: &[T], remaining: &mut usize) {
+    if *remaining == 0 {
+        return;
+    }
+    let steps = T::from(*remaining).unwrap();
+    for (h, &t) in taps.iter_mut().zip(target.iter()) {
+        *h = *h + (t - *h) / steps;
+    }
+    *remaining -= 1;
+}
+
+fn push<T: Float>(history: &mut [T], head: &mut usize, x: T) {
+    *head = (*head + 1) % history.len();
+    history[*head] = x;
+}
+
+fn step<T: Float>(history: &mut [T], head: &mut usize, taps: &[T], x: T) -> T {
+    push(history, head, x);
+
+    let (newer, older) = history.split_at(*head + 1);
+    let delayed = newer.iter().rev().chain(older.iter().rev());
+    taps.iter()
+        .zip(delayed)
+        .fold(T::zero(), |acc, (&h, &x)| multiply_add(h, x, acc))
+}
+
+// `step` after the push, over the lags `phase`, `phase + factor`, ... only
+fn sparse_output<T: Float>(
+    history: &[T],
+    head: usize,
+    taps: &[T],
+    phase: usize,
+    factor: usize,
+) -> T {
+    let len = history.len();
+    let mut acc = T::zero();
+    for lag in (phase..taps.len()).step_by(factor) {
+        let i = if lag <= head {
+            head - lag
+        } else {
+            head + len - lag
+        };
+        acc = multiply_add(taps[lag], history[i], acc);
+    }
+    acc
+}
+
+/// `h * x + acc`, fused into one rounding with the `fma` feature.
+#[inline]
+pub(crate) fn multiply_add<T: Float>(h: T, x: T, acc: T) -> T {
+    if cfg!(feature = "fma") {
+        h.mul_add(x, acc)
+    } else {
+        acc + h * x
+    }
+}
+
+fn frequency_response<T: Float>(taps: &[T], omega: T) -> (T, T) {
+    let (re, im) = taps
+        .iter()
+        .enumerate()
+        .fold((T::zero(), T::zero()), |(re, im), (k, &h)| {
+            let (sin, cos) = (omega * T::from(k).unwrap()).sin_cos();
+            (re + h * cos, im - h * sin)
+        });
+    (re.hypot(im), im.atan2(re))
+}
+
+fn magnitude_at<T: Float>(taps: &[T], normalized_freq: T) -> T {
+    let two_pi = T::from(2. * core::f64::consts::PI).unwrap();
+    frequency_response(taps, two_pi * normalized_freq).0
+}
+
+fn magnitude_response<T: Float>(taps: &[T], n_points: usize) -> Vec<(T, T)> {
+    let last = T::from(n_points.saturating_sub(1).max(1)).unwrap();
+    let half = T::from(0.5).unwrap();
+    (0..n_points)
+        .map(|i| {
+            let f = half * T::from(i).unwrap() / last;
+            (f, magnitude_at(taps, f))
+        })
+        .collect()
+}
+
+fn is_linear_phase<T: Float>(taps: &[T], tolerance: T) -> bool {
+    let mirrored = || taps.iter().zip(taps.iter().rev());
+    let symmetric = mirrored().all(|(&a, &b)| (a - b).abs() <= tolerance);
+    let antisymmetric = mirrored().all(|(&a, &b)| (a + b).abs() <= tolerance);
+    symmetric || antisymmetric
+}
+
+fn group_delay<T: Float>(taps: &[T]) -> Option<T> {
+    is_linear_phase(taps, T::zero()).then(|| T::from(taps.len() - 1).unwrap() / T::from(2).unwrap())
+}
+
+// Re(Σ k·h[k]·e^{-jωk} · conj(H)) / |H|²
+fn group_delay_at<T: Float>(taps: &[T], omega: T) -> T {
+    let (re, im, ramp_re, ramp_im) = taps.iter().enumerate().fold(
+        (T::zero(), T::zero(), T::zero(), T::zero()),
+        |(re, im, ramp_re, ramp_im), (k, &h)| {
+            let k = T::from(k).unwrap();
+            let (sin, cos) = (omega * k).sin_cos();
+            (
+                re + h * cos,
+                im - h * sin,
+                ramp_re + k * h * cos,
+                ramp_im - k * h * sin,
+            )
+        },
+    );
+    (ramp_re * re + ramp_im * im) / (re * re + im * im)
+}
+
+pub(crate) fn dc_gain<T: Float>(taps: &[T]) -> T {
+    taps.iter().fold(T::zero(), |acc, &h| acc + h)
+}
+
+pub(crate) fn nyquist_gain<T: Float>(taps: &[T]) -> T {
+    taps.iter().enumerate().fold(
+        T::zero(),
+        |acc, (k, &h)| if k % 2 == 0 { acc + h } else { acc - h },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DynFirFilter, FirFilter};
+    use crate::{
+        design::{check_perfect_reconstruction, qmf},
+        error::QmfError,
+        wavelet::{Normalization, Wavelet},
+        window::hann,
+    };
+    use core::f64::consts::PI;
+
+    #[test]
+    fn test_reversed_and_qmf_mirror() {
+        let low = FirFilter::<f64, 2>::from_taps([0.5, 0.5]);
+        assert_eq!(&[-0.5, 0.5], low.qmf_mirror().taps());
+        let high = FirFilter::<f64, 2>::from_taps([-0.5, 0.5]);
+        assert_eq!(&[0.5, -0.5], high.reversed().taps());
+
+        // an orthonormal prototype gives a perfect reconstruction quadruple
+        let filters = Wavelet::daubechies::<f64>(3)
+            .unwrap()
+            .normalized(Normalization::Orthonormal);
+        let h0 = DynFirFilter::from_taps(filters.analysis_low());
+        let h1 = h0.qmf_mirror();
+        let (g0, g1) = (h0.reversed(), h1.reversed());
+        let report =
+            check_perfect_reconstruction(h0.taps(), h1.taps(), g0.taps(), g1.taps(), 1e-12);
+        assert!(report.passed, "{report:?}");
+        assert_eq!(qmf(h0.taps()), h1.taps());
+    }
+
+    #[test]
+    fn test_convolve() {
+        let low = FirFilter::<f64, 2>::from_taps([0.5, 0.5]);
+        let high = FirFilter::<f64, 2>::from_taps([1., -1.]);
+        let cascade = low.convolve::<2, 3>(&high);
+        assert_eq!(&[0.5, 0., -0.5], cascade.taps());
+
+        // running the convolution is running both in series
+        let mut series = (low.clone(), high.clone());
+        let mut dynamic =
+            DynFirFilter::from_taps(low.taps()).convolve(&DynFirFilter::from_taps(high.taps()));
+        assert_eq!(cascade.taps(), dynamic.taps());
+        for x in [1., 3., -2., 0.5, 0., 0.] {
+            let y = series.1.consume(series.0.consume(x));
+            assert_eq!(y, dynamic.consume(x));
+        }
+    }
+
+    #[test]
+    fn test_impulse_response() {
+        let mut filter = FirFilter::<f64, 4>::from_taps([0.25, -0.5, 1., 2.]);
+        let response: Vec<_> = [1., 0., 0., 0., 0., 0.]
+            .into_iter()
+            .map(|x| filter.consume(x))
+            .collect();
+        assert_eq!(vec![0.25, -0.5, 1., 2., 0., 0.], response);
+    }
+
+    #[test]
+    fn test_response_helpers_keep_state() {
+        let mut filter = DynFirFilter::from_taps(&[0.25, -0.5, 1., 2.]);
+        filter.consume(3.);
+        assert_eq!(vec![0.25, -0.5, 1., 2., 0.], filter.impulse_response(5));
+        assert_eq!(vec![0.25, -0.25, 0.75, 2.75, 2.75], filter.step_response(5));
+        // the history still holds the 3
+        assert_eq!(-1.5, filter.consume(0.));
+    }
+
+    #[test]
+    fn test_state_continuity() {
+        let xs = [1., 2., 3., 4., 5., 6., 7.];
+
+        let mut whole = FirFilter::<f64, 3>::from_taps([1., 2., 3.]);
+        let expected: Vec<_> = xs.iter().map(|&x| whole.consume(x)).collect();
+
+        let mut split = FirFilter::<f64, 3>::from_taps([1., 2., 3.]);
+        let mut actual: Vec<_> = xs[..2].iter().map(|&x| split.consume(x)).collect();
+        actual.extend(xs[2..].iter().map(|&x| split.consume(x)));
+        assert_eq!(expected, actual);
+
+        split.reset();
+        assert_eq!(1., split.consume(1.));
+    }
+
+    #[test]
+    fn test_identity() {
+        let mut filter = FirFilter::<f64, 3>::from_taps([1, 0, 0]);
+        for x in [3., -1., 4., 1., -5.] {
+            assert_eq!(x, filter.consume(x));
+        }
+    }
+
+    #[test]
+    fn test_moving_average() {
+        let third = 1. / 3.;
+        let mut filter = FirFilter::<f64, 3>::from_taps([third, third, third]);
+        let out: Vec<_> = [3., 6., 9., 12., 15.]
+            .into_iter()
+            .map(|x| filter.consume(x))
+            .collect();
+        let expected = [1., 3., 6., 9., 12.];
+        for (o, e) in out.iter().zip(expected.iter()) {
+            assert!((o - e).abs() < 1e-12, "{o} != {e}");
+        }
+    }
+
+    #[test]
+    fn test_consume_slice_matches_consume() {
+        let xs: Vec<f64> = (0..37).map(|i| ((i * 13 % 7) as f64).sin()).collect();
+
+        let mut element = FirFilter::<f64, 2>::from_taps([0.5, -0.5]);
+        let expected: Vec<_> = xs.iter().map(|&x| element.consume(x)).collect();
+
+        let mut block = FirFilter::<f64, 2>::from_taps([0.5, -0.5]);
+        let mut out = vec![0.; xs.len()];
+        block.consume_slice(&xs[..10], &mut out[..10]);
+        block.consume_slice(&xs[10..], &mut out[10..]);
+        assert_eq!(expected, out);
+
+        let mut in_place = DynFirFilter::<f64>::from_taps(&[0.5, -0.5]);
+        let mut data = xs.clone();
+        in_place.consume_in_place(&mut data[..3]);
+        in_place.consume_in_place(&mut data[3..]);
+        assert_eq!(expected, data);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_consume_slice_length_mismatch() {
+        let mut filter = FirFilter::<f64, 2>::from_taps([0.5, 0.5]);
+        filter.consume_slice(&[1., 2., 3.], &mut [0.; 2]);
+    }
+
+    #[test]
+    fn test_dyn_matches_fixed() {
+        let mut fixed = FirFilter::<f64, 3>::from_taps([0.5, -1., 0.25]);
+        let mut dynamic = DynFirFilter::<f64>::from_taps(&[0.5, -1., 0.25]);
+        for x in [1., 0., -2., 3., 0.5, 0., 0.] {
+            assert_eq!(fixed.consume(x), dynamic.consume(x));
+        }
+    }
+
+    #[test]
+    fn test_magnitude_response() {
+        let lowpass = FirFilter::<f64, 2>::from_taps([0.5, 0.5]);
+        let response = lowpass.magnitude_response(65);
+        assert_eq!(65, response.len());
+        assert_eq!((0., 1.), response[0]);
+        assert_eq!(0.5, response[64].0);
+        assert!(response[64].1 < 1e-15);
+        assert!(response.windows(2).all(|w| w[1].1 <= w[0].1));
+        assert!((lowpass.magnitude_at(0.25) - core::f64::consts::FRAC_1_SQRT_2).abs() < 1e-15);
+
+        // the highpass mirrors it about a quarter of the sample rate
+        let highpass = DynFirFilter::<f64>::from_taps(&[-0.5, 0.5]);
+        for ((f, low), (g, high)) in response
+            .iter()
+            .zip(highpass.magnitude_response(65).iter().rev())
+        {
+            assert!((f + g - 0.5).abs() < 1e-15);
+            assert!((low - high).abs() < 1e-15);
+        }
+        assert!(highpass.magnitude_at(0.) < 1e-15);
+        assert!((highpass.magnitude_at(0.5) - 1.).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_group_delay() {
+        let lowpass = FirFilter::<f64, 2>::from_taps([0.5, 0.5]);
+        assert_eq!(Some(0.5), lowpass.group_delay());
+        assert!((lowpass.group_delay_at(1.) - 0.5).abs() < 1e-15);
+
+        let highpass = DynFirFilter::<f64>::from_taps(&[-0.5, 0., 0.5]);
+        assert_eq!(Some(1.), highpass.group_delay());
+
+        assert!(lowpass.is_linear_phase(0.) && highpass.is_linear_phase(0.));
+        let asymmetric = FirFilter::<f64, 2>::from_taps([0.7, 0.3]);
+        assert!(!asymmetric.is_linear_phase(0.1));
+        assert!(asymmetric.is_linear_phase(0.4));
+        assert_eq!(None, asymmetric.group_delay());
+
+        // a minimum phase filter lags less than its linear phase counterpart
+        let skewed = DynFirFilter::<f64>::from_taps(&[1., 0.5]);
+        assert_eq!(None, skewed.group_delay());
+        assert!((skewed.group_delay_at(0.) - 1. / 3.).abs() < 1e-15);
+        assert!((skewed.group_delay_at(core::f64::consts::PI) + 1.).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_fused_and_plain_agree() {
+        let taps = crate::test_util::white_noise(31, 47);
+        let input = crate::test_util::white_noise(500, 74);
+        let mut filter = DynFirFilter::<f64>::from_taps(&taps);
+        for n in 0..input.len() {
+            let y = filter.consume(input[n]);
+            let window = (0..taps.len()).map(|k| if k <= n { input[n - k] } else { 0. });
+            let (plain, fused) = taps
+                .iter()
+                .zip(window)
+                .fold((0., 0.), |(p, f), (&h, x)| (p + h * x, h.mul_add(x, f)));
+            let scale = taps.iter().map(|h| h.abs()).sum::<f64>();
+            assert!((y - plain).abs() <= 16. * f64::EPSILON * scale);
+            assert!((y - fused).abs() <= 16. * f64::EPSILON * scale);
+        }
+    }
+
+    #[test]
+    fn test_set_taps_ramps() {
+        // a constant input makes the output follow the DC gain of the taps
+        let mut instant = FirFilter::<f64, 2>::from_taps([0.5, 0.5]);
+        let mut ramped = DynFirFilter::<f64>::from_taps(&[0.5, 0.5]);
+        let (mut jump, mut worst) = (0f64, 0f64);
+        let (mut prev_instant, mut prev_ramped) = (0., 0.);
+        for n in 0..200 {
+            if n == 50 {
+                instant.set_taps([1., 1.], 0);
+                ramped.set_taps(&[1., 1.], 64);
+            }
+            let (a, b) = (instant.consume(1.), ramped.consume(1.));
+            if n > 1 {
+                jump = jump.max((a - prev_instant).abs());
+                worst = worst.max((b - prev_ramped).abs());
+            }
+            (prev_instant, prev_ramped) = (a, b);
+        }
+        assert_eq!(1., jump);
+        assert!(worst <= 1. / 64. + 1e-12, "{worst}");
+        assert_eq!(&[1., 1.], ramped.taps());
+        assert_eq!(2., prev_ramped);
+    }
+
+    #[test]
+    fn test_normalize_dc() {
+        let mut filter = FirFilter::<f64, 3>::from_taps([0.5, 1., 0.5]);
+        filter.consume(4.);
+        filter.normalize_dc(1.).unwrap();
+        assert_eq!(&[0.25, 0.5, 0.25], filter.taps());
+        // the history is kept
+        assert_eq!(2., filter.consume(0.));
+        assert_eq!(
+            Err(QmfError::ZeroGain),
+            FirFilter::from_taps([1., -1.]).normalize_dc(1.)
+        );
+
+        let mut dynamic = DynFirFilter::from_taps(&[0.5, 0.5]);
+        dynamic.set_taps(&[2., 2.], 4);
+        dynamic.normalize_dc(3.).unwrap();
+        assert_eq!(&[1.5, 1.5], dynamic.taps());
+        let settled: Vec<f64> = (0..8).map(|_| dynamic.consume(1.)).collect();
+        assert!(
+            settled[1..].iter().all(|&y| (y - 3.).abs() < 1e-12),
+            "{settled:?}"
+        );
+    }
+
+    #[test]
+    fn test_apply_window() {
+        // a truncated sinc, the rectangular window design with cutoff 0.2
+        let sinc: [f64; 31] = core::array::from_fn(|n| {
+            let t = n as f64 - 15.;
+            if t == 0. {
+                0.4
+            } else {
+                (0.4 * PI * t).sin() / (PI * t)
+            }
+        });
+        let rectangular = FirFilter::<f64, 31>::from_taps(sinc);
+        let mut filter = rectangular.clone();
+        filter.apply_window(&hann()).unwrap();
+        assert!((filter.dc_gain() - 1.).abs() < 1e-12);
+        // the taps fall off faster towards the ends, which lowers the sidelobes
+        let edge_ratio = |taps: &[f64; 31]| (taps[1] / taps[15]).abs();
+        assert!(edge_ratio(filter.taps()) < edge_ratio(rectangular.taps()));
+        let sidelobe = |filter: &FirFilter<f64, 31>| {
+            (0..=50)
+                .map(|i| filter.magnitude_at(0.3 + 0.2 * i as f64 / 50.))
+                .fold(0., f64::max)
+        };
+        assert!(sidelobe(&filter) < sidelobe(&rectangular) / 10.);
+
+        let mut dynamic = DynFirFilter::<f64>::from_taps(&sinc);
+        dynamic.apply_window(&hann::<f64, 31>()).unwrap();
+        assert_eq!(filter.taps()[..], dynamic.taps()[..]);
+
+        let mut blocking = FirFilter::<f64, 3>::from_taps([1., 0., -1.]);
+        assert_eq!(Err(QmfError::ZeroGain), blocking.apply_window(&hann()));
+        assert_eq!(&[1., 0., -1.], blocking.taps());
+    }
+}
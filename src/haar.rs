@@ -1,28 +1,355 @@
 use num_traits::{Float, ToPrimitive};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct HaarFilter<T>
-where
-    T: Float,
-{
-    prev: T,
-    taps: [T; 2],
-}
+use crate::{error::QmfError, fir::FirFilter};
+
+pub type HaarFilter<T> = FirFilter<T, 2>;
 
-impl<T> HaarFilter<T>
+impl<T> FirFilter<T, 2>
 where
     T: Float,
 {
+    /// Panics where [`try_new`](Self::try_new) fails.
     pub fn new(h0: impl ToPrimitive, h1: impl ToPrimitive) -> Self {
+        Self::try_new(h0, h1).expect("invalid Haar filter taps")
+    }
+
+    /// Like `new`, but rejects taps that are not finite or cannot be
+    /// represented as `T`, e.g. values read from a config file.
+    pub fn try_new(h0: impl ToPrimitive, h1: impl ToPrimitive) -> Result<Self, QmfError> {
+        let convert = |index, h: &dyn ToPrimitive| -> Result<T, QmfError> {
+            h.to_f64()
+                .and_then(T::from)
+                .filter(|h: &T| h.is_finite())
+                .ok_or(QmfError::InvalidTap(index))
+        };
+        Ok(Self::from_taps([convert(0, &h0)?, convert(1, &h1)?]))
+    }
+
+    /// The most recent input sample (not output), which the next `consume`
+    /// weighs with the second tap.
+    pub fn prev(&self) -> T {
+        self.history[self.head]
+    }
+
+    /// Starts the filter from a nonzero initial condition, e.g. the last
+    /// sample of the previous block when stitching block-processed streams.
+    pub fn with_prev(mut self, prev: T) -> Self {
+        self.history[self.head] = prev;
+        self
+    }
+
+    /// Same as [`consume_slice`](FirFilter::consume_slice).
+    pub fn process(&mut self, input: &[T], output: &mut [T]) {
+        self.consume_slice(input, output)
+    }
+
+    /// Same as [`consume_in_place`](FirFilter::consume_in_place).
+    pub fn process_in_place(&mut self, xs: &mut [T]) {
+        self.consume_in_place(xs)
+    }
+}
+
+/// A fixed point [`HaarFilter`] for targets without an FPU, with Q15 taps
+/// and state.
+///
+/// A Q15 value `q` stands for `q / 32768`, so the analysis taps ±0.5 are
+/// exactly ±0x4000. The unit synthesis taps cannot be represented and
+/// saturate to 0x7FFF, one LSB short of 1.0, which keeps the output within
+/// one LSB of the exact result. Products accumulate in `i32`, saturating, are
+/// rounded to nearest and saturate to the `i16` range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HaarFilterQ15 {
+    taps: [i16; 2],
+    prev: i16,
+}
+
+impl HaarFilterQ15 {
+    pub fn new(h0: i16, h1: i16) -> Self {
         Self {
-            prev: T::zero(),
-            taps: [T::from(h0).unwrap(), T::from(h1).unwrap()],
+            taps: [h0, h1],
+            prev: 0,
         }
     }
 
-    pub fn consume(&mut self, x: T) -> T {
-        let ret = self.taps[0] * x + self.taps[1] * self.prev;
+    /// Quantizes real taps to Q15, saturating those outside `[-1, 1)`.
+    pub fn from_f64(h0: f64, h1: f64) -> Self {
+        let quantize = |h: f64| (h * 32768.).round().clamp(i16::MIN as f64, i16::MAX as f64) as i16;
+        Self::new(quantize(h0), quantize(h1))
+    }
+
+    pub fn consume(&mut self, x: i16) -> i16 {
+        let [h0, h1] = self.taps;
+        // each product fits, but with both taps and samples at -1 their sum
+        // does not
+        let acc = (h0 as i32 * x as i32).saturating_add(h1 as i32 * self.prev as i32);
+        self.prev = x;
+        (acc.saturating_add(1 << 14) >> 15).clamp(i16::MIN as i32, i16::MAX as i32) as i16
+    }
+
+    pub fn reset(&mut self) {
+        self.prev = 0;
+    }
+
+    pub fn taps(&self) -> &[i16; 2] {
+        &self.taps
+    }
+
+    /// The most recent input sample.
+    pub fn prev(&self) -> i16 {
+        self.prev
+    }
+}
+
+/// A fixed point [`HaarFilter`] on `i32` samples with `frac_bits` fractional
+/// bits in the taps, for a [`FixedHaarBand`](crate::FixedHaarBand).
+///
+/// Products accumulate in `i64`, saturating, and are shifted back by `frac_bits` with an
+/// arithmetic shift, rounding halves up, before saturating to the `i32`
+/// range. With one fractional bit the analysis taps ±0.5 are ±1, so the
+/// average of two samples is their sum shifted right once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedHaarFilter {
+    taps: [i32; 2],
+    frac_bits: u32,
+    prev: i32,
+}
+
+impl FixedHaarFilter {
+    /// Panics unless `frac_bits` is at most 30, which leaves room for the
+    /// unit taps of the synthesis filters.
+    pub fn new(h0: i32, h1: i32, frac_bits: u32) -> Self {
+        assert!(frac_bits <= 30, "at most 30 fractional bits");
+        Self {
+            taps: [h0, h1],
+            frac_bits,
+            prev: 0,
+        }
+    }
+
+    /// Quantizes real taps, rounding to nearest and saturating.
+    pub fn from_f64(h0: f64, h1: f64, frac_bits: u32) -> Self {
+        let scale = (1u64 << frac_bits.min(30)) as f64;
+        let quantize = |h: f64| (h * scale).round().clamp(i32::MIN as f64, i32::MAX as f64) as i32;
+        Self::new(quantize(h0), quantize(h1), frac_bits)
+    }
+
+    pub fn consume(&mut self, x: i32) -> i32 {
+        let [h0, h1] = self.taps;
+        // as for the Q15 filter, only the sum of the products can overflow
+        let acc = (h0 as i64 * x as i64).saturating_add(h1 as i64 * self.prev as i64);
         self.prev = x;
-        ret
+        let half = (1i64 << self.frac_bits) >> 1;
+        (acc.saturating_add(half) >> self.frac_bits).clamp(i32::MIN as i64, i32::MAX as i64) as i32
+    }
+
+    pub fn reset(&mut self) {
+        self.prev = 0;
+    }
+
+    pub fn taps(&self) -> &[i32; 2] {
+        &self.taps
+    }
+
+    pub fn frac_bits(&self) -> u32 {
+        self.frac_bits
+    }
+
+    /// The most recent input sample.
+    pub fn prev(&self) -> i32 {
+        self.prev
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FixedHaarFilter, HaarFilter, HaarFilterQ15};
+    use crate::{error::QmfError, test_util::white_noise};
+
+    #[test]
+    fn test_reset() {
+        let mut filter: HaarFilter<f64> = HaarFilter::new(0.5, 0.5);
+        for x in [1., 2., 3., 4.] {
+            filter.consume(x);
+        }
+        filter.reset();
+        assert_eq!(0., filter.consume(0.));
+        assert_eq!(0., filter.consume(0.));
+    }
+
+    #[test]
+    fn test_accessors() {
+        let mut filter: HaarFilter<f64> = HaarFilter::new(0.5, -0.25);
+        assert_eq!(&[0.5, -0.25], filter.taps());
+        assert_eq!(0., filter.prev());
+
+        assert_eq!(0.5, filter.consume(1.));
+        assert_eq!(1., filter.prev());
+        filter.consume(3.);
+        assert_eq!(3., filter.prev());
+    }
+
+    #[test]
+    fn test_with_prev_stitches_blocks() {
+        let xs = [1., 4., -2., 8., 5., 7.];
+
+        let mut whole: HaarFilter<f64> = HaarFilter::new(-0.5, 0.5);
+        let expected: Vec<_> = xs.iter().map(|&x| whole.consume(x)).collect();
+
+        let mut first: HaarFilter<f64> = HaarFilter::new(-0.5, 0.5);
+        let mut actual: Vec<_> = xs[..3].iter().map(|&x| first.consume(x)).collect();
+        let mut second = HaarFilter::new(-0.5, 0.5).with_prev(first.prev());
+        actual.extend(xs[3..].iter().map(|&x| second.consume(x)));
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_frequency_response() {
+        let lowpass: HaarFilter<f64> = HaarFilter::new(0.5, 0.5);
+        assert_eq!(1., lowpass.dc_gain());
+        assert_eq!(0., lowpass.nyquist_gain());
+        assert_eq!((1., 0.), lowpass.frequency_response(0.));
+        assert!(lowpass.frequency_response(core::f64::consts::PI).0 < 1e-15);
+
+        let (magnitude, phase) = lowpass.frequency_response(core::f64::consts::FRAC_PI_2);
+        assert!((magnitude - core::f64::consts::FRAC_1_SQRT_2).abs() < 1e-15);
+        assert!((phase + core::f64::consts::FRAC_PI_4).abs() < 1e-15);
+
+        let highpass: HaarFilter<f64> = HaarFilter::new(-0.5, 0.5);
+        assert_eq!(0., highpass.dc_gain());
+        assert_eq!(-1., highpass.nyquist_gain());
+        assert!((highpass.frequency_response(core::f64::consts::PI).0 - 1.).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_process_matches_consume() {
+        let xs = white_noise(200, 19);
+        let mut element: HaarFilter<f64> = HaarFilter::new(0.5, -0.5);
+        let mut block = element.clone();
+        let mut in_place = element.clone();
+        let mut expected = Vec::new();
+        let mut out = vec![0.; xs.len()];
+        let mut data = xs.clone();
+        let mut start = 0;
+        for end in [1, 8, 72, 72, 101, xs.len()] {
+            // this block ramps the taps, so it falls back to `consume`
+            if end == 101 {
+                for filter in [&mut element, &mut block, &mut in_place] {
+                    filter.set_taps([0.5, 0.5], 20);
+                }
+            }
+            expected.extend(xs[start..end].iter().map(|&x| element.consume(x)));
+            block.process(&xs[start..end], &mut out[start..end]);
+            in_place.process_in_place(&mut data[start..end]);
+            start = end;
+        }
+        assert_eq!(expected, out);
+        assert_eq!(expected, data);
+        assert_eq!(element, block);
+        assert_eq!(element, in_place);
+    }
+
+    #[test]
+    fn test_q15_matches_float() {
+        let sine: Vec<i16> = (0..1000)
+            .map(|n| (32767. * (n as f64 * 0.0173).sin()).round() as i16)
+            .collect();
+        for (h0, h1) in [(0.5, 0.5), (-0.5, 0.5), (1., 1.), (1., -1.)] {
+            let mut fixed = HaarFilterQ15::from_f64(h0, h1);
+            let mut float: HaarFilter<f64> = HaarFilter::new(h0, h1);
+            for &x in &sine {
+                let expected = float.consume(x as f64).round().clamp(-32768., 32767.);
+                let actual = fixed.consume(x) as f64;
+                assert!((actual - expected).abs() <= 1., "{actual} != {expected}");
+            }
+        }
+
+        let mut analysis = HaarFilterQ15::from_f64(0.5, 0.5);
+        assert_eq!(&[0x4000, 0x4000], analysis.taps());
+        assert_eq!(-16384, analysis.consume(i16::MIN));
+        assert_eq!(-32768, analysis.consume(i16::MIN));
+        let mut synthesis = HaarFilterQ15::from_f64(1., 1.);
+        assert_eq!(&[0x7fff, 0x7fff], synthesis.taps());
+        synthesis.consume(i16::MAX);
+        assert_eq!(i16::MAX, synthesis.consume(i16::MAX));
+        synthesis.reset();
+        assert_eq!(0, synthesis.prev());
+
+        // full scale negative taps and samples saturate instead of wrapping
+        let mut negative = HaarFilterQ15::from_f64(-1., -1.);
+        assert_eq!(&[i16::MIN, i16::MIN], negative.taps());
+        assert_eq!(i16::MAX, negative.consume(i16::MIN));
+        assert_eq!(i16::MAX, negative.consume(i16::MIN));
+        assert_eq!(1, negative.consume(i16::MAX));
+    }
+
+    #[test]
+    fn test_try_new() {
+        let filter = HaarFilter::<f32>::try_new(0.5, -0.5).unwrap();
+        assert_eq!(&[0.5, -0.5], filter.taps());
+        assert_eq!(
+            Err(QmfError::InvalidTap(0)),
+            HaarFilter::<f64>::try_new(f64::NAN, 0.5)
+        );
+        assert_eq!(
+            Err(QmfError::InvalidTap(1)),
+            HaarFilter::<f64>::try_new(0.5, f64::NEG_INFINITY)
+        );
+        // finite as f64, but out of range for f32
+        assert_eq!(
+            Err(QmfError::InvalidTap(1)),
+            HaarFilter::<f32>::try_new(1, 1e300)
+        );
+    }
+
+    #[test]
+    fn test_impulse_and_step_response() {
+        for (h0, h1) in [(0.5, 0.5), (-0.5, 0.5), (1., -1.)] {
+            let filter: HaarFilter<f64> = HaarFilter::new(h0, h1);
+            assert_eq!(vec![h0, h1, 0., 0.], filter.impulse_response(4));
+            let step = filter.step_response(8);
+            assert_eq!(h0, step[0]);
+            assert!(step[1..].iter().all(|&y| y == filter.dc_gain()));
+        }
+    }
+
+    #[test]
+    fn test_taps_mut_keeps_prev() {
+        let mut filter: HaarFilter<f64> = HaarFilter::new(0.5, 0.5);
+        assert_eq!(2., filter.consume(4.));
+        filter.taps_mut()[1] = 0.25;
+        assert_eq!(&[0.5, 0.25], filter.taps());
+        assert_eq!(4., filter.prev());
+        // the new weight meets the sample from before the change
+        assert_eq!(1.5, filter.consume(1.));
+
+        let mut ramping: HaarFilter<f64> = HaarFilter::new(0.5, 0.5);
+        ramping.set_taps([1., 1.], 8);
+        *ramping.taps_mut() = [0.5, -0.5];
+        ramping.consume(2.);
+        assert_eq!(&[0.5, -0.5], ramping.taps());
+    }
+
+    #[test]
+    fn test_fixed_haar_filter() {
+        let mut average = FixedHaarFilter::from_f64(0.5, 0.5, 1);
+        assert_eq!(&[1, 1], average.taps());
+        assert_eq!(3, average.consume(5));
+        assert_eq!(-2, average.consume(-10));
+        assert_eq!(-10, average.prev());
+
+        // the unit taps of the synthesis saturate instead of wrapping
+        let mut sum = FixedHaarFilter::from_f64(1., 1., 16);
+        assert_eq!(&[1 << 16, 1 << 16], sum.taps());
+        sum.consume(i32::MAX);
+        assert_eq!(i32::MAX, sum.consume(i32::MAX));
+        sum.reset();
+        sum.consume(i32::MIN);
+        assert_eq!(i32::MIN, sum.consume(i32::MIN));
+        assert_eq!(-1, sum.consume(i32::MAX));
+
+        // taps and samples near i32::MIN overflow even the i64 sum
+        let mut negative = FixedHaarFilter::new(i32::MIN, i32::MIN, 0);
+        negative.consume(i32::MIN);
+        assert_eq!(i32::MAX, negative.consume(i32::MIN));
     }
 }
@@ -0,0 +1,177 @@
+use alloc::vec::Vec;
+use num_traits::Float;
+
+/// A two-channel orthogonal filter bank in lattice form: a chain of plane
+/// rotations separated by one-sample delays on the second branch.
+///
+/// Synthesis undoes each rotation with its transpose, so the round trip is a
+/// pure delay of `angles.len() - 1` sample pairs whatever the angles are. The
+/// filters are orthonormal, with the lowpass taps summing to `√2 · cos(Σθ - π/4)`;
+/// angles adding up to π/4 give a lowpass with no response at Nyquist.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatticeQmf<T>
+where
+    T: Float,
+{
+    // (cos θ, sin θ) of each stage
+    rotations: Vec<(T, T)>,
+    analysis_delays: Vec<T>,
+    synthesis_delays: Vec<T>,
+}
+
+impl<T> LatticeQmf<T>
+where
+    T: Float,
+{
+    pub fn new(angles: &[T]) -> Self {
+        assert!(!angles.is_empty(), "a lattice needs at least one stage");
+        Self {
+            rotations: angles.iter().map(|a| (a.cos(), a.sin())).collect(),
+            analysis_delays: alloc::vec![T::zero(); angles.len() - 1],
+            synthesis_delays: alloc::vec![T::zero(); angles.len() - 1],
+        }
+    }
+
+    /// Splits the sample pair `x0 = x[2m]`, `x1 = x[2m + 1]` into one lowpass
+    /// and one highpass sample.
+    pub fn analysis(&mut self, x0: T, x1: T) -> (T, T) {
+        let (c, s) = self.rotations[0];
+        let (mut u0, mut u1) = rotate(c, s, x0, x1);
+        for (&(c, s), delay) in self.rotations[1..]
+            .iter()
+            .zip(self.analysis_delays.iter_mut())
+        {
+            let delayed = core::mem::replace(delay, u1);
+            (u0, u1) = rotate(c, s, u0, delayed);
+        }
+        (u0, u1)
+    }
+
+    /// Inverse of [`analysis`](Self::analysis), returning the sample pair from
+    /// [`delay`](Self::delay) calls ago.
+    pub fn synthesis(&mut self, low: T, high: T) -> (T, T) {
+        let last = self.rotations.len() - 1;
+        let (c, s) = self.rotations[last];
+        let (mut v0, mut v1) = rotate(c, -s, low, high);
+        for (&(c, s), delay) in self.rotations[..last]
+            .iter()
+            .rev()
+            .zip(self.synthesis_delays.iter_mut())
+        {
+            let delayed = core::mem::replace(delay, v0);
+            (v0, v1) = rotate(c, -s, delayed, v1);
+        }
+        (v0, v1)
+    }
+
+    pub fn reset(&mut self) {
+        self.analysis_delays.fill(T::zero());
+        self.synthesis_delays.fill(T::zero());
+    }
+
+    /// Number of sample pairs the round trip lags behind.
+    pub fn delay(&self) -> usize {
+        self.rotations.len() - 1
+    }
+
+    /// The equivalent direct form lowpass and highpass taps, such that
+    /// `analysis` yields `(h * x)[2m + 1]` for each filter `h`.
+    pub fn taps(&self) -> (Vec<T>, Vec<T>) {
+        // polyphase matrix entries as polynomials in the half-rate delay:
+        // rows are the outputs, columns the inputs x0 and x1
+        let (c, s) = self.rotations[0];
+        let mut e = [
+            [alloc::vec![c], alloc::vec![s]],
+            [alloc::vec![-s], alloc::vec![c]],
+        ];
+        for &(c, s) in &self.rotations[1..] {
+            let [upper, lower] = e;
+            let lower = lower.map(|p| {
+                let mut delayed = alloc::vec![T::zero()];
+                delayed.extend(p);
+                delayed
+            });
+            let upper = upper.map(|mut p| {
+                p.push(T::zero());
+                p
+            });
+            let mix = |a: &[T], b: &[T], ca: T, cb: T| -> Vec<T> {
+                a.iter()
+                    .zip(b.iter())
+                    .map(|(&x, &y)| ca * x + cb * y)
+                    .collect()
+            };
+            e = [
+                [
+                    mix(&upper[0], &lower[0], c, s),
+                    mix(&upper[1], &lower[1], c, s),
+                ],
+                [
+                    mix(&upper[0], &lower[0], -s, c),
+                    mix(&upper[1], &lower[1], -s, c),
+                ],
+            ];
+        }
+        let interleave = |[even, odd]: &[Vec<T>; 2]| -> Vec<T> {
+            odd.iter()
+                .zip(even.iter())
+                .flat_map(|(&a, &b)| [a, b])
+                .collect()
+        };
+        (interleave(&e[0]), interleave(&e[1]))
+    }
+}
+
+fn rotate<T: Float>(c: T, s: T, a: T, b: T) -> (T, T) {
+    (c * a + s * b, c * b - s * a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LatticeQmf;
+    use crate::{fir::DynFirFilter, test_util::white_noise};
+
+    #[test]
+    fn test_random_angles_reconstruct() {
+        for seed in 1..6 {
+            let angles: Vec<f64> = white_noise(4, seed).iter().map(|a| a * 3.).collect();
+            let mut lattice = LatticeQmf::new(&angles);
+            assert_eq!(3, lattice.delay());
+
+            let input = white_noise(256, seed + 100);
+            let mut output = Vec::new();
+            for pair in input.chunks(2) {
+                let (low, high) = lattice.analysis(pair[0], pair[1]);
+                let (x0, x1) = lattice.synthesis(low, high);
+                output.extend([x0, x1]);
+            }
+            let delay = 2 * lattice.delay();
+            assert!(output[..delay].iter().all(|y| y.abs() < 1e-12));
+            for (y, x) in output[delay..].iter().zip(input.iter()) {
+                assert!((y - x).abs() < 1e-12, "{y} != {x}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_taps_match_lattice() {
+        let angles = [0.3, -1.1, 0.7];
+        let mut lattice = LatticeQmf::new(&angles);
+        let (low_taps, high_taps) = lattice.taps();
+        assert_eq!(6, low_taps.len());
+        let sum: f64 = low_taps.iter().sum();
+        let expected = 2f64.sqrt() * (0.3f64 - 1.1 + 0.7 - core::f64::consts::FRAC_PI_4).cos();
+        assert!((sum - expected).abs() < 1e-12);
+
+        let mut low_filter = DynFirFilter::<f64>::from_taps(&low_taps);
+        let mut high_filter = DynFirFilter::<f64>::from_taps(&high_taps);
+        let input = white_noise(64, 27);
+        for pair in input.chunks(2) {
+            let (low, high) = lattice.analysis(pair[0], pair[1]);
+            low_filter.consume(pair[0]);
+            high_filter.consume(pair[0]);
+            assert!((low - low_filter.consume(pair[1])).abs() < 1e-12);
+            assert!((high - high_filter.consume(pair[1])).abs() < 1e-12);
+        }
+    }
+}
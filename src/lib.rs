@@ -1,9 +1,60 @@
-#![cfg_attr(not(test), no_std)]
+// `no_std_check/` builds the crate without `std`, on `alloc` and `libm` only
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(any(feature = "std", feature = "libm")))]
+compile_error!("qmf needs the `std` or the `libm` feature for its float math");
 
 extern crate alloc;
 
+mod allpass;
+mod analytic;
 mod bands;
+mod cascade;
+mod coeffs;
+mod compress;
+mod denoise;
+pub mod design;
+mod error;
+mod fir;
 mod haar;
+mod lattice;
+pub mod lifting;
+mod metrics;
+mod packet;
 mod sampling;
+#[cfg(feature = "serde")]
+mod serde_array;
+#[cfg(test)]
+mod test_util;
+pub mod transform_2d;
+#[cfg(feature = "wav")]
+mod wav;
+mod wavelet;
+pub mod window;
 
-pub use bands::Bands;
+pub use allpass::{AllpassCascade, AllpassQmf};
+pub use analytic::{envelope, ComplexBand, ComplexSample};
+pub use bands::{
+    Analysis, Band, Bands, DynBands, FixedBand, FixedHaarBand, MultiBands, QmfBand, StreamingBands,
+};
+pub use cascade::Cascade;
+pub use coeffs::WaveletCoeffs;
+pub use compress::{dequantize, quantize, QuantizedCoeffs};
+pub use denoise::{hard_threshold, soft_threshold, universal_threshold};
+pub use error::QmfError;
+pub use fir::{DelayLine, DynFirFilter, FilterKernel, FirFilter};
+pub use haar::{FixedHaarFilter, HaarFilter, HaarFilterQ15};
+pub use lattice::LatticeQmf;
+pub use metrics::{psnr, snr};
+pub use packet::WaveletPacket;
+pub use sampling::{
+    resample, Averaging, AveragingDownSampler, CicDecimating, CicDecimator, CicInterpolating,
+    CicInterpolator, Decimating, DownSampler, DownSampling, FarrowResampler, Interpolating,
+    LinearUpSampler, LinearUpSampling, MinMax, OwnedDownSampling, OwnedUpSampling, PeakDecimator,
+    Peaks, PolyphaseDecimator, PolyphaseInterpolator, Quality, Resampler, Resampling,
+    SampleIteratorExt, SamplingError, StridedDownSampler, StridedDownSampling, StridedUpSampler,
+    StridedUpSampling, UpSampler, UpSampling,
+};
+#[cfg(feature = "wav")]
+pub use wav::{process_wav, WavError};
+pub use wavelet::{FilterSet, Normalization, Wavelet};
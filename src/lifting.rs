@@ -0,0 +1,348 @@
+use alloc::vec::Vec;
+use num_traits::Float;
+
+use crate::{
+    bands::{analysis_vecs, Pairs, QmfBand},
+    fir::DelayLine,
+};
+
+/// One level of the Haar transform by lifting, in place: each pair
+/// `(x0, x1)` becomes the average `s = x0 + d / 2` and the difference
+/// `d = x1 - x0`, with the averages gathered in the first half of `xs` and
+/// the differences in the second.
+///
+/// `s` equals the lowpass output of the filter bank on the same pair, and its
+/// highpass output is `-d / 2`.
+///
+/// Panics unless `xs` has an even length.
+pub fn haar_forward<T: Float>(xs: &mut [T]) {
+    assert!(
+        xs.len().is_multiple_of(2),
+        "the Haar transform works on sample pairs"
+    );
+    let half = T::from(0.5).unwrap();
+    for pair in xs.chunks_exact_mut(2) {
+        let d = pair[1] - pair[0];
+        pair[0] = pair[0] + d * half;
+        pair[1] = d;
+    }
+    unshuffle(xs);
+}
+
+/// Inverse of [`haar_forward`].
+pub fn haar_inverse<T: Float>(xs: &mut [T]) {
+    assert!(
+        xs.len().is_multiple_of(2),
+        "the Haar transform works on sample pairs"
+    );
+    let half = T::from(0.5).unwrap();
+    shuffle(xs);
+    for pair in xs.chunks_exact_mut(2) {
+        let d = pair[1];
+        pair[0] = pair[0] - d * half;
+        pair[1] = pair[0] + d;
+    }
+}
+
+// moves the even samples to the front and the odd ones to the back by
+// rotating the halves of ever larger blocks into place
+fn unshuffle<T>(xs: &mut [T]) {
+    let pairs = xs.len() / 2;
+    if pairs < 2 {
+        return;
+    }
+    let h = pairs / 2;
+    let (front, back) = xs.split_at_mut(2 * h);
+    unshuffle(front);
+    unshuffle(back);
+    xs[h..h + pairs].rotate_left(h);
+}
+
+fn shuffle<T>(xs: &mut [T]) {
+    let pairs = xs.len() / 2;
+    if pairs < 2 {
+        return;
+    }
+    let h = pairs / 2;
+    xs[h..h + pairs].rotate_right(h);
+    let (front, back) = xs.split_at_mut(2 * h);
+    shuffle(front);
+    shuffle(back);
+}
+
+/// One level of the reversible integer LeGall 5/3 transform (as in lossless
+/// JPEG 2000), in place, with the `(len + 1) / 2` approximations first and the
+/// details after them. Works on any length, extending the signal
+/// symmetrically at both ends.
+///
+/// The lifting steps wrap on overflow, so [`legall53_inverse`] restores every
+/// buffer bit for bit, `i32::MIN` and `i32::MAX` included. The coefficients
+/// only stay free of wrap-around while the input keeps a bit of headroom,
+/// i.e. stays within `i32::MIN / 2..=i32::MAX / 2`.
+pub fn legall53_forward(xs: &mut [i32]) {
+    let n = xs.len();
+    if n < 2 {
+        return;
+    }
+    // predict the odd samples from their even neighbours
+    for i in (1..n).step_by(2) {
+        let right = if i + 1 < n { xs[i + 1] } else { xs[i - 1] };
+        xs[i] = xs[i].wrapping_sub(floor_div(xs[i - 1] as i64 + right as i64, 2));
+    }
+    // then update the even ones from the neighbouring details
+    for i in (0..n).step_by(2) {
+        let (left, right) = detail_neighbours(xs, i);
+        xs[i] = xs[i].wrapping_add(floor_div(left as i64 + right as i64 + 2, 4));
+    }
+    deinterleave(xs);
+}
+
+/// Inverse of [`legall53_forward`].
+pub fn legall53_inverse(xs: &mut [i32]) {
+    let n = xs.len();
+    if n < 2 {
+        return;
+    }
+    interleave(xs);
+    for i in (0..n).step_by(2) {
+        let (left, right) = detail_neighbours(xs, i);
+        xs[i] = xs[i].wrapping_sub(floor_div(left as i64 + right as i64 + 2, 4));
+    }
+    for i in (1..n).step_by(2) {
+        let right = if i + 1 < n { xs[i + 1] } else { xs[i - 1] };
+        xs[i] = xs[i].wrapping_add(floor_div(xs[i - 1] as i64 + right as i64, 2));
+    }
+}
+
+/// Multi-level 5/3 transform of a whole block, in the manner of
+/// [`Bands::process`](crate::Bands::process): the closure sees the
+/// approximation as band `levels`, then the details from the deepest band to
+/// band 0, and the block is rebuilt from whatever it leaves behind. Unlike
+/// `Bands` there is no state carried between calls and no delay.
+pub fn legall53_process<F>(buffer: &mut [i32], levels: usize, mut closure: F)
+where
+    F: FnMut(&mut [i32], usize),
+{
+    // lengths of the signal entering each level
+    let mut lens = Vec::with_capacity(levels + 1);
+    lens.push(buffer.len());
+    for level in 0..levels {
+        legall53_forward(&mut buffer[..lens[level]]);
+        lens.push(lens[level].div_ceil(2));
+    }
+    closure(&mut buffer[..lens[levels]], levels);
+    for level in (0..levels).rev() {
+        closure(&mut buffer[lens[level + 1]..lens[level]], level);
+    }
+    for level in (0..levels).rev() {
+        legall53_inverse(&mut buffer[..lens[level]]);
+    }
+}
+
+fn floor_div(x: i64, d: i64) -> i32 {
+    x.div_euclid(d) as i32
+}
+
+// details on either side of the even sample at `i`, mirrored at the ends
+fn detail_neighbours(xs: &[i32], i: usize) -> (i32, i32) {
+    let n = xs.len();
+    let right = if i + 1 < n { xs[i + 1] } else { xs[i - 1] };
+    let left = if i > 0 { xs[i - 1] } else { right };
+    (left, right)
+}
+
+// `unshuffle` and `shuffle` extended to odd lengths, where the last even
+// sample closes the first half
+fn deinterleave<T>(xs: &mut [T]) {
+    let n = xs.len();
+    unshuffle(&mut xs[..n - n % 2]);
+    if n % 2 == 1 {
+        xs[n / 2..].rotate_right(1);
+    }
+}
+
+fn interleave<T>(xs: &mut [T]) {
+    let n = xs.len();
+    if n % 2 == 1 {
+        xs[n / 2..].rotate_left(1);
+    }
+    shuffle(&mut xs[..n - n % 2]);
+}
+
+/// A streaming Haar band computed by lifting, so only the samples that
+/// survive decimation get computed. Matches the filter bank [`Band::new`]
+/// up to rounding.
+///
+/// [`Band::new`]: crate::Band::new
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiftingHaar<T> {
+    pairs: Pairs<T>,
+}
+
+impl<T> LiftingHaar<T>
+where
+    T: Float,
+{
+    pub fn new() -> Self {
+        Self {
+            pairs: Pairs::new(),
+        }
+    }
+}
+
+impl<T> Default for LiftingHaar<T>
+where
+    T: Float,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> QmfBand<T> for LiftingHaar<T>
+where
+    T: Float,
+{
+    type Compensator = DelayLine<T>;
+
+    fn analysis(&mut self, xs: &[T]) -> (Vec<T>, Vec<T>) {
+        analysis_vecs(xs.len(), |low, high| self.analysis_into(xs, low, high))
+    }
+
+    fn analysis_into(&mut self, xs: &[T], low: &mut [T], high: &mut [T]) -> usize {
+        let half = T::from(0.5).unwrap();
+        self.pairs.analysis_into(xs, low, high, |odd, even| {
+            let d = even - odd;
+            (odd + d * half, -d * half)
+        })
+    }
+
+    fn synthesis(&mut self, low: &[T], high: &[T], out: &mut [T]) {
+        // undo the update, then the predict step
+        self.pairs.synthesis(low, high, out, |s, h| (s + h, s - h))
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    fn delay(&self) -> usize {
+        1
+    }
+
+    fn compensator(&self, stride: usize) -> DelayLine<T> {
+        DelayLine::new(stride)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        haar_forward, haar_inverse, legall53_forward, legall53_inverse, legall53_process,
+        LiftingHaar,
+    };
+    use crate::{
+        bands::{Band, Bands},
+        test_util::white_noise,
+    };
+
+    #[test]
+    fn test_haar_round_trip() {
+        for len in [2, 4, 6, 14, 64, 100] {
+            let input = white_noise(len, len as u64);
+            let mut data = input.clone();
+            haar_forward(&mut data);
+            haar_inverse(&mut data);
+            for (y, x) in data.iter().zip(input.iter()) {
+                assert!((y - x).abs() < 1e-15, "{y} != {x}");
+            }
+        }
+
+        let mut data = [1., 3., 5., 5., 0., -2.];
+        haar_forward(&mut data);
+        assert_eq!([2., 5., -1., 2., 0., -2.], data);
+    }
+
+    #[test]
+    fn test_haar_matches_filter_bank() {
+        // the bank pairs x[2m - 1] with x[2m], so lead with a zero
+        let input = white_noise(64, 29);
+        let mut shifted = vec![0.];
+        shifted.extend_from_slice(&input[..63]);
+        haar_forward(&mut shifted);
+
+        let (low, high) = Band::new().analysis(&input);
+        for m in 0..32 {
+            assert!((low[m] - shifted[m]).abs() < 1e-15);
+            assert!((high[m] + shifted[32 + m] / 2.).abs() < 1e-15);
+        }
+    }
+
+    #[test]
+    fn test_lifting_bands_reconstruct() {
+        let input = white_noise(301, 30);
+        let mut filters = Bands::<f64, 3>::new();
+        let mut expected = input.clone();
+        filters.process(expected.as_mut_slice(), |_d, _c| {});
+
+        let mut lifting = Bands::<f64, 3, LiftingHaar<f64>>::lifting_haar();
+        assert_eq!(filters.delay(), lifting.delay());
+        let mut actual = input.clone();
+        lifting.process(actual.as_mut_slice(), |_d, _c| {});
+        for (y, e) in actual.iter().zip(expected.iter()) {
+            assert!((y - e).abs() < 1e-12, "{y} != {e}");
+        }
+    }
+
+    // xorshift over the full i32 range, with the extremes mixed in
+    fn random_i32s(len: usize, state: &mut u64) -> Vec<i32> {
+        (0..len)
+            .map(|_| {
+                *state ^= *state << 13;
+                *state ^= *state >> 7;
+                *state ^= *state << 17;
+                match *state % 8 {
+                    0 => i32::MIN,
+                    1 => i32::MAX,
+                    2 => (*state >> 40) as i32 % 64 - 32,
+                    _ => (*state >> 32) as i32,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_legall53_lossless() {
+        let mut state = 0x5eed_0053;
+        for round in 0..4000 {
+            let input = random_i32s(round % 67, &mut state);
+            let mut data = input.clone();
+            legall53_forward(&mut data);
+            legall53_inverse(&mut data);
+            assert_eq!(input, data);
+
+            let mut data = input.clone();
+            legall53_process(&mut data, 1 + round % 5, |_d, _c| {});
+            assert_eq!(input, data);
+        }
+    }
+
+    #[test]
+    fn test_legall53_coefficients() {
+        // a ramp is predicted exactly except at the mirrored end
+        let mut data: Vec<i32> = (0..8).map(|x| 3 * x).collect();
+        legall53_forward(&mut data);
+        assert_eq!(vec![0, 6, 12, 19, 0, 0, 0, 3], data);
+
+        let mut levels = Vec::new();
+        let mut data = vec![7; 20];
+        legall53_process(&mut data, 3, |band, level| {
+            levels.push((level, band.len()));
+            if level < 3 {
+                assert!(band.iter().all(|&d| d == 0));
+            }
+        });
+        assert_eq!(vec![(3, 3), (2, 2), (1, 5), (0, 10)], levels);
+        assert_eq!(vec![7; 20], data);
+    }
+}
@@ -0,0 +1,56 @@
+use num_traits::Float;
+
+/// Signal-to-noise ratio in dB of `test` against `reference`: the power of
+/// the reference over that of the difference. Infinite if they are equal.
+///
+/// Panics unless both are as long.
+pub fn snr<T: Float>(reference: &[T], test: &[T]) -> T {
+    let signal = reference.iter().fold(T::zero(), |acc, &x| acc + x * x);
+    decibels(signal, error_energy(reference, test))
+}
+
+/// Peak signal-to-noise ratio in dB of `test` against `reference`: `peak²`
+/// over the mean squared difference. Infinite if they are equal.
+///
+/// Panics unless both are as long.
+pub fn psnr<T: Float>(reference: &[T], test: &[T], peak: T) -> T {
+    let len = T::from(reference.len()).unwrap();
+    decibels(peak * peak * len, error_energy(reference, test))
+}
+
+fn error_energy<T: Float>(reference: &[T], test: &[T]) -> T {
+    assert_eq!(reference.len(), test.len(), "signal lengths differ");
+    reference
+        .iter()
+        .zip(test)
+        .fold(T::zero(), |acc, (&x, &y)| acc + (y - x) * (y - x))
+}
+
+fn decibels<T: Float>(signal: T, noise: T) -> T {
+    if noise == T::zero() {
+        return T::infinity();
+    }
+    T::from(10).unwrap() * (signal / noise).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{psnr, snr};
+
+    #[test]
+    fn test_identical_signals() {
+        let signal = [1., -2., 0.5];
+        assert_eq!(f64::INFINITY, snr(&signal, &signal));
+        assert_eq!(f64::INFINITY, psnr(&signal, &signal, 2.));
+    }
+
+    #[test]
+    fn test_known_error() {
+        // unit power against an error of power 0.01 is 20 dB
+        let reference: [f64; 4] = [1., -1., 1., -1.];
+        let test = [1.1, -0.9, 1.1, -0.9];
+        assert!((snr(&reference, &test) - 20.).abs() < 1e-12);
+        // a peak of 10 over the same error is 40 dB
+        assert!((psnr(&reference, &test, 10.) - 40.).abs() < 1e-12);
+    }
+}
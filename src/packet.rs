@@ -0,0 +1,186 @@
+use alloc::vec::Vec;
+use num_traits::Float;
+
+use crate::{
+    bands::{Band, QmfBand},
+    error::QmfError,
+    wavelet::{FilterSet, Wavelet},
+};
+
+/// A wavelet packet tree: unlike [`Bands`](crate::Bands) every band splits
+/// both of its outputs again, ending in `2^DEPTH` leaves of equal width.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaveletPacket<T, const DEPTH: usize>
+where
+    T: Float,
+{
+    // the tree in breadth first order, the children of node `i` being
+    // `2i + 1` (low) and `2i + 2` (high)
+    bands: Vec<Band<T>>,
+    // samples synthesized so far, modulo 2^DEPTH
+    position: usize,
+}
+
+impl<T, const DEPTH: usize> WaveletPacket<T, DEPTH>
+where
+    T: Float,
+{
+    pub fn new() -> Self {
+        Self::from_filter_set(&FilterSet::haar())
+    }
+
+    pub fn with_wavelet(wavelet: Wavelet) -> Result<Self, QmfError> {
+        Ok(Self::from_filter_set(&wavelet.filter_set()?))
+    }
+
+    pub fn from_filter_set(filters: &FilterSet<T>) -> Self {
+        Self {
+            bands: (1..1 << DEPTH)
+                .map(|_| Band::from_filter_set(filters))
+                .collect(),
+            position: 0,
+        }
+    }
+
+    /// Splits `buffer` into `2^DEPTH` leaves, ordered from the lowest
+    /// frequencies to the highest.
+    pub fn analyze(&mut self, buffer: &[T]) -> Vec<Vec<T>> {
+        let mut nodes = alloc::vec![buffer.to_vec()];
+        for level in 0..DEPTH {
+            let first = (1 << level) - 1;
+            nodes = nodes
+                .iter()
+                .zip(self.bands[first..].iter_mut())
+                .flat_map(|(node, band)| {
+                    let (low, high) = band.analysis(node);
+                    [low, high]
+                })
+                .collect();
+        }
+        // decimating a highpass output mirrors its spectrum, so the children
+        // of every high branch come out in reverse frequency order
+        let mut leaves = alloc::vec![Vec::new(); nodes.len()];
+        for (n, node) in nodes.into_iter().enumerate() {
+            leaves[n ^ (n >> 1)] = node;
+        }
+        leaves
+    }
+
+    /// Rebuilds the signal from leaves in the order `analyze` returns them;
+    /// `out` receives it [`delay`](Self::delay) samples late.
+    ///
+    /// Panics unless there are `2^DEPTH` leaves.
+    pub fn synthesize(&mut self, leaves: &[Vec<T>], out: &mut [T]) {
+        assert_eq!(1 << DEPTH, leaves.len(), "need one leaf per subband");
+        let mut nodes: Vec<Vec<T>> = (0..leaves.len())
+            .map(|n| leaves[n ^ (n >> 1)].clone())
+            .collect();
+
+        // how many samples each level rebuilds, given that the kept phase of
+        // every level is the even one
+        let mut lens = Vec::with_capacity(DEPTH);
+        let (mut start, mut end) = (self.position, self.position + out.len());
+        for _ in 0..DEPTH {
+            lens.push(end - start);
+            (start, end) = (start.div_ceil(2), end.div_ceil(2));
+        }
+        self.position = (self.position + out.len()) % (1 << DEPTH);
+
+        for level in (0..DEPTH).rev() {
+            let first = (1 << level) - 1;
+            if level == 0 {
+                self.bands[0].synthesis(&nodes[0], &nodes[1], out);
+                return;
+            }
+            nodes = nodes
+                .chunks(2)
+                .zip(self.bands[first..].iter_mut())
+                .map(|(pair, band)| {
+                    let mut merged = alloc::vec![T::zero(); lens[level]];
+                    band.synthesis(&pair[0], &pair[1], &mut merged);
+                    merged
+                })
+                .collect();
+        }
+        // no levels at all
+        for (o, &x) in out.iter_mut().zip(nodes[0].iter()) {
+            *o = x;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.bands.iter_mut().for_each(Band::reset);
+        self.position = 0;
+    }
+
+    /// Number of samples the reconstructed signal lags behind the input.
+    pub fn delay(&self) -> usize {
+        (0..DEPTH).rev().fold(0, |inner, level| {
+            2 * inner + self.bands[(1 << level) - 1].delay()
+        })
+    }
+}
+
+impl<T, const DEPTH: usize> Default for WaveletPacket<T, DEPTH>
+where
+    T: Float,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WaveletPacket;
+    use crate::{test_util::white_noise, wavelet::Wavelet};
+
+    #[test]
+    fn test_packet_reconstruct() {
+        let input = white_noise(1000, 44);
+        let mut packet = WaveletPacket::<f64, 2>::with_wavelet(Wavelet::Daubechies(3)).unwrap();
+        let delay = packet.delay();
+        assert_eq!(15, delay);
+
+        let mut output = alloc::vec![0.; input.len()];
+        let mut start = 0;
+        for end in [1, 100, 333, 334, 1000] {
+            let leaves = packet.analyze(&input[start..end]);
+            assert_eq!(4, leaves.len());
+            packet.synthesize(&leaves, &mut output[start..end]);
+            start = end;
+        }
+        for (y, x) in output[delay..].iter().zip(input.iter()) {
+            assert!((y - x).abs() < 1e-12, "{y} != {x}");
+        }
+    }
+
+    #[test]
+    fn test_clone_continues_the_stream() {
+        let input = white_noise(64, 45);
+        let mut packet = WaveletPacket::<f64, 2>::new();
+        packet.analyze(&input[..21]);
+        let mut copy = packet.clone();
+        assert_eq!(packet, copy);
+        assert_ne!(WaveletPacket::new(), packet);
+        assert_eq!(packet.analyze(&input[21..]), copy.analyze(&input[21..]));
+    }
+
+    #[test]
+    fn test_sine_lands_in_one_leaf() {
+        for leaf in 0..4 {
+            // the center of the leaf, in radians per sample
+            let omega = core::f64::consts::PI * (leaf as f64 + 0.5) / 4.;
+            let input: Vec<f64> = (0..4096).map(|n| (omega * n as f64).sin()).collect();
+            let mut packet =
+                WaveletPacket::<f64, 2>::with_wavelet(Wavelet::Daubechies(10)).unwrap();
+            let energies: Vec<f64> = packet
+                .analyze(&input)
+                .iter()
+                .map(|band| band.iter().map(|x| x * x).sum())
+                .collect();
+            let total: f64 = energies.iter().sum();
+            assert!(energies[leaf] > 0.95 * total, "leaf {leaf}: {energies:?}");
+        }
+    }
+}
@@ -1,31 +1,189 @@
-use num_traits::Num;
+use alloc::vec::Vec;
+use core::{fmt, iter::FusedIterator};
+use num_traits::{Float, Num};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+use crate::{
+    design::{lowpass, Window},
+    fir::{multiply_add, DynFirFilter, FilterKernel},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SamplingError {
+    /// A sampler was asked to scale by zero.
+    ZeroScale,
+    /// A sampler phase was not smaller than its scale.
+    PhaseOutOfRange,
+    /// A resampler was given no filter taps.
+    EmptyFilter,
+    /// A CIC filter was asked for zero stages.
+    ZeroOrder,
+    /// The register growth of a CIC filter does not fit its 64-bit
+    /// accumulators on top of 32-bit samples.
+    RegisterGrowth,
+    /// A resampling ratio was not positive and finite, or too large.
+    InvalidRatio,
+    /// A strided sampler was asked for zero channels.
+    ZeroChannels,
+}
+
+impl fmt::Display for SamplingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SamplingError::ZeroScale => write!(f, "sampling scale must be nonzero"),
+            SamplingError::PhaseOutOfRange => write!(f, "sampling phase must be below the scale"),
+            SamplingError::EmptyFilter => write!(f, "resampling filter has no taps"),
+            SamplingError::ZeroOrder => write!(f, "CIC filter order must be nonzero"),
+            SamplingError::RegisterGrowth => {
+                write!(f, "CIC register growth exceeds the 64-bit accumulators")
+            }
+            SamplingError::InvalidRatio => write!(f, "resampling ratio is out of range"),
+            SamplingError::ZeroChannels => write!(f, "strided sampler needs a channel"),
+        }
+    }
+}
+
+impl core::error::Error for SamplingError {}
+
+/// Upsamples by inserting `scale - 1` fill values after every input, zeros
+/// for QMF synthesis. [`LinearUpSampler`] interpolates instead.
+///
+/// The [`phase`](Self::phase) is the position within the current group of
+/// `scale` outputs and carries across `iter` calls. A call that stops right
+/// after an input, as [`Band`](crate::Band) synthesis does when its output
+/// is full, leaves the fill values of that input owed, and the next call
+/// starts with them:
+///
+/// ```
+/// let mut up = qmf::UpSampler::with_zero(2).unwrap();
+/// assert_eq!(Some(1), up.iter([1].into_iter()).next());
+/// assert_eq!(1, up.phase());
+/// assert_eq!(vec![0, 2, 0], up.iter([2].into_iter()).collect::<Vec<_>>());
+/// assert_eq!(0, up.phase());
+/// ```
+///
+/// Integer samplers are `Eq`, float ones only `PartialEq`:
+///
+/// ```
+/// fn assert_eq<T: Eq>() {}
+/// assert_eq::<qmf::UpSampler<i32>>();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UpSampler<T>
 where
     T: Num,
 {
     scale: usize,
     with: T,
+    // repeat the last input instead of `with`, which then tracks that input
+    hold: bool,
     count: usize,
 }
 
+impl<T> Eq for UpSampler<T> where T: Num + Eq {}
+
 impl<T> UpSampler<T>
 where
     T: Num,
 {
-    pub fn new(scale: usize, with: T) -> UpSampler<T> {
-        UpSampler {
+    pub fn new(scale: usize, with: T) -> Result<UpSampler<T>, SamplingError> {
+        if scale == 0 {
+            return Err(SamplingError::ZeroScale);
+        }
+        Ok(UpSampler {
             scale,
             with,
+            hold: false,
             count: 0,
-        }
+        })
     }
 
-    pub fn with_zero(scale: usize) -> UpSampler<T> {
+    pub fn with_zero(scale: usize) -> Result<UpSampler<T>, SamplingError> {
         UpSampler::new(scale, T::zero())
     }
 
+    /// Zero-stuffs each channel of interleaved data with `channels` channels
+    /// on its own; see [`StridedUpSampler::from_sampler`] for other fills.
+    pub fn strided(scale: usize, channels: usize) -> Result<StridedUpSampler<T>, SamplingError>
+    where
+        T: Clone,
+    {
+        StridedUpSampler::from_sampler(UpSampler::with_zero(scale)?, channels)
+    }
+
+    /// Sample-and-hold: every input is repeated `scale` times.
+    pub fn hold(scale: usize) -> Result<UpSampler<T>, SamplingError> {
+        let mut sampler = UpSampler::with_zero(scale)?;
+        sampler.hold = true;
+        Ok(sampler)
+    }
+
+    pub fn reset(&mut self) {
+        self.count = 0;
+        if self.hold {
+            self.with = T::zero();
+        }
+    }
+
+    /// Outputs already emitted in the current group; zero when the next
+    /// output is an input sample.
+    pub fn phase(&self) -> usize {
+        self.count
+    }
+
+    /// Moves to position `phase` of the group, so the next `scale - phase`
+    /// outputs are fill values, or the next is an input for zero.
+    pub fn set_phase(&mut self, phase: usize) -> Result<(), SamplingError> {
+        if phase >= self.scale {
+            return Err(SamplingError::PhaseOutOfRange);
+        }
+        self.count = phase;
+        Ok(())
+    }
+
+    /// Switches to a new scale. The phase goes back to zero, so fill values
+    /// still owed to the last input are dropped and the next output is the
+    /// next input.
+    pub fn set_scale(&mut self, scale: usize) -> Result<(), SamplingError> {
+        if scale == 0 {
+            return Err(SamplingError::ZeroScale);
+        }
+        self.scale = scale;
+        self.count = 0;
+        Ok(())
+    }
+
+    /// Takes ownership instead of borrowing, so that the adaptor can be
+    /// stored or returned; [`OwnedUpSampling::into_parts`] gives the sampler
+    /// back.
+    pub fn into_sampling<I: Iterator<Item = T>>(self, iter: I) -> OwnedUpSampling<I, T> {
+        OwnedUpSampling {
+            iter,
+            sampler: self,
+        }
+    }
+
+    // the outputs for an inner iterator of this `size_hint`
+    fn size_hint(&self, (lower, upper): (usize, Option<usize>)) -> (usize, Option<usize>) {
+        // fill values still owed for the sample emitted before this call
+        let scale = self.scale;
+        let pending = (scale - self.count) % scale;
+        let lower = lower.saturating_mul(scale).saturating_add(pending);
+        let upper = upper.and_then(|n| n.checked_mul(scale)?.checked_add(pending));
+        (lower, upper)
+    }
+
+    /// Upsamples `input` into `out` without allocating, as
+    /// `iter(input).take(out.len())` would, and returns how many samples
+    /// were written. If `out` fills up first, the rest of `input` is left
+    /// unread and the phase stops where the writing did.
+    pub fn process_into(&mut self, input: &[T], out: &mut [T]) -> usize
+    where
+        T: Clone,
+    {
+        write_into(self.iter(input.iter().cloned()), out)
+    }
+
     pub fn iter<I: Iterator<Item = T>>(&mut self, iter: I) -> UpSampling<'_, I, T> {
         UpSampling {
             iter,
@@ -34,7 +192,7 @@ where
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq)]
 pub struct UpSampling<'a, I, T>
 where
     T: Num,
@@ -43,6 +201,13 @@ where
     sampler: &'a mut UpSampler<T>,
 }
 
+impl<'a, I, T> Eq for UpSampling<'a, I, T>
+where
+    I: Eq,
+    T: Num + Eq,
+{
+}
+
 impl<'a, I, T> Iterator for UpSampling<'a, I, T>
 where
     I: Iterator<Item = T>,
@@ -51,8 +216,12 @@ where
     type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let ret = if self.sampler.count % self.sampler.scale == 0 {
-            self.iter.next()
+        let ret = if self.sampler.count.is_multiple_of(self.sampler.scale) {
+            let ret = self.iter.next();
+            if let (true, Some(x)) = (self.sampler.hold, &ret) {
+                self.sampler.with = x.clone();
+            }
+            ret
         } else {
             Some(self.sampler.with.clone())
         };
@@ -61,23 +230,153 @@ where
         }
         ret
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sampler.size_hint(self.iter.size_hint())
+    }
+}
+
+// once the inner iterator is exhausted the phase sits at zero, so no fill
+// value can follow a `None`
+impl<'a, I, T> FusedIterator for UpSampling<'a, I, T>
+where
+    I: FusedIterator<Item = T>,
+    T: Num + Clone,
+{
+}
+
+impl<'a, I, T> ExactSizeIterator for UpSampling<'a, I, T>
+where
+    I: ExactSizeIterator<Item = T>,
+    T: Num + Clone,
+{
+}
+
+// `out` leads so that a full buffer pulls nothing more from `samples`
+fn write_into<T>(samples: impl Iterator<Item = T>, out: &mut [T]) -> usize {
+    out.iter_mut().zip(samples).map(|(o, x)| *o = x).count()
 }
 
+/// Keeps one sample of every group of `scale`.
+///
+/// The position within the current group, its [`phase`](Self::phase),
+/// carries across `iter` calls, so splitting a stream into blocks of any
+/// lengths yields the same samples as decimating it in one go. It is
+/// independent of which sample of the group is kept, as chosen by
+/// [`with_phase`](Self::with_phase):
+///
+/// ```
+/// let mut down = qmf::DownSampler::with_phase(3, 1).unwrap();
+/// assert_eq!(vec![2], down.iter([1, 2, 3, 4].into_iter()).collect::<Vec<_>>());
+/// assert_eq!(1, down.phase());
+/// assert_eq!(vec![5], down.iter([5, 6].into_iter()).collect::<Vec<_>>());
+/// assert_eq!(0, down.phase());
+/// ```
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DownSampler {
     scale: usize,
+    #[cfg_attr(feature = "serde", serde(rename = "phase"))]
+    kept: usize,
     count: usize,
 }
 
 impl DownSampler {
-    pub fn new(scale: usize) -> Self {
-        Self { scale, count: 0 }
+    pub fn new(scale: usize) -> Result<Self, SamplingError> {
+        DownSampler::with_phase(scale, 0)
+    }
+
+    /// Keeps the `phase`-th sample of every group of `scale`.
+    pub fn with_phase(scale: usize, phase: usize) -> Result<Self, SamplingError> {
+        if scale == 0 {
+            return Err(SamplingError::ZeroScale);
+        }
+        if phase >= scale {
+            return Err(SamplingError::PhaseOutOfRange);
+        }
+        Ok(Self {
+            scale,
+            kept: phase,
+            count: 0,
+        })
+    }
+
+    /// Decimates each channel of interleaved data with `channels` channels
+    /// on its own; see [`StridedDownSampler::from_sampler`] for other phases.
+    pub fn strided(scale: usize, channels: usize) -> Result<StridedDownSampler, SamplingError> {
+        StridedDownSampler::from_sampler(DownSampler::new(scale)?, channels)
+    }
+
+    pub fn reset(&mut self) {
+        self.count = 0;
+    }
+
+    /// Inputs already consumed in the current group; zero at a group start.
+    pub fn phase(&self) -> usize {
+        self.count
+    }
+
+    /// Moves to position `phase` of the group, so the next `scale - phase`
+    /// inputs finish it.
+    pub fn set_phase(&mut self, phase: usize) -> Result<(), SamplingError> {
+        if phase >= self.scale {
+            return Err(SamplingError::PhaseOutOfRange);
+        }
+        self.count = phase;
+        Ok(())
+    }
+
+    /// See [`UpSampler::process_into`].
+    pub fn process_into<T: Clone>(&mut self, input: &[T], out: &mut [T]) -> usize {
+        write_into(self.iter(input.iter().cloned()), out)
+    }
+
+    /// Switches to a new scale, keeping the same sample of each group. The
+    /// phase goes back to zero, so the next input starts the first group at
+    /// the new scale. Fails, leaving the sampler as it was, unless the kept
+    /// sample is below the new scale.
+    pub fn set_scale(&mut self, scale: usize) -> Result<(), SamplingError> {
+        if scale == 0 {
+            return Err(SamplingError::ZeroScale);
+        }
+        if self.kept >= scale {
+            return Err(SamplingError::PhaseOutOfRange);
+        }
+        self.scale = scale;
+        self.count = 0;
+        Ok(())
+    }
+
+    /// See [`UpSampler::into_sampling`].
+    pub fn into_sampling<I: Iterator>(self, iter: I) -> OwnedDownSampling<I> {
+        OwnedDownSampling {
+            iter,
+            sampler: self,
+        }
+    }
+
+    // the outputs for an inner iterator of this `size_hint`, whose next
+    // input sits at `front` within its group
+    fn size_hint(
+        &self,
+        front: usize,
+        (lower, upper): (usize, Option<usize>),
+    ) -> (usize, Option<usize>) {
+        let scale = self.scale;
+        // inputs to skip before the next kept one
+        let skip = (scale + self.kept - front) % scale;
+        let kept = |n: usize| match n.checked_sub(skip) {
+            Some(rest) if rest > 0 => (rest - 1) / scale + 1,
+            _ => 0,
+        };
+        (kept(lower), upper.map(kept))
     }
 
     pub fn iter<I: Iterator>(&mut self, iter: I) -> DownSampling<'_, I> {
         DownSampling {
             iter,
             sampler: self,
+            back: 0,
         }
     }
 }
@@ -86,6 +385,17 @@ impl DownSampler {
 pub struct DownSampling<'a, I> {
     iter: I,
     sampler: &'a mut DownSampler,
+    // inputs taken from the back, modulo the scale; they already count
+    // towards the phase of the sampler, but not towards that of the front
+    back: usize,
+}
+
+impl<'a, I> DownSampling<'a, I> {
+    // the position of the next input from the front within its group
+    fn front(&self) -> usize {
+        let scale = self.sampler.scale;
+        (self.sampler.count + scale - self.back) % scale
+    }
 }
 
 impl<'a, I> Iterator for DownSampling<'a, I>
@@ -100,54 +410,2483 @@ where
             let Some(item) = self.iter.next() else {
                 break;
             };
-            if self.sampler.count == 0 {
+            if self.front() == self.sampler.kept {
                 ret = Some(item);
             }
             self.sampler.count = (self.sampler.count + 1) % self.sampler.scale;
         }
         ret
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sampler.size_hint(self.front(), self.iter.size_hint())
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::sampling::{DownSampler, UpSampler};
+// the back end needs the inner length to find the last kept input
+impl<'a, I> DoubleEndedIterator for DownSampling<'a, I>
+where
+    I: DoubleEndedIterator + ExactSizeIterator,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let scale = self.sampler.scale;
+        let skip = (scale + self.sampler.kept - self.front()) % scale;
+        let len = self.iter.len();
+        let (taken, ret) = if len <= skip {
+            self.iter.by_ref().for_each(drop);
+            (len, None)
+        } else {
+            let trailing = (len - 1 - skip) % scale;
+            (trailing + 1, self.iter.nth_back(trailing))
+        };
+        self.sampler.count = (self.sampler.count + taken) % scale;
+        self.back = (self.back + taken) % scale;
+        ret
+    }
+}
 
-    #[test]
-    fn test_upsampling() {
-        let vec = vec![1, 2, 3];
-        let mut sampler = UpSampler::with_zero(2);
-        let mut iter = sampler.iter(vec.into_iter());
-        assert_eq!(Some(1), iter.next());
-        assert_eq!(Some(0), iter.next());
-        assert_eq!(Some(2), iter.next());
-        assert_eq!(Some(0), iter.next());
-        assert_eq!(Some(3), iter.next());
+impl<'a, I> FusedIterator for DownSampling<'a, I> where I: FusedIterator {}
 
-        let vec = vec![4, 5, 6];
-        let mut iter = sampler.iter(vec.into_iter());
-        assert_eq!(Some(0), iter.next());
-        assert_eq!(Some(4), iter.next());
-        assert_eq!(Some(0), iter.next());
-        assert_eq!(Some(5), iter.next());
-        assert_eq!(Some(0), iter.next());
-        assert_eq!(Some(6), iter.next());
-        assert_eq!(Some(0), iter.next());
-        assert_eq!(None, iter.next());
+// `size_hint` is exact whenever the inner one is, for every phase and count
+impl<'a, I> ExactSizeIterator for DownSampling<'a, I> where I: ExactSizeIterator {}
+
+/// An [`UpSampling`] that owns its sampler, from
+/// [`UpSampler::into_sampling`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnedUpSampling<I, T>
+where
+    T: Num,
+{
+    iter: I,
+    sampler: UpSampler<T>,
+}
+
+impl<I, T> OwnedUpSampling<I, T>
+where
+    T: Num,
+{
+    pub fn sampler(&self) -> &UpSampler<T> {
+        &self.sampler
     }
 
-    #[test]
-    fn test_downsampling() {
-        let vec = vec![1, 2, 3];
-        let mut sampler = DownSampler::new(2);
-        let mut iter = sampler.iter(vec.into_iter());
-        assert_eq!(Some(1), iter.next());
-        assert_eq!(Some(3), iter.next());
-        assert_eq!(None, iter.next());
+    /// The sampler, with its phase where the adaptor stopped, and what is
+    /// left of the inner iterator.
+    pub fn into_parts(self) -> (UpSampler<T>, I) {
+        (self.sampler, self.iter)
+    }
+}
 
-        let vec = vec![4, 5, 6];
-        let mut iter = sampler.iter(vec.into_iter());
-        assert_eq!(Some(5), iter.next());
-        assert_eq!(None, iter.next());
+impl<I, T> Iterator for OwnedUpSampling<I, T>
+where
+    I: Iterator<Item = T>,
+    T: Num + Clone,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.sampler.iter(&mut self.iter).next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sampler.size_hint(self.iter.size_hint())
+    }
+}
+
+impl<I, T> FusedIterator for OwnedUpSampling<I, T>
+where
+    I: FusedIterator<Item = T>,
+    T: Num + Clone,
+{
+}
+
+impl<I, T> ExactSizeIterator for OwnedUpSampling<I, T>
+where
+    I: ExactSizeIterator<Item = T>,
+    T: Num + Clone,
+{
+}
+
+/// A [`DownSampling`] that owns its sampler, from
+/// [`DownSampler::into_sampling`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedDownSampling<I> {
+    iter: I,
+    sampler: DownSampler,
+}
+
+impl<I> OwnedDownSampling<I> {
+    pub fn sampler(&self) -> &DownSampler {
+        &self.sampler
+    }
+
+    /// See [`OwnedUpSampling::into_parts`].
+    pub fn into_parts(self) -> (DownSampler, I) {
+        (self.sampler, self.iter)
+    }
+}
+
+impl<I> Iterator for OwnedDownSampling<I>
+where
+    I: Iterator,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.sampler.iter(&mut self.iter).next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sampler
+            .size_hint(self.sampler.count, self.iter.size_hint())
+    }
+}
+
+impl<I> FusedIterator for OwnedDownSampling<I> where I: FusedIterator {}
+
+impl<I> ExactSizeIterator for OwnedDownSampling<I> where I: ExactSizeIterator {}
+
+/// Sampling adaptors on any iterator, each owning a fresh sampler:
+///
+/// ```
+/// use qmf::SampleIteratorExt;
+///
+/// let out: Vec<i32> = (1..=4).downsample(2).upsample(2).collect();
+/// assert_eq!(vec![1, 0, 3, 0], out);
+/// ```
+///
+/// They are one-shot; to carry the phase over to the next block, keep an
+/// [`UpSampler`] or [`DownSampler`] instead, or take it back from
+/// [`OwnedUpSampling::into_parts`].
+///
+/// Every adaptor panics if `scale` is zero, as [`Iterator::step_by`] does.
+pub trait SampleIteratorExt: Iterator + Sized {
+    /// See [`UpSampler::with_zero`].
+    fn upsample(self, scale: usize) -> OwnedUpSampling<Self, Self::Item>
+    where
+        Self::Item: Num + Clone,
+    {
+        self.upsample_with(scale, num_traits::zero())
+    }
+
+    /// See [`UpSampler::new`].
+    fn upsample_with(self, scale: usize, with: Self::Item) -> OwnedUpSampling<Self, Self::Item>
+    where
+        Self::Item: Num + Clone,
+    {
+        let sampler = UpSampler::new(scale, with).unwrap_or_else(|err| panic!("{err}"));
+        sampler.into_sampling(self)
+    }
+
+    /// See [`DownSampler::new`].
+    fn downsample(self, scale: usize) -> OwnedDownSampling<Self> {
+        self.downsample_phase(scale, 0)
+    }
+
+    /// See [`DownSampler::with_phase`]; also panics unless `phase` is below
+    /// `scale`.
+    fn downsample_phase(self, scale: usize, phase: usize) -> OwnedDownSampling<Self> {
+        let sampler = DownSampler::with_phase(scale, phase).unwrap_or_else(|err| panic!("{err}"));
+        sampler.into_sampling(self)
+    }
+}
+
+impl<I: Iterator> SampleIteratorExt for I {}
+
+/// Samples interleaved multichannel data, one [`DownSampler`] per channel,
+/// from [`DownSampler::strided`]. The output stays interleaved.
+///
+/// Every channel keeps its own phase, so a block may end in the middle of a
+/// frame and the next one picks up with the channel after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StridedDownSampler {
+    samplers: Vec<DownSampler>,
+    // the channel of the next input
+    next: usize,
+}
+
+impl StridedDownSampler {
+    /// Runs a copy of `sampler` on each of `channels` channels.
+    pub fn from_sampler(sampler: DownSampler, channels: usize) -> Result<Self, SamplingError> {
+        if channels == 0 {
+            return Err(SamplingError::ZeroChannels);
+        }
+        Ok(Self {
+            samplers: alloc::vec![sampler; channels],
+            next: 0,
+        })
+    }
+
+    pub fn channels(&self) -> usize {
+        self.samplers.len()
+    }
+
+    pub fn sampler(&self, channel: usize) -> &DownSampler {
+        &self.samplers[channel]
+    }
+
+    pub fn reset(&mut self) {
+        self.samplers.iter_mut().for_each(DownSampler::reset);
+        self.next = 0;
+    }
+
+    pub fn iter<I: Iterator>(&mut self, iter: I) -> StridedDownSampling<'_, I> {
+        StridedDownSampling {
+            iter,
+            sampler: self,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct StridedDownSampling<'a, I> {
+    iter: I,
+    sampler: &'a mut StridedDownSampler,
+}
+
+impl<'a, I> Iterator for StridedDownSampling<'a, I>
+where
+    I: Iterator,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sampler = &mut *self.sampler;
+        loop {
+            let item = self.iter.next()?;
+            let channel = sampler.next;
+            sampler.next = (channel + 1) % sampler.samplers.len();
+            let kept = sampler.samplers[channel].iter(core::iter::once(())).next();
+            if kept.is_some() {
+                return Some(item);
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let sampler = &*self.sampler;
+        let channels = sampler.samplers.len();
+        let kept = |n: usize| {
+            (0..channels)
+                .map(|offset| {
+                    let channel = &sampler.samplers[(sampler.next + offset) % channels];
+                    let inputs = n / channels + usize::from(offset < n % channels);
+                    channel.size_hint(channel.count, (inputs, Some(inputs))).0
+                })
+                .sum()
+        };
+        let (lower, upper) = self.iter.size_hint();
+        (kept(lower), upper.map(kept))
+    }
+}
+
+impl<'a, I> FusedIterator for StridedDownSampling<'a, I> where I: FusedIterator {}
+
+impl<'a, I> ExactSizeIterator for StridedDownSampling<'a, I> where I: ExactSizeIterator {}
+
+/// Upsamples interleaved multichannel data, one [`UpSampler`] per channel,
+/// from [`UpSampler::strided`]. The output stays interleaved, a whole frame
+/// of fill values following every input frame.
+///
+/// The channels take turns, each keeping its own phase. A channel that needs
+/// an input when there is none ends the call, and the next call starts with
+/// that channel.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StridedUpSampler<T>
+where
+    T: Num,
+{
+    samplers: Vec<UpSampler<T>>,
+    // the channel of the next output
+    next: usize,
+}
+
+impl<T> StridedUpSampler<T>
+where
+    T: Num + Clone,
+{
+    /// Runs a copy of `sampler` on each of `channels` channels.
+    pub fn from_sampler(sampler: UpSampler<T>, channels: usize) -> Result<Self, SamplingError> {
+        if channels == 0 {
+            return Err(SamplingError::ZeroChannels);
+        }
+        Ok(Self {
+            samplers: alloc::vec![sampler; channels],
+            next: 0,
+        })
+    }
+
+    pub fn channels(&self) -> usize {
+        self.samplers.len()
+    }
+
+    pub fn sampler(&self, channel: usize) -> &UpSampler<T> {
+        &self.samplers[channel]
+    }
+
+    pub fn reset(&mut self) {
+        self.samplers.iter_mut().for_each(UpSampler::reset);
+        self.next = 0;
+    }
+
+    pub fn iter<I: Iterator<Item = T>>(&mut self, iter: I) -> StridedUpSampling<'_, I, T> {
+        StridedUpSampling {
+            iter,
+            sampler: self,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct StridedUpSampling<'a, I, T>
+where
+    T: Num,
+{
+    iter: I,
+    sampler: &'a mut StridedUpSampler<T>,
+}
+
+impl<'a, I, T> Iterator for StridedUpSampling<'a, I, T>
+where
+    I: Iterator<Item = T>,
+    T: Num + Clone,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sampler = &mut *self.sampler;
+        let ret = sampler.samplers[sampler.next].iter(&mut self.iter).next()?;
+        sampler.next = (sampler.next + 1) % sampler.samplers.len();
+        Some(ret)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let sampler = &*self.sampler;
+        let channels = sampler.samplers.len();
+        // the turns taken until a channel runs out of inputs
+        let turns = |n: usize| {
+            (0..channels)
+                .filter_map(|offset| {
+                    let channel = &sampler.samplers[(sampler.next + offset) % channels];
+                    let inputs = n / channels + usize::from(offset < n % channels);
+                    let own = channel.size_hint((inputs, Some(inputs))).1?;
+                    own.checked_mul(channels)?.checked_add(offset)
+                })
+                .min()
+                .unwrap_or(usize::MAX)
+        };
+        let (lower, upper) = self.iter.size_hint();
+        (turns(lower), upper.map(turns))
+    }
+}
+
+impl<'a, I, T> FusedIterator for StridedUpSampling<'a, I, T>
+where
+    I: FusedIterator<Item = T>,
+    T: Num + Clone,
+{
+}
+
+impl<'a, I, T> ExactSizeIterator for StridedUpSampling<'a, I, T>
+where
+    I: ExactSizeIterator<Item = T>,
+    T: Num + Clone,
+{
+}
+
+/// Downsamples by the mean of every group of `scale` samples instead of
+/// keeping one of them, which aliases less on slowly varying data such as
+/// envelopes.
+///
+/// A group cut short by the end of the input yields the mean of the samples
+/// it got, and the next `iter` call starts a new group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AveragingDownSampler {
+    scale: usize,
+}
+
+impl AveragingDownSampler {
+    pub fn new(scale: usize) -> Result<Self, SamplingError> {
+        if scale == 0 {
+            return Err(SamplingError::ZeroScale);
+        }
+        Ok(Self { scale })
+    }
+
+    pub fn iter<I, T>(&mut self, iter: I) -> Averaging<'_, I>
+    where
+        I: Iterator<Item = T>,
+        T: Float,
+    {
+        Averaging {
+            iter,
+            sampler: self,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Averaging<'a, I> {
+    iter: I,
+    sampler: &'a mut AveragingDownSampler,
+}
+
+impl<'a, I, T> Iterator for Averaging<'a, I>
+where
+    I: Iterator<Item = T>,
+    T: Float,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (sum, n) = self
+            .iter
+            .by_ref()
+            .take(self.sampler.scale)
+            .fold((T::zero(), 0), |(sum, n), x| (sum + x, n + 1));
+        (n > 0).then(|| sum / T::from(n).unwrap())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let scale = self.sampler.scale;
+        let (lower, upper) = self.iter.size_hint();
+        (lower.div_ceil(scale), upper.map(|n| n.div_ceil(scale)))
+    }
+}
+
+impl<'a, I, T> FusedIterator for Averaging<'a, I>
+where
+    I: FusedIterator<Item = T>,
+    T: Float,
+{
+}
+
+impl<'a, I, T> ExactSizeIterator for Averaging<'a, I>
+where
+    I: ExactSizeIterator<Item = T>,
+    T: Float,
+{
+}
+
+/// Downsamples by the extremes of every group of `scale` samples, so a
+/// transient shorter than a group still reaches a level meter or a waveform
+/// overview, where keeping one sample in `scale` would miss it.
+///
+/// Like [`DownSampler`] it carries a partial group over to the next `iter`
+/// or `min_max` call.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PeakDecimator<T> {
+    scale: usize,
+    count: usize,
+    min: T,
+    max: T,
+}
+
+impl<T> PeakDecimator<T>
+where
+    T: Float,
+{
+    pub fn new(scale: usize) -> Result<Self, SamplingError> {
+        if scale == 0 {
+            return Err(SamplingError::ZeroScale);
+        }
+        Ok(Self {
+            scale,
+            count: 0,
+            min: T::infinity(),
+            max: T::neg_infinity(),
+        })
+    }
+
+    pub fn factor(&self) -> usize {
+        self.scale
+    }
+
+    pub fn reset(&mut self) {
+        self.count = 0;
+        self.min = T::infinity();
+        self.max = T::neg_infinity();
+    }
+
+    /// Yields the largest magnitude of every group.
+    pub fn iter<I>(&mut self, iter: I) -> Peaks<'_, I, T>
+    where
+        I: Iterator<Item = T>,
+    {
+        Peaks {
+            iter,
+            decimator: self,
+        }
+    }
+
+    /// Yields the `(min, max)` of every group.
+    pub fn min_max<I>(&mut self, iter: I) -> MinMax<'_, I, T>
+    where
+        I: Iterator<Item = T>,
+    {
+        MinMax {
+            iter,
+            decimator: self,
+        }
+    }
+
+    fn next_group(&mut self, iter: &mut impl Iterator<Item = T>) -> Option<(T, T)> {
+        loop {
+            let x = iter.next()?;
+            self.min = self.min.min(x);
+            self.max = self.max.max(x);
+            self.count += 1;
+            if self.count == self.scale {
+                let group = (self.min, self.max);
+                self.reset();
+                return Some(group);
+            }
+        }
+    }
+
+    fn size_hint(&self, inner: (usize, Option<usize>)) -> (usize, Option<usize>) {
+        let kept = |n: usize| n.saturating_add(self.count) / self.scale;
+        (kept(inner.0), inner.1.map(kept))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Peaks<'a, I, T> {
+    iter: I,
+    decimator: &'a mut PeakDecimator<T>,
+}
+
+impl<'a, I, T> Iterator for Peaks<'a, I, T>
+where
+    I: Iterator<Item = T>,
+    T: Float,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (min, max) = self.decimator.next_group(&mut self.iter)?;
+        Some(min.abs().max(max.abs()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.decimator.size_hint(self.iter.size_hint())
+    }
+}
+
+impl<'a, I, T> FusedIterator for Peaks<'a, I, T>
+where
+    I: FusedIterator<Item = T>,
+    T: Float,
+{
+}
+
+impl<'a, I, T> ExactSizeIterator for Peaks<'a, I, T>
+where
+    I: ExactSizeIterator<Item = T>,
+    T: Float,
+{
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MinMax<'a, I, T> {
+    iter: I,
+    decimator: &'a mut PeakDecimator<T>,
+}
+
+impl<'a, I, T> Iterator for MinMax<'a, I, T>
+where
+    I: Iterator<Item = T>,
+    T: Float,
+{
+    type Item = (T, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.decimator.next_group(&mut self.iter)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.decimator.size_hint(self.iter.size_hint())
+    }
+}
+
+impl<'a, I, T> FusedIterator for MinMax<'a, I, T>
+where
+    I: FusedIterator<Item = T>,
+    T: Float,
+{
+}
+
+impl<'a, I, T> ExactSizeIterator for MinMax<'a, I, T>
+where
+    I: ExactSizeIterator<Item = T>,
+    T: Float,
+{
+}
+
+/// Upsamples by drawing a straight line between consecutive input samples.
+///
+/// The first sample ever seen is emitted on its own; every later sample is
+/// reached in `scale` steps from the one before it, so the interpolation
+/// carries across `iter` calls.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearUpSampler<T>
+where
+    T: Float,
+{
+    scale: usize,
+    prev: Option<T>,
+    target: Option<T>,
+    step: usize,
+}
+
+impl<T> LinearUpSampler<T>
+where
+    T: Float,
+{
+    pub fn new(scale: usize) -> Result<Self, SamplingError> {
+        if scale == 0 {
+            return Err(SamplingError::ZeroScale);
+        }
+        Ok(Self {
+            scale,
+            prev: None,
+            target: None,
+            step: 0,
+        })
+    }
+
+    pub fn reset(&mut self) {
+        self.prev = None;
+        self.target = None;
+        self.step = 0;
+    }
+
+    pub fn iter<I: Iterator<Item = T>>(&mut self, iter: I) -> LinearUpSampling<'_, I, T> {
+        LinearUpSampling {
+            iter,
+            sampler: self,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LinearUpSampling<'a, I, T>
+where
+    T: Float,
+{
+    iter: I,
+    sampler: &'a mut LinearUpSampler<T>,
+}
+
+impl<'a, I, T> Iterator for LinearUpSampling<'a, I, T>
+where
+    I: Iterator<Item = T>,
+    T: Float,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sampler = &mut *self.sampler;
+        let (prev, target) = match (sampler.prev, sampler.target) {
+            (Some(prev), Some(target)) => (prev, target),
+            (prev, _) => {
+                let x = self.iter.next()?;
+                match prev {
+                    Some(prev) => {
+                        sampler.target = Some(x);
+                        (prev, x)
+                    }
+                    None => {
+                        sampler.prev = Some(x);
+                        return Some(x);
+                    }
+                }
+            }
+        };
+
+        sampler.step += 1;
+        if sampler.step == sampler.scale {
+            sampler.prev = Some(target);
+            sampler.target = None;
+            sampler.step = 0;
+            return Some(target);
+        }
+        let t = T::from(sampler.step).unwrap() / T::from(sampler.scale).unwrap();
+        Some(prev + (target - prev) * t)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let scale = self.sampler.scale;
+        let pending = match self.sampler.target {
+            Some(_) => scale - self.sampler.step,
+            None => 0,
+        };
+        let first = self.sampler.prev.is_none();
+        let steps = |n: usize| {
+            if first && n > 0 {
+                (n - 1).checked_mul(scale)?.checked_add(pending + 1)
+            } else {
+                n.checked_mul(scale)?.checked_add(pending)
+            }
+        };
+        let (lower, upper) = self.iter.size_hint();
+        (steps(lower).unwrap_or(usize::MAX), upper.and_then(steps))
+    }
+}
+
+impl<'a, I, T> FusedIterator for LinearUpSampling<'a, I, T>
+where
+    I: FusedIterator<Item = T>,
+    T: Float,
+{
+}
+
+impl<'a, I, T> ExactSizeIterator for LinearUpSampling<'a, I, T>
+where
+    I: ExactSizeIterator<Item = T>,
+    T: Float,
+{
+}
+
+/// Changes the rate by `up / down`: zero-stuffs by `up`, runs the result
+/// through an interpolation FIR, then keeps every `down`-th sample. The
+/// filter runs in polyphase form, so only the taps that meet an input are
+/// evaluated. Like the other samplers it keeps its state across `iter`
+/// calls, so a stream can be converted in blocks of any lengths.
+///
+/// The upsampled stream carries `1 / up` of the input level, so the taps
+/// need a gain of `up` to keep the passband at unity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resampler<T>
+where
+    T: Num,
+{
+    up: usize,
+    down: usize,
+    taps: Vec<T>,
+    // the latest inputs, the newest at `head`
+    history: Vec<T>,
+    head: usize,
+    // upsampled position of the next output relative to the newest input;
+    // `up` or more means more inputs are needed first
+    offset: usize,
+}
+
+impl<T> Eq for Resampler<T> where T: Num + Eq {}
+
+impl<T> Resampler<T>
+where
+    T: Num + Clone,
+{
+    /// Plain zero-stuffing and decimation, with no interpolation filter in
+    /// between. The factors are reduced by their greatest common divisor.
+    pub fn new(up: usize, down: usize) -> Result<Self, SamplingError> {
+        if up == 0 || down == 0 {
+            return Err(SamplingError::ZeroScale);
+        }
+        let divisor = gcd(up, down);
+        Self::with_taps(up / divisor, down / divisor, &[T::one()])
+    }
+
+    /// Filters the upsampled stream with `taps`, which run at `up` times the
+    /// input rate. The factors are kept as given, as reducing them would
+    /// change that rate.
+    pub fn with_taps(up: usize, down: usize, taps: &[T]) -> Result<Self, SamplingError> {
+        if up == 0 || down == 0 {
+            return Err(SamplingError::ZeroScale);
+        }
+        if taps.is_empty() {
+            return Err(SamplingError::EmptyFilter);
+        }
+        Ok(Self {
+            up,
+            down,
+            taps: taps.to_vec(),
+            history: alloc::vec![T::zero(); taps.len().div_ceil(up)],
+            head: 0,
+            offset: up,
+        })
+    }
+
+    /// The `(up, down)` factors.
+    pub fn factors(&self) -> (usize, usize) {
+        (self.up, self.down)
+    }
+
+    pub fn taps(&self) -> &[T] {
+        &self.taps
+    }
+
+    /// How many samples the next `iter` call yields for `input_len` inputs.
+    pub fn output_len(&self, input_len: usize) -> usize {
+        // an output needs the input `offset / up` after the newest one
+        match (input_len + 1)
+            .checked_mul(self.up)
+            .and_then(|end| end.checked_sub(self.offset + 1))
+        {
+            Some(rest) => rest / self.down + 1,
+            None => 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.history.fill(T::zero());
+        self.head = 0;
+        self.offset = self.up;
+    }
+
+    pub fn iter<I: Iterator<Item = T>>(&mut self, iter: I) -> Resampling<'_, I, T> {
+        Resampling {
+            iter,
+            resampler: self,
+        }
+    }
+
+    fn push(&mut self, x: T) {
+        self.head = (self.head + 1) % self.history.len();
+        self.history[self.head] = x;
+    }
+
+    // the taps meeting the newest input are `offset`, `offset + up`, ...
+    fn output(&self) -> T {
+        let len = self.history.len();
+        self.taps
+            .get(self.offset..)
+            .unwrap_or_default()
+            .iter()
+            .step_by(self.up)
+            .enumerate()
+            .fold(T::zero(), |acc, (age, h)| {
+                let x = self.history[(self.head + len - age) % len].clone();
+                acc + h.clone() * x
+            })
+    }
+}
+
+fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Resampling<'a, I, T>
+where
+    T: Num,
+{
+    iter: I,
+    resampler: &'a mut Resampler<T>,
+}
+
+impl<'a, I, T> Eq for Resampling<'a, I, T>
+where
+    I: Eq,
+    T: Num + Eq,
+{
+}
+
+impl<'a, I, T> Iterator for Resampling<'a, I, T>
+where
+    I: Iterator<Item = T>,
+    T: Num + Clone,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let resampler = &mut *self.resampler;
+        while resampler.offset >= resampler.up {
+            resampler.push(self.iter.next()?);
+            resampler.offset -= resampler.up;
+        }
+        let y = resampler.output();
+        resampler.offset += resampler.down;
+        Some(y)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.iter.size_hint();
+        let len = |n| self.resampler.output_len(n);
+        (len(lower), upper.map(len))
+    }
+}
+
+// once an input is missing the offset stays put, so every later call asks
+// the inner iterator again
+impl<'a, I, T> FusedIterator for Resampling<'a, I, T>
+where
+    I: FusedIterator<Item = T>,
+    T: Num + Clone,
+{
+}
+
+impl<'a, I, T> ExactSizeIterator for Resampling<'a, I, T>
+where
+    I: ExactSizeIterator<Item = T>,
+    T: Num + Clone,
+{
+}
+
+/// Filter presets of [`resample`], trading speed for a flatter passband and
+/// less aliasing. Each runs a Kaiser-windowed sinc through a [`Resampler`],
+/// whose group delay is 8 samples at the lower of the two rates for `Fast`,
+/// 16 for `Medium` and 32 for `High`. [`resample`] takes that delay out
+/// again, but it is also about how long the ends of the output ring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quality {
+    /// Flat within 0.1 dB to about 60% of the lower Nyquist frequency,
+    /// rejecting about 55 dB beyond it.
+    Fast,
+    /// Flat to about 70%, rejecting about 70 dB.
+    Medium,
+    /// Flat to about 80%, rejecting about 100 dB.
+    High,
+}
+
+impl Quality {
+    // taps per branch of the longer of the two factors, the Kaiser beta and
+    // the -6 dB cutoff relative to the lower Nyquist frequency
+    fn design(self) -> (usize, f64, f64) {
+        match self {
+            Quality::Fast => (16, 5., 0.8),
+            Quality::Medium => (32, 8., 0.85),
+            Quality::High => (64, 10., 0.9),
+        }
+    }
+}
+
+/// Resamples `input` from `from_hz` to `to_hz`, returning
+/// `ceil(input.len() · to_hz / from_hz)` samples aligned with the input: the
+/// delay of the filter is taken out. Equal rates copy the input.
+///
+/// Use a [`Resampler`] for streams; this one sees the whole signal.
+///
+/// Panics if a rate is zero.
+pub fn resample<T: Float>(input: &[T], from_hz: u32, to_hz: u32, quality: Quality) -> Vec<T> {
+    assert!(from_hz > 0 && to_hz > 0, "sample rates must be nonzero");
+    if from_hz == to_hz {
+        return input.to_vec();
+    }
+    let divisor = gcd(from_hz as usize, to_hz as usize);
+    let (up, down) = (to_hz as usize / divisor, from_hz as usize / divisor);
+
+    let (branch_taps, beta, cutoff) = quality.design();
+    let longer = up.max(down);
+    // a delay of whole outputs, so they can be dropped to align the result
+    let delay = down * (branch_taps * longer).div_ceil(2 * down);
+    let taps: Vec<T> = lowpass(
+        2 * delay + 1,
+        cutoff * 0.5 / longer as f64,
+        Window::Kaiser { beta },
+    )
+    .into_iter()
+    // zero-stuffing divides the gain by `up`
+    .map(|h| T::from(h * up as f64).unwrap())
+    .collect();
+
+    let mut resampler = Resampler::with_taps(up, down, &taps).unwrap();
+    let padding = core::iter::repeat_n(T::zero(), delay.div_ceil(up));
+    resampler
+        .iter(input.iter().copied().chain(padding))
+        .skip(delay / down)
+        .take((input.len() * up).div_ceil(down))
+        .collect()
+}
+
+/// A FIR filter fused with a [`DownSampler`]: only the kept outputs are
+/// computed, and the dropped inputs just pass into the delay line. The
+/// output is identical to filtering every sample and decimating after.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolyphaseDecimator<T>
+where
+    T: Float,
+{
+    filter: DynFirFilter<T>,
+    downsampler: DownSampler,
+}
+
+impl<T> PolyphaseDecimator<T>
+where
+    T: Float,
+{
+    pub fn new(taps: &[T], factor: usize) -> Result<Self, SamplingError> {
+        Self::with_phase(taps, factor, 0)
+    }
+
+    /// Keeps the `phase`-th output of every group of `factor`, see
+    /// [`DownSampler::with_phase`].
+    pub fn with_phase(taps: &[T], factor: usize, phase: usize) -> Result<Self, SamplingError> {
+        if taps.is_empty() {
+            return Err(SamplingError::EmptyFilter);
+        }
+        Ok(Self {
+            downsampler: DownSampler::with_phase(factor, phase)?,
+            filter: DynFirFilter::from_taps(taps),
+        })
+    }
+
+    pub fn factor(&self) -> usize {
+        self.downsampler.scale
+    }
+
+    pub fn taps(&self) -> &[T] {
+        self.filter.taps()
+    }
+
+    pub fn process(&mut self, xs: &[T]) -> Vec<T> {
+        self.iter(xs.iter().copied()).collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.filter.reset();
+        self.downsampler.reset();
+    }
+
+    pub fn iter<I: Iterator<Item = T>>(&mut self, iter: I) -> Decimating<'_, I, T> {
+        Decimating {
+            iter,
+            decimator: self,
+        }
+    }
+}
+
+/// Runs `x` through `filter` and `sampler`, computing the output only if
+/// the sampler keeps it.
+pub(crate) fn decimate<T, K>(filter: &mut K, sampler: &mut DownSampler, x: T) -> Option<T>
+where
+    K: FilterKernel<T>,
+{
+    if sampler.iter(core::iter::once(())).next().is_some() {
+        Some(filter.consume(x))
+    } else {
+        filter.skip(x);
+        None
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Decimating<'a, I, T>
+where
+    T: Float,
+{
+    iter: I,
+    decimator: &'a mut PolyphaseDecimator<T>,
+}
+
+impl<'a, I, T> Iterator for Decimating<'a, I, T>
+where
+    I: Iterator<Item = T>,
+    T: Float,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let PolyphaseDecimator {
+            filter,
+            downsampler,
+        } = &mut *self.decimator;
+        self.iter.find_map(|x| decimate(filter, downsampler, x))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // as many as the downsampler alone would keep
+        let kept = |n| {
+            let mut downsampler = self.decimator.downsampler.clone();
+            downsampler.iter(core::iter::repeat_n((), n)).len()
+        };
+        let (lower, upper) = self.iter.size_hint();
+        (kept(lower), upper.map(kept))
+    }
+}
+
+impl<'a, I, T> FusedIterator for Decimating<'a, I, T>
+where
+    I: FusedIterator<Item = T>,
+    T: Float,
+{
+}
+
+impl<'a, I, T> ExactSizeIterator for Decimating<'a, I, T>
+where
+    I: ExactSizeIterator<Item = T>,
+    T: Float,
+{
+}
+
+/// A FIR filter commuted through a zero-stuffing [`UpSampler`]: the taps
+/// are split into `factor` phases that each run on the real samples only,
+/// so the stuffed zeros are never multiplied. The output is identical to
+/// upsampling and filtering after.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolyphaseInterpolator<T>
+where
+    T: Float,
+{
+    taps: Vec<T>,
+    // taps `p`, `p + factor`, ... for every phase `p`
+    phases: Vec<Vec<T>>,
+    // the latest real samples twice over, so that the newest first from
+    // `head` are contiguous
+    history: Vec<T>,
+    head: usize,
+    upsampler: UpSampler<T>,
+}
+
+impl<T> PolyphaseInterpolator<T>
+where
+    T: Float,
+{
+    pub fn new(taps: &[T], factor: usize) -> Result<Self, SamplingError> {
+        if taps.is_empty() {
+            return Err(SamplingError::EmptyFilter);
+        }
+        let upsampler = UpSampler::with_zero(factor)?;
+        let phases = (0..factor)
+            .map(|p| taps.iter().skip(p).step_by(factor).copied().collect())
+            .collect();
+        let len = taps.len().div_ceil(factor);
+        Ok(Self {
+            taps: taps.to_vec(),
+            phases,
+            history: alloc::vec![T::zero(); 2 * len],
+            head: 0,
+            upsampler,
+        })
+    }
+
+    pub fn factor(&self) -> usize {
+        self.upsampler.scale
+    }
+
+    pub fn taps(&self) -> &[T] {
+        &self.taps
+    }
+
+    pub fn process(&mut self, xs: &[T]) -> Vec<T> {
+        self.iter(xs.iter().copied()).collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.history.fill(T::zero());
+        self.head = 0;
+        self.upsampler.reset();
+    }
+
+    pub fn iter<I: Iterator<Item = T>>(&mut self, iter: I) -> Interpolating<'_, I, T> {
+        Interpolating {
+            iter,
+            interpolator: self,
+        }
+    }
+
+    fn push(&mut self, x: T) {
+        let len = self.history.len() / 2;
+        self.head = self.head.checked_sub(1).unwrap_or(len - 1);
+        self.history[self.head] = x;
+        self.history[self.head + len] = x;
+    }
+}
+
+/// Pulls the next sample of `iter` through `sampler` and `filter`, telling
+/// the filter where the stuffed zeros are.
+pub(crate) fn interpolate<T, K, I>(
+    filter: &mut K,
+    sampler: &mut UpSampler<T>,
+    iter: &mut I,
+) -> Option<T>
+where
+    T: Num + Clone,
+    K: FilterKernel<T>,
+    I: Iterator<Item = T>,
+{
+    // held or nonzero fill values are real samples too
+    let (phase, factor) = if !sampler.hold && sampler.with.is_zero() {
+        (sampler.count, sampler.scale)
+    } else {
+        (0, 1)
+    };
+    let x = sampler.iter(iter).next()?;
+    Some(filter.consume_upsampled(x, phase, factor))
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Interpolating<'a, I, T>
+where
+    T: Float,
+{
+    iter: I,
+    interpolator: &'a mut PolyphaseInterpolator<T>,
+}
+
+impl<'a, I, T> Iterator for Interpolating<'a, I, T>
+where
+    I: Iterator<Item = T>,
+    T: Float,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let interpolator = &mut *self.interpolator;
+        let phase = interpolator.upsampler.count;
+        let x = interpolator.upsampler.iter(&mut self.iter).next()?;
+        if phase == 0 {
+            interpolator.push(x);
+        }
+        let window = &interpolator.history[interpolator.head..];
+        let y = interpolator.phases[phase]
+            .iter()
+            .zip(window)
+            .fold(T::zero(), |acc, (&h, &x)| multiply_add(h, x, acc));
+        Some(y)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // as many as the upsampler alone would give
+        self.interpolator.upsampler.size_hint(self.iter.size_hint())
+    }
+}
+
+impl<'a, I, T> FusedIterator for Interpolating<'a, I, T>
+where
+    I: FusedIterator<Item = T>,
+    T: Float,
+{
+}
+
+impl<'a, I, T> ExactSizeIterator for Interpolating<'a, I, T>
+where
+    I: ExactSizeIterator<Item = T>,
+    T: Float,
+{
+}
+
+/// Checks the factors of a CIC filter and returns its registers.
+fn cic_registers(order: usize, factor: usize) -> Result<Vec<i64>, SamplingError> {
+    if factor == 0 {
+        return Err(SamplingError::ZeroScale);
+    }
+    if order == 0 {
+        return Err(SamplingError::ZeroOrder);
+    }
+    match cic_gain(order, factor) {
+        Some(gain) if bits(gain) <= 32 => Ok(alloc::vec![0; order]),
+        _ => Err(SamplingError::RegisterGrowth),
+    }
+}
+
+fn cic_gain(order: usize, factor: usize) -> Option<u64> {
+    (factor as u64).checked_pow(u32::try_from(order).ok()?)
+}
+
+// bits needed on top of a sample to hold it times `gain`
+fn bits(gain: u64) -> u32 {
+    match gain {
+        0 | 1 => 0,
+        gain => (gain - 1).ilog2() + 1,
+    }
+}
+
+/// A cascaded integrator-comb decimator: `order` integrators at the input
+/// rate, then `order` combs at the output rate, which is `1 / factor` of
+/// it. It needs no multiplications, which makes it cheap for large factors.
+///
+/// The response is `(sin(πfR) / (R·sin(πf)))^N` times the DC gain `R^N`
+/// for `R = factor` and `N = order`. Its passband droops, by about 0.4 dB
+/// for order 3 at a tenth of the output rate;
+/// [`cic_compensator`](crate::design::cic_compensator) flattens it after
+/// decimating. The registers are 64-bit and wrap, which is exact as long as
+/// the output fits, so the [`bit_growth`](Self::bit_growth) is limited to 32
+/// bits over the `i32` input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CicDecimator {
+    factor: usize,
+    integrators: Vec<i64>,
+    combs: Vec<i64>,
+    count: usize,
+}
+
+impl CicDecimator {
+    pub fn new(order: usize, factor: usize) -> Result<Self, SamplingError> {
+        let integrators = cic_registers(order, factor)?;
+        Ok(Self {
+            factor,
+            combs: integrators.clone(),
+            integrators,
+            count: 0,
+        })
+    }
+
+    pub fn order(&self) -> usize {
+        self.integrators.len()
+    }
+
+    pub fn factor(&self) -> usize {
+        self.factor
+    }
+
+    /// The DC gain `factor^order`.
+    pub fn gain(&self) -> i64 {
+        cic_gain(self.order(), self.factor).unwrap() as i64
+    }
+
+    /// Bits the output needs beyond the input, `ceil(order·log2(factor))`.
+    pub fn bit_growth(&self) -> u32 {
+        bits(self.gain() as u64)
+    }
+
+    pub fn reset(&mut self) {
+        self.integrators.fill(0);
+        self.combs.fill(0);
+        self.count = 0;
+    }
+
+    /// Yields one output for the last input of every group of `factor`,
+    /// carrying a partial group over to the next call.
+    pub fn iter<I: Iterator<Item = i32>>(&mut self, iter: I) -> CicDecimating<'_, I> {
+        CicDecimating {
+            iter,
+            decimator: self,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CicDecimating<'a, I> {
+    iter: I,
+    decimator: &'a mut CicDecimator,
+}
+
+impl<'a, I> Iterator for CicDecimating<'a, I>
+where
+    I: Iterator<Item = i32>,
+{
+    type Item = i64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cic = &mut *self.decimator;
+        loop {
+            let mut v = i64::from(self.iter.next()?);
+            for acc in cic.integrators.iter_mut() {
+                *acc = acc.wrapping_add(v);
+                v = *acc;
+            }
+            cic.count += 1;
+            if cic.count == cic.factor {
+                cic.count = 0;
+                for delayed in cic.combs.iter_mut() {
+                    (v, *delayed) = (v.wrapping_sub(*delayed), v);
+                }
+                return Some(v);
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (factor, count) = (self.decimator.factor, self.decimator.count);
+        let (lower, upper) = self.iter.size_hint();
+        let kept = |n: usize| n.saturating_add(count) / factor;
+        (kept(lower), upper.map(kept))
+    }
+}
+
+impl<'a, I> FusedIterator for CicDecimating<'a, I> where I: FusedIterator<Item = i32> {}
+
+impl<'a, I> ExactSizeIterator for CicDecimating<'a, I> where I: ExactSizeIterator<Item = i32> {}
+
+/// A cascaded integrator-comb interpolator: `order` combs at the input
+/// rate, zero-stuffing by `factor`, then `order` integrators. The DC gain
+/// is `factor^(order - 1)`, and the droop is that of [`CicDecimator`]
+/// over the input band. Like [`UpSampler`] it carries the rest of a group
+/// over to the next call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CicInterpolator {
+    factor: usize,
+    combs: Vec<i64>,
+    integrators: Vec<i64>,
+    count: usize,
+}
+
+impl CicInterpolator {
+    pub fn new(order: usize, factor: usize) -> Result<Self, SamplingError> {
+        let combs = cic_registers(order, factor)?;
+        Ok(Self {
+            factor,
+            integrators: combs.clone(),
+            combs,
+            count: 0,
+        })
+    }
+
+    pub fn order(&self) -> usize {
+        self.combs.len()
+    }
+
+    pub fn factor(&self) -> usize {
+        self.factor
+    }
+
+    /// The DC gain `factor^(order - 1)`.
+    pub fn gain(&self) -> i64 {
+        cic_gain(self.order() - 1, self.factor).unwrap() as i64
+    }
+
+    /// Bits the output needs beyond the input. The registers before the
+    /// last integrator never need more.
+    pub fn bit_growth(&self) -> u32 {
+        bits(self.gain() as u64)
+    }
+
+    pub fn reset(&mut self) {
+        self.combs.fill(0);
+        self.integrators.fill(0);
+        self.count = 0;
+    }
+
+    pub fn iter<I: Iterator<Item = i32>>(&mut self, iter: I) -> CicInterpolating<'_, I> {
+        CicInterpolating {
+            iter,
+            interpolator: self,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CicInterpolating<'a, I> {
+    iter: I,
+    interpolator: &'a mut CicInterpolator,
+}
+
+impl<'a, I> Iterator for CicInterpolating<'a, I>
+where
+    I: Iterator<Item = i32>,
+{
+    type Item = i64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cic = &mut *self.interpolator;
+        let mut v = 0;
+        if cic.count == 0 {
+            v = i64::from(self.iter.next()?);
+            for delayed in cic.combs.iter_mut() {
+                (v, *delayed) = (v.wrapping_sub(*delayed), v);
+            }
+        }
+        for acc in cic.integrators.iter_mut() {
+            *acc = acc.wrapping_add(v);
+            v = *acc;
+        }
+        cic.count = (cic.count + 1) % cic.factor;
+        Some(v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let factor = self.interpolator.factor;
+        let pending = (factor - self.interpolator.count) % factor;
+        let (lower, upper) = self.iter.size_hint();
+        let lower = lower.saturating_mul(factor).saturating_add(pending);
+        let upper = upper.and_then(|n| n.checked_mul(factor)?.checked_add(pending));
+        (lower, upper)
+    }
+}
+
+impl<'a, I> FusedIterator for CicInterpolating<'a, I> where I: FusedIterator<Item = i32> {}
+
+impl<'a, I> ExactSizeIterator for CicInterpolating<'a, I> where I: ExactSizeIterator<Item = i32> {}
+
+// fractional bits of the Farrow phase, leaving 16 integer bits for the ratio
+const FARROW_FRAC_BITS: u32 = 48;
+
+/// Resamples by any ratio, which can change while running, with a cubic
+/// Lagrange interpolator in Farrow form. The ratio is the input samples
+/// advanced per output, so `2.0` halves the rate and `0.5` doubles it.
+///
+/// The phase is fixed point with 48 fractional bits, independent of `T`,
+/// so it accumulates without drift: after `n` outputs at one ratio exactly
+/// `n` times the ratio as rounded to those bits has been consumed.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FarrowResampler<T> {
+    step: u64,
+    // input samples to pull before the next output, then the fraction
+    // between `window[1]` and `window[2]` it falls at
+    phase: u64,
+    // the latest four inputs, oldest first
+    window: [T; 4],
+}
+
+impl<T> FarrowResampler<T>
+where
+    T: Float,
+{
+    pub fn new(ratio: f64) -> Result<Self, SamplingError> {
+        let mut resampler = Self {
+            step: 0,
+            phase: 1 << FARROW_FRAC_BITS,
+            window: [T::zero(); 4],
+        };
+        resampler.set_ratio(ratio)?;
+        Ok(resampler)
+    }
+
+    /// Changes the ratio from the next output on, keeping the phase.
+    pub fn set_ratio(&mut self, ratio: f64) -> Result<(), SamplingError> {
+        let step = ratio * (1u64 << FARROW_FRAC_BITS) as f64;
+        if !(step >= 1. && step < u64::MAX as f64) {
+            return Err(SamplingError::InvalidRatio);
+        }
+        self.step = step.round() as u64;
+        Ok(())
+    }
+
+    /// The ratio as rounded to the phase resolution.
+    pub fn ratio(&self) -> f64 {
+        self.step as f64 / (1u64 << FARROW_FRAC_BITS) as f64
+    }
+
+    /// Inputs the output lags behind: an output at input position `p` needs
+    /// the input two after it.
+    pub fn delay(&self) -> usize {
+        2
+    }
+
+    pub fn reset(&mut self) {
+        self.phase = 1 << FARROW_FRAC_BITS;
+        self.window = [T::zero(); 4];
+    }
+
+    /// Pulls as many inputs as the next output needs. `None` once `input`
+    /// runs out, after which a later call picks up where this one stopped.
+    pub fn next_output(&mut self, input: &mut impl Iterator<Item = T>) -> Option<T> {
+        while self.phase >> FARROW_FRAC_BITS > 0 {
+            let x = input.next()?;
+            self.window = [self.window[1], self.window[2], self.window[3], x];
+            self.phase -= 1 << FARROW_FRAC_BITS;
+        }
+        let mu = self.phase as f64 / (1u64 << FARROW_FRAC_BITS) as f64;
+        let mu = T::from(mu).unwrap();
+        self.phase += self.step;
+
+        let [x0, x1, x2, x3] = self.window;
+        let (two, three, six) = (
+            T::from(2).unwrap(),
+            T::from(3).unwrap(),
+            T::from(6).unwrap(),
+        );
+        let c1 = x2 - x0 / three - x1 / two - x3 / six;
+        let c2 = (x0 + x2) / two - x1;
+        let c3 = (x3 - x0) / six + (x1 - x2) / two;
+        Some(((c3 * mu + c2) * mu + c1) * mu + x1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fir::DynFirFilter,
+        metrics::snr,
+        sampling::{
+            resample, AveragingDownSampler, CicDecimator, CicInterpolator, DownSampler,
+            FarrowResampler, LinearUpSampler, OwnedDownSampling, OwnedUpSampling, PeakDecimator,
+            PolyphaseDecimator, PolyphaseInterpolator, Quality, Resampler, SampleIteratorExt,
+            SamplingError, UpSampler,
+        },
+        test_util::{allocations, white_noise},
+    };
+    use core::f64::consts::PI;
+
+    #[test]
+    fn test_upsampling() {
+        let vec = vec![1, 2, 3];
+        let mut sampler = UpSampler::with_zero(2).unwrap();
+        let mut iter = sampler.iter(vec.into_iter());
+        assert_eq!(Some(1), iter.next());
+        assert_eq!(Some(0), iter.next());
+        assert_eq!(Some(2), iter.next());
+        assert_eq!(Some(0), iter.next());
+        assert_eq!(Some(3), iter.next());
+
+        let vec = vec![4, 5, 6];
+        let mut iter = sampler.iter(vec.into_iter());
+        assert_eq!(Some(0), iter.next());
+        assert_eq!(Some(4), iter.next());
+        assert_eq!(Some(0), iter.next());
+        assert_eq!(Some(5), iter.next());
+        assert_eq!(Some(0), iter.next());
+        assert_eq!(Some(6), iter.next());
+        assert_eq!(Some(0), iter.next());
+        assert_eq!(None, iter.next());
+    }
+
+    #[test]
+    fn test_downsampling() {
+        let vec = vec![1, 2, 3];
+        let mut sampler = DownSampler::new(2).unwrap();
+        let mut iter = sampler.iter(vec.into_iter());
+        assert_eq!(Some(1), iter.next());
+        assert_eq!(Some(3), iter.next());
+        assert_eq!(None, iter.next());
+
+        let vec = vec![4, 5, 6];
+        let mut iter = sampler.iter(vec.into_iter());
+        assert_eq!(Some(5), iter.next());
+        assert_eq!(None, iter.next());
+    }
+
+    #[test]
+    fn test_sampler_reset() {
+        let mut down = DownSampler::new(2).unwrap();
+        let out: Vec<_> = down.iter(vec![1, 2, 3].into_iter()).collect();
+        assert_eq!(vec![1, 3], out);
+        // without the reset the first input would be skipped
+        down.reset();
+        assert_eq!(Some(4), down.iter(vec![4, 5, 6].into_iter()).next());
+
+        let mut up = UpSampler::with_zero(3).unwrap();
+        assert_eq!(Some(1), up.iter(vec![1].into_iter()).next());
+        // and a fill value owed to the 1 would come first
+        up.reset();
+        assert_eq!(Some(7), up.iter(vec![7].into_iter()).next());
+    }
+
+    #[test]
+    fn test_phase_across_partial_buffers() {
+        let mut up = UpSampler::with_zero(3).unwrap();
+        // a buffer of outputs ending anywhere in a group leaves the phase there
+        let mut inputs = 1..;
+        let mut outputs = vec![];
+        for (len, phase) in [(2, 2), (4, 0), (1, 1), (5, 0)] {
+            outputs.extend(up.iter(&mut inputs).take(len));
+            assert_eq!(phase, up.phase(), "{len}");
+        }
+        assert_eq!(vec![1, 0, 0, 2, 0, 0, 3, 0, 0, 4, 0, 0], outputs);
+
+        let mut down = DownSampler::with_phase(3, 2).unwrap();
+        let mut outputs = vec![];
+        for (buffer, phase) in [(&[1, 2][..], 2), (&[3, 4, 5, 6], 0), (&[7], 1), (&[], 1)] {
+            outputs.extend(down.iter(buffer.iter().copied()));
+            assert_eq!(phase, down.phase(), "{buffer:?}");
+        }
+        assert_eq!(vec![3, 6], outputs);
+    }
+
+    #[test]
+    fn test_process_into() {
+        let noise = white_noise(64, 21);
+        let input: Vec<i32> = noise.iter().map(|x| (x * 100.) as i32).collect();
+        let mut lengths = noise.iter().map(|x| ((x + 1.) * 8.) as usize).cycle();
+        for scale in 1..4 {
+            for phase in 0..scale {
+                let mut up = UpSampler::with_zero(scale).unwrap();
+                let mut down = DownSampler::with_phase(scale, phase).unwrap();
+                up.set_phase(phase).unwrap();
+                down.set_phase(scale - 1 - phase).unwrap();
+                let (mut up_iter, mut down_iter) = (up.clone(), down.clone());
+                let mut start = 0;
+                for _ in 0..6 {
+                    let (len, capacity) = (lengths.next().unwrap(), lengths.next().unwrap());
+                    let block = &input[start..(start + len).min(input.len())];
+                    start = (start + len).min(input.len());
+
+                    let mut out = vec![0; capacity];
+                    let written = up.process_into(block, &mut out);
+                    let expected: Vec<_> =
+                        up_iter.iter(block.iter().copied()).take(capacity).collect();
+                    assert_eq!(expected, out[..written]);
+                    assert_eq!(up_iter, up);
+
+                    let written = down.process_into(block, &mut out);
+                    let expected: Vec<_> = down_iter
+                        .iter(block.iter().copied())
+                        .take(capacity)
+                        .collect();
+                    assert_eq!(expected, out[..written]);
+                    assert_eq!(down_iter, down);
+                }
+            }
+        }
+
+        // a short buffer stops early and says so
+        let mut up = UpSampler::with_zero(2).unwrap();
+        let mut out = [0; 3];
+        let (written, count) = allocations(|| up.process_into(&[1, 2, 3], &mut out));
+        assert_eq!((3, 0), (written, count));
+        assert_eq!([1, 0, 2], out);
+        assert_eq!(1, up.phase());
+    }
+
+    #[test]
+    fn test_owned_sampling() {
+        // a pipeline stored in a struct, fed one buffer at a time
+        struct Pipeline {
+            stage: Option<OwnedDownSampling<OwnedUpSampling<alloc::vec::IntoIter<i32>, i32>>>,
+        }
+
+        impl Pipeline {
+            fn process(&mut self, buffer: Vec<i32>) -> Vec<i32> {
+                let (down, rest) = self.stage.take().unwrap().into_parts();
+                let (up, _) = rest.into_parts();
+                let mut stage = down.into_sampling(up.into_sampling(buffer.into_iter()));
+                let out = stage.by_ref().collect();
+                self.stage = Some(stage);
+                out
+            }
+        }
+
+        let up = UpSampler::with_zero(3).unwrap();
+        let down = DownSampler::new(2).unwrap();
+        let mut pipeline = Pipeline {
+            stage: Some(down.into_sampling(up.into_sampling(vec![].into_iter()))),
+        };
+        let mut up = UpSampler::with_zero(3).unwrap();
+        let mut down = DownSampler::new(2).unwrap();
+        for buffer in [vec![1, 2, 3], vec![4], vec![5, 6, 7, 8, 9]] {
+            let expected: Vec<_> = down.iter(up.iter(buffer.clone().into_iter())).collect();
+            assert_eq!(expected, pipeline.process(buffer));
+        }
+
+        // and one returned from a function, behind a box
+        fn decimate(src: Box<dyn Iterator<Item = i32>>) -> Box<dyn Iterator<Item = i32>> {
+            Box::new(DownSampler::new(2).unwrap().into_sampling(src))
+        }
+        let out: Vec<_> = decimate(Box::new(1..8)).collect();
+        assert_eq!(vec![1, 3, 5, 7], out);
+
+        let stage = DownSampler::new(3).unwrap().into_sampling(0..10);
+        assert_eq!(4, stage.len());
+    }
+
+    #[test]
+    fn test_set_scale() {
+        let mut down = DownSampler::with_phase(2, 1).unwrap();
+        assert_eq!(vec![2], down.iter(1..4).collect::<Vec<_>>());
+        // the 3 started a group at scale 2, which the switch abandons
+        down.set_scale(3).unwrap();
+        assert_eq!(0, down.phase());
+        assert_eq!(vec![5, 8], down.iter(4..10).collect::<Vec<_>>());
+        assert_eq!(
+            Err(SamplingError::PhaseOutOfRange),
+            DownSampler::with_phase(3, 2).unwrap().set_scale(2)
+        );
+
+        let mut up = UpSampler::with_zero(2).unwrap();
+        assert_eq!(vec![1, 0, 2], up.iter(1..3).take(3).collect::<Vec<_>>());
+        // the zero owed to the 2 goes with the old scale
+        up.set_scale(3).unwrap();
+        assert_eq!(vec![3, 0, 0, 4, 0, 0], up.iter(3..5).collect::<Vec<_>>());
+
+        assert_eq!(Err(SamplingError::ZeroScale), up.set_scale(0));
+        assert_eq!(Err(SamplingError::ZeroScale), down.set_scale(0));
+        assert_eq!(3, up.iter(0..1).len());
+    }
+
+    #[test]
+    fn test_set_phase() {
+        let mut up = UpSampler::with_zero(3).unwrap();
+        up.set_phase(1).unwrap();
+        assert_eq!(
+            vec![0, 0, 5, 0, 0],
+            up.iter([5].into_iter()).collect::<Vec<_>>()
+        );
+        assert_eq!(Err(SamplingError::PhaseOutOfRange), up.set_phase(3));
+        assert_eq!(0, up.phase());
+
+        let mut down = DownSampler::new(3).unwrap();
+        down.set_phase(2).unwrap();
+        assert_eq!(vec![2, 5], down.iter(1..6).collect::<Vec<_>>());
+        assert_eq!(Err(SamplingError::PhaseOutOfRange), down.set_phase(3));
+        assert_eq!(1, down.phase());
+    }
+
+    #[test]
+    fn test_zero_scale() {
+        assert_eq!(Some(SamplingError::ZeroScale), DownSampler::new(0).err());
+        assert_eq!(
+            Some(SamplingError::ZeroScale),
+            UpSampler::<i32>::with_zero(0).err()
+        );
+        assert_eq!(Some(SamplingError::ZeroScale), UpSampler::new(0, 1.5).err());
+    }
+
+    #[test]
+    fn test_upsampling_size_hint() {
+        let mut sampler = UpSampler::with_zero(2).unwrap();
+        let iter = sampler.iter(vec![1, 2, 3].into_iter());
+        assert_eq!((6, Some(6)), iter.size_hint());
+        let out: Vec<_> = iter.take(5).collect();
+        assert_eq!(vec![1, 0, 2, 0, 3], out);
+
+        // the fill owed for the 3 comes first
+        let out: Vec<_> = sampler.iter(vec![4, 5, 6].into_iter()).collect();
+        assert_eq!(7, out.len());
+        assert_eq!(7, out.capacity());
+    }
+
+    #[test]
+    fn test_downsampling_size_hint() {
+        let mut sampler = DownSampler::new(3).unwrap();
+        let out: Vec<_> = sampler
+            .iter((0..14).collect::<Vec<_>>().into_iter())
+            .collect();
+        assert_eq!(vec![0, 3, 6, 9, 12], out);
+        assert_eq!(5, out.capacity());
+
+        // two samples of the next group were already consumed
+        for (len, expected) in [(0, 0), (1, 0), (2, 1), (4, 1), (5, 2), (14, 5)] {
+            let mut sampler = sampler.clone();
+            let iter = sampler.iter(vec![0; len].into_iter());
+            assert_eq!((expected, Some(expected)), iter.size_hint());
+            assert_eq!(expected, iter.count());
+        }
+    }
+
+    #[test]
+    fn test_downsampling_split_anywhere() {
+        for scale in [2, 3, 5] {
+            for phase in 0..scale {
+                let sampler = DownSampler::with_phase(scale, phase).unwrap();
+                let whole: Vec<_> = sampler.clone().iter(0..23).collect();
+                for cuts in [[1, 2], [4, 11], [7, 7], [13, 22]] {
+                    let mut sampler = sampler.clone();
+                    let mut parts: Vec<_> = sampler.iter(0..cuts[0]).collect();
+                    parts.extend(sampler.iter(cuts[0]..cuts[1]));
+                    parts.extend(sampler.iter(cuts[1]..23));
+                    assert_eq!(whole, parts, "{scale}, {phase}, {cuts:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_exact_sizes() {
+        // the length stays exact as the adapter is drained
+        fn assert_exact(mut iter: impl ExactSizeIterator) {
+            let mut len = iter.len();
+            while iter.next().is_some() {
+                len -= 1;
+                assert_eq!(len, iter.len());
+            }
+            assert_eq!(0, len);
+        }
+
+        for scale in 1..5 {
+            for kept in 0..scale {
+                for consumed in 0..scale {
+                    let mut down = DownSampler::with_phase(scale, kept).unwrap();
+                    down.set_phase(consumed).unwrap();
+                    for len in 0..10 {
+                        let out: Vec<_> = down.clone().iter(0..len).collect();
+                        assert_eq!(out.len(), down.clone().iter(0..len).len());
+                        assert_exact(down.clone().iter(0..len));
+                    }
+                    let out: Vec<_> = down.iter(0..100).collect();
+                    assert_eq!(out.len(), out.capacity(), "{scale}, {kept}, {consumed}");
+                }
+            }
+            for phase in 0..scale {
+                let mut up = UpSampler::with_zero(scale).unwrap();
+                up.set_phase(phase).unwrap();
+                for len in 0..10 {
+                    let out: Vec<_> = up.clone().iter(0..len).collect();
+                    assert_eq!(out.len(), up.clone().iter(0..len).len());
+                    assert_exact(up.clone().iter(0..len));
+                }
+            }
+        }
+
+        let mut up = UpSampler::hold(3).unwrap();
+        up.iter(0..2).take(4).for_each(drop);
+        assert_eq!(3 * 5 + 2, up.iter(0..5).len());
+        let mut linear = LinearUpSampler::new(4).unwrap();
+        assert_eq!(9, linear.iter(vec![0., 1., 2.].into_iter()).len());
+        let mut averaging = AveragingDownSampler::new(4).unwrap();
+        assert_eq!(3, averaging.iter(vec![0.; 9].into_iter()).len());
+        let mut resampler = Resampler::new(3, 2).unwrap();
+        let out: Vec<_> = resampler.iter(0..10).collect();
+        assert_eq!((15, 15), (out.len(), out.capacity()));
+    }
+
+    #[test]
+    fn test_downsampling_phase() {
+        let mut sampler = DownSampler::with_phase(2, 1).unwrap();
+        let out: Vec<_> = sampler.iter(vec![1, 2, 3, 4].into_iter()).collect();
+        assert_eq!(vec![2, 4], out);
+
+        let mut sampler = DownSampler::with_phase(3, 2).unwrap();
+        let iter = sampler.iter(vec![1, 2, 3, 4, 5, 6, 7, 8].into_iter());
+        assert_eq!((2, Some(2)), iter.size_hint());
+        assert_eq!(vec![3, 6], iter.collect::<Vec<_>>());
+
+        assert_eq!(
+            Some(SamplingError::PhaseOutOfRange),
+            DownSampler::with_phase(2, 2).err()
+        );
+    }
+
+    #[test]
+    fn test_downsampling_rev() {
+        for phase in 0..3 {
+            for offset in 0..3 {
+                for len in 0..8 {
+                    let mut forward = DownSampler::with_phase(3, phase).unwrap();
+                    forward.iter(0..offset).for_each(drop);
+                    let mut backward = forward.clone();
+
+                    let mut expected: Vec<_> = forward.iter(0..len).collect();
+                    expected.reverse();
+                    let reversed: Vec<_> = backward.iter(0..len).rev().collect();
+                    assert_eq!(expected, reversed, "{phase}, {offset}, {len}");
+                    // the next block continues from the same phase either way
+                    assert_eq!(forward, backward, "{phase}, {offset}, {len}");
+                }
+            }
+        }
+
+        let mut down = DownSampler::new(2).unwrap();
+        let mut both = down.iter(0..7);
+        assert_eq!(Some(6), both.next_back());
+        assert_eq!(Some(0), both.next());
+        assert_eq!(vec![4, 2], both.rev().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_downsampling_phase_across_calls() {
+        let mut sampler = DownSampler::with_phase(2, 1).unwrap();
+        let out: Vec<_> = sampler.iter(0..8).collect();
+        assert_eq!(vec![1, 3, 5, 7], out);
+
+        // a block ending mid-group leaves the kept sample to the next one
+        sampler.reset();
+        let out: Vec<_> = sampler.iter(0..3).collect();
+        assert_eq!(vec![1], out);
+        let out: Vec<_> = sampler.iter(3..8).collect();
+        assert_eq!(vec![3, 5, 7], out);
+    }
+
+    #[test]
+    fn test_strided_sampling() {
+        // interleaved ramps, left counting up and right down
+        let stereo: Vec<i32> = (0..12).flat_map(|n| [n, -n]).collect();
+        let mut down = DownSampler::strided(2, 2).unwrap();
+        let iter = down.iter(stereo.iter().copied());
+        assert_eq!((12, Some(12)), iter.size_hint());
+        let out: Vec<_> = iter.collect();
+        let mut mono = DownSampler::new(2).unwrap();
+        let left: Vec<_> = mono.iter(0..12).collect();
+        let expected: Vec<_> = left.iter().flat_map(|&n| [n, -n]).collect();
+        assert_eq!(expected, out);
+
+        // a block ending mid-frame leaves each channel at its own phase
+        down.reset();
+        let mut out: Vec<_> = down.iter(stereo[..5].iter().copied()).collect();
+        assert_eq!((1, 0), (down.sampler(0).phase(), down.sampler(1).phase()));
+        out.extend(down.iter(stereo[5..].iter().copied()));
+        assert_eq!(expected, out);
+
+        let mut up = UpSampler::strided(2, 2).unwrap();
+        let out: Vec<_> = up.iter([1, -1, 2, -2].into_iter()).collect();
+        assert_eq!(vec![1, -1, 0, 0, 2, -2, 0, 0], out);
+        let iter = up.iter([3, -3, 4].into_iter());
+        assert_eq!((5, Some(5)), iter.size_hint());
+        assert_eq!(vec![3, -3, 0, 0, 4], iter.collect::<Vec<_>>());
+        let iter = up.iter([-4].into_iter());
+        assert_eq!((3, Some(3)), iter.size_hint());
+        assert_eq!(vec![-4, 0, 0], iter.collect::<Vec<_>>());
+
+        assert_eq!(
+            Some(SamplingError::ZeroChannels),
+            DownSampler::strided(2, 0).err()
+        );
+        assert_eq!(
+            Some(SamplingError::ZeroChannels),
+            UpSampler::<i32>::strided(2, 0).err()
+        );
+    }
+
+    #[test]
+    fn test_resample() {
+        // band-limited well below the 22.05 kHz Nyquist frequency
+        let tones = |tones: &'static [(f64, f64)], rate: f64, len: usize| -> Vec<f64> {
+            (0..len)
+                .map(|n| {
+                    let t = n as f64 / rate;
+                    tones
+                        .iter()
+                        .map(|&(hz, amplitude)| amplitude * (2. * PI * hz * t).sin())
+                        .sum()
+                })
+                .collect()
+        };
+        let signal = |rate, len| tones(&[(1000., 0.5), (5500., 0.3), (12000., 0.2)], rate, len);
+        let input = signal(44100., 4410);
+        // the ends ring for about the length of the filter
+        let interior = 200..4000;
+
+        let up = resample(&input, 44100, 48000, Quality::Medium);
+        assert_eq!(4800, up.len());
+        let expected = signal(48000., 4800);
+        assert!(snr(&expected[interior.clone()], &up[interior.clone()]) > 60.);
+
+        let back = resample(&up, 48000, 44100, Quality::Medium);
+        assert_eq!(input.len(), back.len());
+        let round_trip = snr(&input[interior.clone()], &back[interior.clone()]);
+        assert!(round_trip > 60., "{round_trip} dB");
+
+        // within 60% of the 16 kHz Nyquist frequency
+        let low = |rate, len| tones(&[(1000., 0.5), (4000., 0.3), (9000., 0.2)], rate, len);
+        for (quality, min_snr) in [
+            (Quality::Fast, 50.),
+            (Quality::Medium, 80.),
+            (Quality::High, 110.),
+        ] {
+            let down = resample(&low(44100., 4410), 44100, 32000, quality);
+            assert_eq!(3200, down.len());
+            let expected = low(32000., 3200);
+            let quality_snr = snr(&expected[200..3000], &down[200..3000]);
+            assert!(quality_snr > min_snr, "{quality:?}: {quality_snr} dB");
+        }
+
+        assert_eq!(input, resample(&input, 44100, 44100, Quality::Fast));
+        assert_eq!(2, resample(&[1f32; 3], 48000, 32000, Quality::Fast).len());
+    }
+
+    #[test]
+    fn test_sample_iterator_ext() {
+        let signal = [1, 2, 3, 4, 5, 6, 7];
+        let mut down = DownSampler::with_phase(3, 1).unwrap();
+        let expected: Vec<_> = down.iter(signal.iter().copied()).collect();
+        let out: Vec<_> = signal.iter().copied().downsample_phase(3, 1).collect();
+        assert_eq!(expected, out);
+        assert_eq!(3, signal.iter().downsample(3).len());
+
+        let mut up = UpSampler::new(2, 9).unwrap();
+        let expected: Vec<_> = up.iter(signal.iter().copied()).collect();
+        let out: Vec<_> = signal.iter().copied().upsample_with(2, 9).collect();
+        assert_eq!(expected, out);
+
+        let out: Vec<i32> = signal
+            .iter()
+            .map(|x| x * 10)
+            .downsample(2)
+            .upsample(2)
+            .take(5)
+            .map(|x| x + 1)
+            .collect();
+        assert_eq!(vec![11, 1, 31, 1, 51], out);
+    }
+
+    #[test]
+    #[should_panic(expected = "sampling scale must be nonzero")]
+    fn test_sample_iterator_ext_zero_scale() {
+        let _ = (0..4).downsample(0);
+    }
+
+    #[test]
+    fn test_averaging_downsampling() {
+        let mut sampler = AveragingDownSampler::new(4).unwrap();
+        let iter = sampler.iter(vec![1., 3., 5., 7., 2., 2., 2., 2., 10., 20.].into_iter());
+        assert_eq!((3, Some(3)), iter.size_hint());
+        // the trailing pair is averaged on its own
+        assert_eq!(vec![4., 2., 15.], iter.collect::<Vec<_>>());
+
+        let out: Vec<f64> = sampler.iter(vec![-1., 1., 4., 0.].into_iter()).collect();
+        assert_eq!(vec![1.], out);
+        assert_eq!(None, sampler.iter(core::iter::empty::<f64>()).next());
+
+        assert_eq!(
+            Some(SamplingError::ZeroScale),
+            AveragingDownSampler::new(0).err()
+        );
+    }
+
+    #[test]
+    fn test_peak_decimator() {
+        // a spike anywhere in a group survives, also across a call boundary
+        for position in 0..8 {
+            let mut input = [0.1; 8];
+            input[position] = -5.;
+            let mut decimator = PeakDecimator::new(4).unwrap();
+            let mut out: Vec<f64> = decimator.iter(input[..3].iter().copied()).collect();
+            out.extend(decimator.iter(input[3..].iter().copied()));
+            let expected = if position < 4 { [5., 0.1] } else { [0.1, 5.] };
+            assert_eq!(expected, out[..]);
+        }
+
+        let mut decimator = PeakDecimator::new(3).unwrap();
+        let iter = decimator.min_max([1., -2., 3., 0.5, 0.25].into_iter());
+        assert_eq!((1, Some(1)), iter.size_hint());
+        assert_eq!(vec![(-2., 3.)], iter.collect::<Vec<_>>());
+        let out: Vec<_> = decimator.min_max([4.].into_iter()).collect();
+        assert_eq!(vec![(0.25, 4.)], out);
+
+        decimator.min_max([9.].into_iter()).for_each(drop);
+        decimator.reset();
+        assert_eq!(0, decimator.iter([1., 1.].into_iter()).len());
+        assert_eq!(
+            Some(SamplingError::ZeroScale),
+            PeakDecimator::<f32>::new(0).err()
+        );
+    }
+
+    #[test]
+    fn test_linear_upsampling() {
+        let mut sampler = LinearUpSampler::new(4).unwrap();
+        let iter = sampler.iter(vec![0., 4.].into_iter());
+        assert_eq!((5, Some(5)), iter.size_hint());
+        assert_eq!(vec![0., 1., 2., 3., 4.], iter.collect::<Vec<_>>());
+
+        // the ramp continues from the last sample of the previous block
+        let mut iter = sampler.iter(vec![8., 0.].into_iter());
+        assert_eq!(Some(5.), iter.next());
+        assert_eq!((7, Some(7)), iter.size_hint());
+        let rest: Vec<_> = iter.collect();
+        assert_eq!(vec![6., 7., 8., 6., 4., 2., 0.], rest);
+
+        sampler.reset();
+        let out: Vec<_> = sampler.iter(vec![2., 2.].into_iter()).collect();
+        assert_eq!(vec![2., 2., 2., 2., 2.], out);
+
+        assert_eq!(
+            Some(SamplingError::ZeroScale),
+            LinearUpSampler::<f64>::new(0).err()
+        );
+    }
+
+    #[test]
+    fn test_linear_upsampling_by_two() {
+        let mut sampler = LinearUpSampler::new(2).unwrap();
+        let out: Vec<_> = sampler.iter(vec![0., 2.].into_iter()).collect();
+        assert_eq!(vec![0., 1., 2.], out);
+
+        // the output stops on the last input, and the next block starts from it
+        let mut iter = sampler.iter(vec![6.].into_iter());
+        assert_eq!(Some(4.), iter.next());
+        assert_eq!(Some(6.), iter.next());
+        assert_eq!(None, iter.next());
+        let out: Vec<_> = sampler.iter(vec![5., 5.].into_iter()).collect();
+        assert_eq!(vec![5.5, 5., 5., 5.], out);
+    }
+
+    #[test]
+    fn test_hold_upsampling_across_calls() {
+        let vec = vec![1, 2, 3];
+        let mut sampler = UpSampler::hold(2).unwrap();
+        let mut iter = sampler.iter(vec.into_iter());
+        assert_eq!(Some(1), iter.next());
+        assert_eq!(Some(1), iter.next());
+        assert_eq!(Some(2), iter.next());
+        assert_eq!(Some(2), iter.next());
+        assert_eq!(Some(3), iter.next());
+
+        let vec = vec![4, 5, 6];
+        let mut iter = sampler.iter(vec.into_iter());
+        assert_eq!(Some(3), iter.next());
+        assert_eq!(Some(4), iter.next());
+        assert_eq!(Some(4), iter.next());
+        assert_eq!(Some(5), iter.next());
+
+        // a reset mid-repeat drops the held value with the phase
+        sampler.reset();
+        let mut iter = sampler.iter(vec![8].into_iter());
+        assert_eq!(Some(8), iter.next());
+        assert_eq!(Some(8), iter.next());
+        assert_eq!(None, iter.next());
+    }
+
+    #[test]
+    fn test_hold_upsampling() {
+        let mut sampler = UpSampler::hold(2).unwrap();
+        let out: Vec<_> = sampler.iter(vec![1, 2, 3].into_iter()).collect();
+        assert_eq!(vec![1, 1, 2, 2, 3, 3], out);
+
+        // a repeat cut off at the end of a block is finished by the next one
+        assert_eq!(Some(7), sampler.iter(vec![7].into_iter()).next());
+        let out: Vec<_> = sampler.iter(vec![9].into_iter()).collect();
+        assert_eq!(vec![7, 9, 9], out);
+
+        sampler.iter(vec![4].into_iter()).next();
+        sampler.reset();
+        let out: Vec<_> = sampler.iter(vec![5, 6].into_iter()).collect();
+        assert_eq!(vec![5, 5, 6, 6], out);
+    }
+
+    #[test]
+    fn test_resampling() {
+        let mut resampler = Resampler::new(3, 2).unwrap();
+        assert_eq!((3, 2), resampler.factors());
+        let out: Vec<_> = resampler.iter(vec![1, 2, 3, 4].into_iter()).collect();
+        assert_eq!(vec![1, 0, 0, 3, 0, 0], out);
+
+        let mut resampler = Resampler::new(2, 4).unwrap();
+        assert_eq!((1, 2), resampler.factors());
+        let out: Vec<_> = resampler.iter(vec![1, 2, 3, 4, 5].into_iter()).collect();
+        assert_eq!(vec![1, 3, 5], out);
+        let out: Vec<_> = resampler.iter(vec![6, 7, 8].into_iter()).collect();
+        assert_eq!(vec![7], out);
+
+        assert_eq!(
+            Some(SamplingError::ZeroScale),
+            Resampler::<i32>::new(0, 2).err()
+        );
+    }
+
+    // a windowed-sinc lowpass at `up` times the input rate, cutting off below
+    // both the input and output Nyquist frequencies, with a gain of `up`
+    fn interpolation_taps(up: usize, down: usize, len: usize) -> Vec<f64> {
+        let cutoff = 0.45 / up.max(down) as f64;
+        let center = (len - 1) as f64 / 2.;
+        (0..len)
+            .map(|n| {
+                let t = n as f64 - center;
+                let sinc = if t == 0. {
+                    2. * cutoff
+                } else {
+                    (2. * PI * cutoff * t).sin() / (PI * t)
+                };
+                let window = 0.5 - 0.5 * (2. * PI * n as f64 / (len - 1) as f64).cos();
+                up as f64 * sinc * window
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_filtered_resampling() {
+        let f0 = 0.04;
+        let input: Vec<f64> = (0..600).map(|n| (2. * PI * f0 * n as f64).sin()).collect();
+        for (up, down) in [(3, 2), (2, 3), (147, 160)] {
+            let taps = interpolation_taps(up, down, 24 * up.max(down) + 1);
+            let mut resampler = Resampler::with_taps(up, down, &taps).unwrap();
+            let len = resampler.output_len(input.len());
+            let output: Vec<f64> = resampler.iter(input.iter().copied()).collect();
+            assert_eq!(len, output.len());
+
+            // past the filter transient the output fits a sine at the converted
+            // frequency and the original amplitude
+            let f = f0 * down as f64 / up as f64;
+            let settled = &output[taps.len() / down + 1..];
+            let (mut s, mut c) = (0., 0.);
+            for (n, y) in settled.iter().enumerate() {
+                s += y * (2. * PI * f * n as f64).sin();
+                c += y * (2. * PI * f * n as f64).cos();
+            }
+            let (s, c) = (2. * s / settled.len() as f64, 2. * c / settled.len() as f64);
+            let amplitude = (s * s + c * c).sqrt();
+            assert!((amplitude - 1.).abs() < 0.02, "{up}/{down}: {amplitude}");
+            let residual = settled.iter().enumerate().map(|(n, y)| {
+                let phase = 2. * PI * f * n as f64;
+                (y - s * phase.sin() - c * phase.cos()).abs()
+            });
+            let residual = residual.fold(0., f64::max);
+            assert!(residual < 0.02, "{up}/{down}: {residual}");
+        }
+    }
+
+    #[test]
+    fn test_resampling_across_calls() {
+        let input = white_noise(200, 4);
+        let taps = interpolation_taps(3, 4, 49);
+        let mut whole = Resampler::with_taps(3, 4, &taps).unwrap();
+        let expected: Vec<f64> = whole.iter(input.iter().copied()).collect();
+
+        let mut blocks = Resampler::with_taps(3, 4, &taps).unwrap();
+        let mut output = vec![];
+        for block in input.chunks(7).chain([&[][..]]) {
+            let len = blocks.output_len(block.len());
+            let before = output.len();
+            output.extend(blocks.iter(block.iter().copied()));
+            assert_eq!(len, output.len() - before);
+        }
+        assert_eq!(expected, output);
+        assert_eq!(whole, blocks);
+
+        assert_eq!(
+            Some(SamplingError::EmptyFilter),
+            Resampler::<f64>::with_taps(3, 4, &[]).err()
+        );
+    }
+
+    #[test]
+    fn test_polyphase_decimator() {
+        let taps: Vec<f64> = (0..9).map(|k| 1. / (k as f64 + 2.)).collect();
+        let input = white_noise(50, 6);
+        for factor in 1..4 {
+            for phase in 0..factor {
+                let mut filter = DynFirFilter::from_taps(&taps);
+                let mut downsampler = DownSampler::with_phase(factor, phase).unwrap();
+                let mut decimator = PolyphaseDecimator::with_phase(&taps, factor, phase).unwrap();
+                for block in [&input[..13], &input[13..14], &input[14..]] {
+                    let filtered = block.iter().map(|&x| filter.consume(x));
+                    let expected: Vec<f64> = downsampler.iter(filtered).collect();
+                    assert_eq!(expected.len(), decimator.iter(block.iter().copied()).len());
+                    assert_eq!(expected, decimator.process(block), "{factor}, {phase}");
+                }
+            }
+        }
+
+        assert_eq!(
+            Some(SamplingError::EmptyFilter),
+            PolyphaseDecimator::<f64>::new(&[], 2).err()
+        );
+        assert_eq!(
+            Some(SamplingError::ZeroScale),
+            PolyphaseDecimator::new(&[1.], 0).err()
+        );
+    }
+
+    #[test]
+    fn test_polyphase_interpolator() {
+        let taps: Vec<f64> = (0..8).map(|k| 1. / (k as f64 + 2.)).collect();
+        let input = white_noise(30, 7);
+        for factor in 1..4 {
+            let mut filter = DynFirFilter::from_taps(&taps);
+            let mut upsampler = UpSampler::with_zero(factor).unwrap();
+            let mut interpolator = PolyphaseInterpolator::new(&taps, factor).unwrap();
+            for (block, outputs) in [(&input[..7], 10), (&input[7..8], 2), (&input[8..], 100)] {
+                // stopping short of a whole group leaves zeros owed to the next block
+                let upsampled = upsampler.iter(block.iter().copied()).take(outputs);
+                let expected: Vec<f64> = upsampled.map(|x| filter.consume(x)).collect();
+                let interpolated = interpolator.iter(block.iter().copied()).take(outputs);
+                assert_eq!(expected, interpolated.collect::<Vec<_>>(), "{factor}");
+            }
+            assert_eq!(upsampler, interpolator.upsampler);
+            assert_eq!(&taps, interpolator.taps());
+        }
+
+        let mut interpolator = PolyphaseInterpolator::new(&[1., 2.], 3).unwrap();
+        assert_eq!(6, interpolator.iter([1., 1.].into_iter()).len());
+        assert_eq!(
+            Some(SamplingError::EmptyFilter),
+            PolyphaseInterpolator::<f64>::new(&[], 2).err()
+        );
+    }
+
+    #[test]
+    fn test_cic_decimator() {
+        let mut cic = CicDecimator::new(3, 64).unwrap();
+        assert_eq!((1 << 18, 18), (cic.gain(), cic.bit_growth()));
+
+        // one output per 64 inputs, carried across calls
+        let input = vec![1000; 64 * 10 + 5];
+        assert_eq!(10, cic.iter(input.iter().copied()).len());
+        let mut out: Vec<i64> = cic.iter(input.iter().copied()).collect();
+        out.extend(cic.iter(core::iter::repeat_n(1000, 59)));
+        assert_eq!(11, out.len());
+        // after the combs have filled, DC comes out times the gain
+        assert!(out[3..].iter().all(|&y| y == 1000 << 18), "{out:?}");
+
+        // full scale input grows by exactly the 18 bits, wrapping inside
+        for x in [i32::MAX, i32::MIN] {
+            cic.reset();
+            let out: Vec<i64> = cic.iter(core::iter::repeat_n(x, 64 * 8)).collect();
+            assert_eq!(i64::from(x) << 18, out[7]);
+            let peak = out.iter().map(|y| y.unsigned_abs()).max().unwrap();
+            assert!(peak <= 1 << (31 + 18));
+        }
+
+        assert_eq!(
+            Some(SamplingError::ZeroOrder),
+            CicDecimator::new(0, 64).err()
+        );
+        assert_eq!(
+            Some(SamplingError::ZeroScale),
+            CicDecimator::new(3, 0).err()
+        );
+        assert!(CicDecimator::new(4, 256).is_ok());
+        assert_eq!(
+            Some(SamplingError::RegisterGrowth),
+            CicDecimator::new(4, 257).err()
+        );
+    }
+
+    #[test]
+    fn test_cic_interpolator() {
+        let mut cic = CicInterpolator::new(3, 64).unwrap();
+        assert_eq!((1 << 12, 12), (cic.gain(), cic.bit_growth()));
+        assert_eq!(64 * 5, cic.iter(0..5).len());
+
+        // stopping mid-group leaves the rest of it to the next call
+        let mut out: Vec<i64> = cic.iter([7; 2].into_iter()).take(100).collect();
+        out.extend(cic.iter([7; 6].into_iter()));
+        assert_eq!(64 * 8, out.len());
+        assert!(out[3 * 64..].iter().all(|&y| y == 7 << 12));
+
+        cic.reset();
+        let out: Vec<i64> = cic.iter(core::iter::repeat_n(i32::MIN, 8)).collect();
+        assert_eq!(i64::from(i32::MIN) << 12, *out.last().unwrap());
+        let peak = out.iter().map(|y| y.unsigned_abs()).max().unwrap();
+        assert!(peak <= 1 << (31 + 12));
+    }
+
+    #[test]
+    fn test_farrow_resampler() {
+        let sine: Vec<f64> = (0..1000).map(|n| (0.01 * n as f64).sin()).collect();
+        let mut farrow = FarrowResampler::new(1.).unwrap();
+        let mut input = sine.iter().copied();
+        let out: Vec<f64> = core::iter::from_fn(|| farrow.next_output(&mut input)).collect();
+        let delay = farrow.delay();
+        assert_eq!(sine.len(), out.len());
+        assert_eq!(&sine[..sine.len() - delay], &out[delay..]);
+
+        // counting zero crossings, half the ratio halves the frequency
+        let crossings = |xs: &[f64]| xs.windows(2).filter(|w| (w[0] < 0.) != (w[1] < 0.)).count();
+        let sine: Vec<f64> = (0..4000).map(|n| (0.1 * n as f64).sin()).collect();
+        let mut farrow = FarrowResampler::new(0.5).unwrap();
+        let mut input = sine.iter().copied();
+        let out: Vec<f64> = core::iter::from_fn(|| farrow.next_output(&mut input)).collect();
+        assert_eq!(2 * sine.len(), out.len());
+        let (before, after) = (crossings(&sine[..2000]), crossings(&out[10..2010]));
+        assert!((2 * after).abs_diff(before) <= 2, "{before} {after}");
+        // past the zeros the window starts with
+        let error = out[2 * delay..]
+            .iter()
+            .enumerate()
+            .skip(2)
+            .map(|(k, y)| (y - (0.05 * k as f64).sin()).abs())
+            .fold(0., f64::max);
+        assert!(error < 1e-4, "{error}");
+    }
+
+    #[test]
+    fn test_farrow_phase_does_not_drift() {
+        let mut farrow = FarrowResampler::<f64>::new(1. / 3.).unwrap();
+        farrow.set_ratio(3.).unwrap();
+        let mut consumed = 0u64;
+        let mut input = core::iter::repeat(0.).inspect(|_| consumed += 1);
+        let outputs = 1_000_000;
+        for _ in 0..outputs {
+            farrow.next_output(&mut input);
+        }
+        drop(input);
+        assert_eq!(3 * (outputs - 1) + 1, consumed);
+
+        assert_eq!(Err(SamplingError::InvalidRatio), farrow.set_ratio(0.));
+        assert_eq!(Err(SamplingError::InvalidRatio), farrow.set_ratio(f64::NAN));
+        assert_eq!(
+            Some(SamplingError::InvalidRatio),
+            FarrowResampler::<f64>::new(-1.).err()
+        );
+    }
+
+    #[test]
+    fn test_fused() {
+        fn assert_fused<I: core::iter::FusedIterator>(mut iter: I) {
+            while iter.next().is_some() {}
+            for _ in 0..4 {
+                assert!(iter.next().is_none());
+            }
+        }
+
+        let mut upsampler = UpSampler::with_zero(3).unwrap();
+        assert_fused(upsampler.iter(vec![1, 2].into_iter()));
+        let mut downsampler = DownSampler::new(3).unwrap();
+        assert_fused(downsampler.iter(vec![1, 2, 3, 4].into_iter()));
+        let mut linear = LinearUpSampler::new(2).unwrap();
+        assert_fused(linear.iter(vec![1., 2.].into_iter()));
+        let mut resampler = Resampler::new(3, 2).unwrap();
+        assert_fused(resampler.iter(vec![1, 2, 3].into_iter()));
     }
 }
@@ -0,0 +1,37 @@
+//! `#[serde(with = "serde_array")]` for const generic arrays, which serde
+//! only supports up to a fixed length. Arrays go out as sequences.
+
+use alloc::vec::Vec;
+use core::fmt;
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+pub(crate) fn serialize<S, T, const N: usize>(
+    array: &[T; N],
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize,
+{
+    serializer.collect_seq(array)
+}
+
+pub(crate) fn deserialize<'de, D, T, const N: usize>(deserializer: D) -> Result<[T; N], D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let items = Vec::<T>::deserialize(deserializer)?;
+    let len = items.len();
+    items
+        .try_into()
+        .map_err(|_| D::Error::invalid_length(len, &Expected(N)))
+}
+
+struct Expected(usize);
+
+impl serde::de::Expected for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an array of length {}", self.0)
+    }
+}
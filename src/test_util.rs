@@ -0,0 +1,443 @@
+/// Deterministic uniform noise in [-1, 1) from a xorshift generator.
+pub fn white_noise(len: usize, seed: u64) -> Vec<f64> {
+    let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 11) as f64 / (1u64 << 52) as f64 - 1.
+        })
+        .collect()
+}
+
+/// Deterministic standard normal noise, by Box-Muller from [`white_noise`].
+pub fn gaussian_noise(len: usize, seed: u64) -> Vec<f64> {
+    let uniform = white_noise(len + 1, seed);
+    uniform
+        .windows(2)
+        .map(|pair| {
+            let radius = (-2. * (1. - (pair[0] + 1.) / 2.).ln()).sqrt();
+            radius * (core::f64::consts::PI * pair[1]).cos()
+        })
+        .collect()
+}
+
+// counts the allocations of each thread, so tests running in parallel do not
+// see each other's
+struct CountingAllocator;
+
+std::thread_local! {
+    static ALLOCATIONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+unsafe impl std::alloc::GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        std::alloc::System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+        std::alloc::System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Runs `f`, also returning how many heap allocations it made.
+pub fn allocations<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let before = ALLOCATIONS.with(|count| count.get());
+    let result = f();
+    (result, ALLOCATIONS.with(|count| count.get()) - before)
+}
+
+/// A minimal binary-like serde format for round-trip tests: values become a
+/// flat token list, and reading them back relies on the type, as in bincode.
+#[cfg(feature = "serde")]
+pub mod tokens {
+    use core::fmt;
+    use serde::{
+        de::{self, DeserializeSeed, SeqAccess, Visitor},
+        forward_to_deserialize_any, ser, Deserialize, Serialize,
+    };
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Token {
+        Bool(bool),
+        I64(i64),
+        U64(u64),
+        F64(f64),
+        Len(usize),
+        None,
+        Some,
+    }
+
+    #[derive(Debug)]
+    pub struct Error(String);
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str(&self.0)
+        }
+    }
+
+    impl std::error::Error for Error {}
+
+    impl ser::Error for Error {
+        fn custom<M: fmt::Display>(msg: M) -> Self {
+            Error(msg.to_string())
+        }
+    }
+
+    impl de::Error for Error {
+        fn custom<M: fmt::Display>(msg: M) -> Self {
+            Error(msg.to_string())
+        }
+    }
+
+    pub fn to_tokens<V: Serialize>(value: &V) -> Vec<Token> {
+        let mut writer = Writer(Vec::new());
+        value.serialize(&mut writer).unwrap();
+        writer.0
+    }
+
+    pub fn from_tokens<'de, V: Deserialize<'de>>(tokens: &[Token]) -> V {
+        let mut reader = Reader(tokens.iter());
+        let value = V::deserialize(&mut reader).unwrap();
+        assert!(reader.0.next().is_none(), "tokens left over");
+        value
+    }
+
+    struct Writer(Vec<Token>);
+
+    fn unsupported<T>() -> Result<T, Error> {
+        Err(Error("unsupported by the token format".into()))
+    }
+
+    impl ser::Serializer for &mut Writer {
+        type Ok = ();
+        type Error = Error;
+        type SerializeSeq = Self;
+        type SerializeTuple = Self;
+        type SerializeTupleStruct = Self;
+        type SerializeTupleVariant = Self;
+        type SerializeMap = Self;
+        type SerializeStruct = Self;
+        type SerializeStructVariant = Self;
+
+        fn serialize_bool(self, v: bool) -> Result<(), Error> {
+            self.0.push(Token::Bool(v));
+            Ok(())
+        }
+        fn serialize_i8(self, v: i8) -> Result<(), Error> {
+            self.serialize_i64(v as i64)
+        }
+        fn serialize_i16(self, v: i16) -> Result<(), Error> {
+            self.serialize_i64(v as i64)
+        }
+        fn serialize_i32(self, v: i32) -> Result<(), Error> {
+            self.serialize_i64(v as i64)
+        }
+        fn serialize_i64(self, v: i64) -> Result<(), Error> {
+            self.0.push(Token::I64(v));
+            Ok(())
+        }
+        fn serialize_u8(self, v: u8) -> Result<(), Error> {
+            self.serialize_u64(v as u64)
+        }
+        fn serialize_u16(self, v: u16) -> Result<(), Error> {
+            self.serialize_u64(v as u64)
+        }
+        fn serialize_u32(self, v: u32) -> Result<(), Error> {
+            self.serialize_u64(v as u64)
+        }
+        fn serialize_u64(self, v: u64) -> Result<(), Error> {
+            self.0.push(Token::U64(v));
+            Ok(())
+        }
+        fn serialize_f32(self, v: f32) -> Result<(), Error> {
+            self.serialize_f64(v as f64)
+        }
+        fn serialize_f64(self, v: f64) -> Result<(), Error> {
+            self.0.push(Token::F64(v));
+            Ok(())
+        }
+        fn serialize_char(self, _v: char) -> Result<(), Error> {
+            unsupported()
+        }
+        fn serialize_str(self, _v: &str) -> Result<(), Error> {
+            unsupported()
+        }
+        fn serialize_bytes(self, _v: &[u8]) -> Result<(), Error> {
+            unsupported()
+        }
+        fn serialize_none(self) -> Result<(), Error> {
+            self.0.push(Token::None);
+            Ok(())
+        }
+        fn serialize_some<V: Serialize + ?Sized>(self, value: &V) -> Result<(), Error> {
+            self.0.push(Token::Some);
+            value.serialize(self)
+        }
+        fn serialize_unit(self) -> Result<(), Error> {
+            Ok(())
+        }
+        fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+            Ok(())
+        }
+        fn serialize_unit_variant(
+            self,
+            _name: &'static str,
+            _index: u32,
+            _variant: &'static str,
+        ) -> Result<(), Error> {
+            unsupported()
+        }
+        fn serialize_newtype_struct<V: Serialize + ?Sized>(
+            self,
+            _name: &'static str,
+            value: &V,
+        ) -> Result<(), Error> {
+            value.serialize(self)
+        }
+        fn serialize_newtype_variant<V: Serialize + ?Sized>(
+            self,
+            _name: &'static str,
+            _index: u32,
+            _variant: &'static str,
+            _value: &V,
+        ) -> Result<(), Error> {
+            unsupported()
+        }
+        fn serialize_seq(self, len: Option<usize>) -> Result<Self, Error> {
+            self.0
+                .push(Token::Len(len.expect("sequences need a length")));
+            Ok(self)
+        }
+        fn serialize_tuple(self, _len: usize) -> Result<Self, Error> {
+            Ok(self)
+        }
+        fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self, Error> {
+            Ok(self)
+        }
+        fn serialize_tuple_variant(
+            self,
+            _name: &'static str,
+            _index: u32,
+            _variant: &'static str,
+            _len: usize,
+        ) -> Result<Self, Error> {
+            unsupported()
+        }
+        fn serialize_map(self, _len: Option<usize>) -> Result<Self, Error> {
+            unsupported()
+        }
+        fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self, Error> {
+            Ok(self)
+        }
+        fn serialize_struct_variant(
+            self,
+            _name: &'static str,
+            _index: u32,
+            _variant: &'static str,
+            _len: usize,
+        ) -> Result<Self, Error> {
+            unsupported()
+        }
+    }
+
+    macro_rules! compound {
+        ($trait:ident, $method:ident $(, $key:ident)?) => {
+            impl ser::$trait for &mut Writer {
+                type Ok = ();
+                type Error = Error;
+
+                fn $method<V: Serialize + ?Sized>(
+                    &mut self,
+                    $($key: &'static str,)?
+                    value: &V,
+                ) -> Result<(), Error> {
+                    value.serialize(&mut **self)
+                }
+
+                fn end(self) -> Result<(), Error> {
+                    Ok(())
+                }
+            }
+        };
+    }
+
+    compound!(SerializeSeq, serialize_element);
+    compound!(SerializeTuple, serialize_element);
+    compound!(SerializeTupleStruct, serialize_field);
+    compound!(SerializeTupleVariant, serialize_field);
+    compound!(SerializeStruct, serialize_field, _key);
+    compound!(SerializeStructVariant, serialize_field, _key);
+
+    impl ser::SerializeMap for &mut Writer {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_key<V: Serialize + ?Sized>(&mut self, _key: &V) -> Result<(), Error> {
+            unsupported()
+        }
+        fn serialize_value<V: Serialize + ?Sized>(&mut self, _value: &V) -> Result<(), Error> {
+            unsupported()
+        }
+        fn end(self) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    struct Reader<'a>(core::slice::Iter<'a, Token>);
+
+    impl Reader<'_> {
+        fn next(&mut self) -> Result<Token, Error> {
+            self.0
+                .next()
+                .copied()
+                .ok_or_else(|| Error("ran out of tokens".into()))
+        }
+    }
+
+    fn mismatch<T>(token: Token) -> Result<T, Error> {
+        Err(Error(format!("unexpected {token:?}")))
+    }
+
+    impl<'de> de::Deserializer<'de> for &mut Reader<'_> {
+        type Error = Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Error> {
+            unsupported()
+        }
+
+        fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self.next()? {
+                Token::Bool(v) => visitor.visit_bool(v),
+                token => mismatch(token),
+            }
+        }
+
+        fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self.next()? {
+                Token::I64(v) => visitor.visit_i64(v),
+                token => mismatch(token),
+            }
+        }
+
+        fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self.next()? {
+                Token::U64(v) => visitor.visit_u64(v),
+                token => mismatch(token),
+            }
+        }
+
+        fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self.next()? {
+                Token::F64(v) => visitor.visit_f64(v),
+                token => mismatch(token),
+            }
+        }
+
+        fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            self.deserialize_i64(visitor)
+        }
+        fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            self.deserialize_i64(visitor)
+        }
+        fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            self.deserialize_i64(visitor)
+        }
+        fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            self.deserialize_u64(visitor)
+        }
+        fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            self.deserialize_u64(visitor)
+        }
+        fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            self.deserialize_u64(visitor)
+        }
+        fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            self.deserialize_f64(visitor)
+        }
+
+        fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self.next()? {
+                Token::None => visitor.visit_none(),
+                Token::Some => visitor.visit_some(self),
+                token => mismatch(token),
+            }
+        }
+
+        fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_unit()
+        }
+
+        fn deserialize_newtype_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_newtype_struct(self)
+        }
+
+        fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self.next()? {
+                Token::Len(len) => visitor.visit_seq(Elements(self, len)),
+                token => mismatch(token),
+            }
+        }
+
+        fn deserialize_tuple<V: Visitor<'de>>(
+            self,
+            len: usize,
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_seq(Elements(self, len))
+        }
+
+        fn deserialize_tuple_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            len: usize,
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_seq(Elements(self, len))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_seq(Elements(self, fields.len()))
+        }
+
+        forward_to_deserialize_any! {
+            char str string bytes byte_buf unit_struct map enum identifier ignored_any
+        }
+    }
+
+    struct Elements<'r, 'a>(&'r mut Reader<'a>, usize);
+
+    impl<'de> SeqAccess<'de> for Elements<'_, '_> {
+        type Error = Error;
+
+        fn next_element_seed<S: DeserializeSeed<'de>>(
+            &mut self,
+            seed: S,
+        ) -> Result<Option<S::Value>, Error> {
+            if self.1 == 0 {
+                return Ok(None);
+            }
+            self.1 -= 1;
+            seed.deserialize(&mut *self.0).map(Some)
+        }
+
+        fn size_hint(&self) -> Option<usize> {
+            Some(self.1)
+        }
+    }
+}
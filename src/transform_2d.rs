@@ -0,0 +1,214 @@
+//! Separable Haar transform of images stored row by row.
+//!
+//! Every level transforms the rows and then the columns of the current
+//! approximation, which leaves it in the usual quadrant layout:
+//!
+//! ```text
+//! +----+----+
+//! | LL | HL |
+//! +----+----+
+//! | LH | HH |
+//! +----+----+
+//! ```
+//!
+//! The first letter is the horizontal band, so a vertical edge shows up in
+//! `HL` and a horizontal one in `LH`. The next level splits `LL` again.
+//!
+//! The streaming [`Bands`](crate::Bands) delay their output and carry state
+//! from one row into the next, so the rows and columns are transformed by the
+//! block lifting of [`haar_forward`], whose approximation matches the Haar
+//! bank.
+
+use alloc::vec::Vec;
+use num_traits::Float;
+
+use crate::lifting::{haar_forward, haar_inverse};
+
+/// A subband of one level of the 2D transform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quadrant {
+    /// Lowpass along rows and columns, the approximation.
+    LowLow,
+    /// Highpass along the rows, lowpass along the columns.
+    HighLow,
+    /// Lowpass along the rows, highpass along the columns.
+    LowHigh,
+    HighHigh,
+}
+
+/// Transforms `levels` levels of a `width` × `height` image in place.
+///
+/// Panics unless `buffer` holds exactly `width * height` samples and both
+/// sides are multiples of `2^levels`.
+pub fn forward_2d<T: Float>(buffer: &mut [T], width: usize, height: usize, levels: usize) {
+    check_shape(buffer, width, height, levels);
+    let mut column = Vec::with_capacity(height);
+    for level in 0..levels {
+        let (w, h) = (width >> level, height >> level);
+        for row in buffer.chunks_exact_mut(width).take(h) {
+            haar_forward(&mut row[..w]);
+        }
+        for x in 0..w {
+            gather(buffer, width, x, h, &mut column);
+            haar_forward(&mut column);
+            scatter(buffer, width, x, &column);
+        }
+    }
+}
+
+/// Inverse of [`forward_2d`].
+pub fn inverse_2d<T: Float>(buffer: &mut [T], width: usize, height: usize, levels: usize) {
+    check_shape(buffer, width, height, levels);
+    let mut column = Vec::with_capacity(height);
+    for level in (0..levels).rev() {
+        let (w, h) = (width >> level, height >> level);
+        for x in 0..w {
+            gather(buffer, width, x, h, &mut column);
+            haar_inverse(&mut column);
+            scatter(buffer, width, x, &column);
+        }
+        for row in buffer.chunks_exact_mut(width).take(h) {
+            haar_inverse(&mut row[..w]);
+        }
+    }
+}
+
+/// The 2D counterpart of [`Bands::process`](crate::Bands::process): the
+/// closure sees the final approximation first, then the three detail
+/// quadrants of each level from the deepest up, each as a row by row copy
+/// `width >> (level + 1)` samples wide. The image is rebuilt from whatever
+/// the closure leaves behind.
+///
+/// Panics like [`forward_2d`].
+pub fn process_2d<T, F>(
+    buffer: &mut [T],
+    width: usize,
+    height: usize,
+    levels: usize,
+    mut closure: F,
+) where
+    T: Float,
+    F: FnMut(&mut [T], Quadrant, usize),
+{
+    forward_2d(buffer, width, height, levels);
+    let mut scratch = Vec::with_capacity(width * height / 4);
+    let details = (0..levels).rev().flat_map(|level| {
+        [Quadrant::HighLow, Quadrant::LowHigh, Quadrant::HighHigh].map(|q| (level, q))
+    });
+    let approximation = levels
+        .checked_sub(1)
+        .map(|deepest| (deepest, Quadrant::LowLow));
+    for (level, quadrant) in approximation.into_iter().chain(details) {
+        let shape = (width, height, level);
+        with_quadrant(buffer, shape, quadrant, &mut scratch, &mut closure);
+    }
+    inverse_2d(buffer, width, height, levels);
+}
+
+fn check_shape<T>(buffer: &[T], width: usize, height: usize, levels: usize) {
+    assert_eq!(
+        width * height,
+        buffer.len(),
+        "buffer does not match the image size"
+    );
+    let block = 1 << levels;
+    assert!(
+        width.is_multiple_of(block) && height.is_multiple_of(block),
+        "image sides must be multiples of 2^levels"
+    );
+}
+
+fn gather<T: Copy>(buffer: &[T], width: usize, x: usize, h: usize, column: &mut Vec<T>) {
+    column.clear();
+    column.extend(buffer.iter().skip(x).step_by(width).take(h));
+}
+
+fn scatter<T: Copy>(buffer: &mut [T], width: usize, x: usize, column: &[T]) {
+    for (y, &c) in column.iter().enumerate() {
+        buffer[y * width + x] = c;
+    }
+}
+
+fn with_quadrant<T, F>(
+    buffer: &mut [T],
+    (width, height, level): (usize, usize, usize),
+    quadrant: Quadrant,
+    scratch: &mut Vec<T>,
+    closure: &mut F,
+) where
+    T: Copy,
+    F: FnMut(&mut [T], Quadrant, usize),
+{
+    let (w, h) = (width >> (level + 1), height >> (level + 1));
+    let (x0, y0) = match quadrant {
+        Quadrant::LowLow => (0, 0),
+        Quadrant::HighLow => (w, 0),
+        Quadrant::LowHigh => (0, h),
+        Quadrant::HighHigh => (w, h),
+    };
+    let rows = || (y0..y0 + h).map(|y| y * width + x0);
+    scratch.clear();
+    for start in rows() {
+        scratch.extend_from_slice(&buffer[start..start + w]);
+    }
+    closure(scratch, quadrant, level);
+    for (start, row) in rows().zip(scratch.chunks_exact(w.max(1))) {
+        buffer[start..start + w].copy_from_slice(row);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{forward_2d, inverse_2d, process_2d, Quadrant};
+    use crate::test_util::white_noise;
+
+    #[test]
+    fn test_reconstruct_8x8() {
+        let image: Vec<f64> = (0..64).map(|i| ((i * 37 % 29) as f64) - 14.).collect();
+        for levels in 0..=3 {
+            let mut data = image.clone();
+            forward_2d(&mut data, 8, 8, levels);
+            inverse_2d(&mut data, 8, 8, levels);
+            assert_eq!(image, data, "levels = {levels}");
+        }
+
+        let image = white_noise(16 * 8, 67);
+        let mut data = image.clone();
+        let mut seen = Vec::new();
+        process_2d(&mut data, 16, 8, 2, |quadrant, q, level| {
+            seen.push((q, level, quadrant.len()));
+        });
+        assert_eq!(
+            vec![
+                (Quadrant::LowLow, 1, 8),
+                (Quadrant::HighLow, 1, 8),
+                (Quadrant::LowHigh, 1, 8),
+                (Quadrant::HighHigh, 1, 8),
+                (Quadrant::HighLow, 0, 32),
+                (Quadrant::LowHigh, 0, 32),
+                (Quadrant::HighHigh, 0, 32),
+            ],
+            seen
+        );
+        for (y, x) in data.iter().zip(&image) {
+            assert!((y - x).abs() < 1e-12, "{y} != {x}");
+        }
+    }
+
+    #[test]
+    fn test_vertical_edge() {
+        // dark left of column 3, which splits a pair of columns
+        let image: Vec<f64> = (0..64).map(|i| if i % 8 < 3 { 0. } else { 1. }).collect();
+        let mut data = image.clone();
+        process_2d(&mut data, 8, 8, 1, |quadrant, q, _level| {
+            let energy: f64 = quadrant.iter().map(|c| c * c).sum();
+            match q {
+                Quadrant::LowLow | Quadrant::HighLow => assert!(energy > 1., "{q:?}"),
+                Quadrant::LowHigh | Quadrant::HighHigh => assert_eq!(0., energy, "{q:?}"),
+            }
+        });
+        assert_eq!(image, data);
+    }
+}
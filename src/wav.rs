@@ -0,0 +1,186 @@
+use core::fmt;
+use std::{fs, io, path::Path, vec::Vec};
+
+use crate::DynBands;
+
+#[derive(Debug)]
+pub enum WavError {
+    Io(io::Error),
+    /// Not a RIFF/WAVE file, or not mono 16-bit PCM.
+    Unsupported,
+}
+
+impl fmt::Display for WavError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WavError::Io(err) => write!(f, "wav i/o failed: {err}"),
+            WavError::Unsupported => write!(f, "not a mono 16-bit PCM wav file"),
+        }
+    }
+}
+
+impl std::error::Error for WavError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WavError::Io(err) => Some(err),
+            WavError::Unsupported => None,
+        }
+    }
+}
+
+impl From<io::Error> for WavError {
+    fn from(err: io::Error) -> Self {
+        WavError::Io(err)
+    }
+}
+
+/// Reads a mono 16-bit PCM WAV file, splits it into `depth` levels of
+/// [`DynBands`], scales band `level` by `gains[level]` (the approximation is
+/// band `depth`) and writes the result at the same sample rate.
+///
+/// The output is trimmed by the [`delay`](DynBands::delay) of the filter
+/// bank, so it lines up with the input and has the same length. Samples
+/// beyond full scale are clipped.
+///
+/// # Panics
+///
+/// If `gains` does not hold `depth + 1` values.
+pub fn process_wav(
+    in_path: impl AsRef<Path>,
+    out_path: impl AsRef<Path>,
+    depth: usize,
+    gains: &[f32],
+) -> Result<(), WavError> {
+    assert_eq!(depth + 1, gains.len(), "need one gain per band");
+    let (sample_rate, samples) = read(&fs::read(in_path)?)?;
+
+    let mut bands = DynBands::<f32>::new(depth);
+    let delay = bands.delay();
+    let mut buffer: Vec<f32> = samples.iter().map(|&x| f32::from(x) / 32768.).collect();
+    buffer.resize(samples.len() + delay, 0.);
+    bands.process(&mut buffer, |band, level| {
+        band.iter_mut().for_each(|x| *x *= gains[level]);
+    });
+
+    let out: Vec<i16> = buffer[delay..]
+        .iter()
+        .map(|&y| (y * 32768.).round().clamp(-32768., 32767.) as i16)
+        .collect();
+    fs::write(out_path, write(sample_rate, &out))?;
+    Ok(())
+}
+
+/// The sample rate and samples of a mono 16-bit PCM file, skipping any
+/// chunks other than `fmt ` and `data`.
+fn read(bytes: &[u8]) -> Result<(u32, Vec<i16>), WavError> {
+    if bytes.len() < 12 || &bytes[..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(WavError::Unsupported);
+    }
+    let mut rest = &bytes[12..];
+    let mut sample_rate = None;
+    while rest.len() >= 8 {
+        let size = u32::from_le_bytes(rest[4..8].try_into().unwrap()) as usize;
+        let body = rest.get(8..8 + size).ok_or(WavError::Unsupported)?;
+        match &rest[..4] {
+            b"fmt " => {
+                let field = |at: usize| {
+                    body.get(at..at + 2)
+                        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+                };
+                if body.len() < 16
+                    || field(0) != Some(1)
+                    || field(2) != Some(1)
+                    || field(14) != Some(16)
+                {
+                    return Err(WavError::Unsupported);
+                }
+                sample_rate = Some(u32::from_le_bytes(body[4..8].try_into().unwrap()));
+            }
+            b"data" => {
+                let sample_rate = sample_rate.ok_or(WavError::Unsupported)?;
+                let samples = body
+                    .chunks_exact(2)
+                    .map(|b| i16::from_le_bytes([b[0], b[1]]))
+                    .collect();
+                return Ok((sample_rate, samples));
+            }
+            _ => {}
+        }
+        // chunks are padded to an even size
+        rest = rest.get(8 + size + size % 2..).unwrap_or_default();
+    }
+    Err(WavError::Unsupported)
+}
+
+fn write(sample_rate: u32, samples: &[i16]) -> Vec<u8> {
+    let data_len = 2 * samples.len() as u32;
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, mono
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(2 * sample_rate).to_le_bytes());
+    // block align, bits per sample
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for x in samples {
+        bytes.extend_from_slice(&x.to_le_bytes());
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    // a file in the temp dir unique to this process, removed when dropped
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            let name = format!("qmf_{}_{name}.wav", std::process::id());
+            Self(std::env::temp_dir().join(name))
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_process_wav_round_trip() {
+        let (in_file, out_file) = (TempFile::new("sine_in"), TempFile::new("sine_out"));
+        let (in_path, out_path) = (&in_file.0, &out_file.0);
+        let sine: Vec<i16> = (0..4000)
+            .map(|n| (8000. * (n as f32 * 0.05).sin()) as i16)
+            .collect();
+        fs::write(in_path, write(8000, &sine)).unwrap();
+
+        process_wav(in_path, out_path, 3, &[1.; 4]).unwrap();
+        let (sample_rate, out) = read(&fs::read(out_path).unwrap()).unwrap();
+        assert_eq!((8000, sine.len()), (sample_rate, out.len()));
+        let energy = |x: &[i16]| x.iter().map(|&x| f64::from(x).powi(2)).sum::<f64>();
+        assert!((energy(&out) / energy(&sine) - 1.).abs() < 0.05);
+
+        // silencing everything but the approximation keeps a slow sine
+        process_wav(in_path, out_path, 3, &[0., 0., 0., 1.]).unwrap();
+        let (_, out) = read(&fs::read(out_path).unwrap()).unwrap();
+        assert!(energy(&out) / energy(&sine) > 0.9);
+
+        fs::write(in_path, b"RIFF\0\0\0\0WAVE").unwrap();
+        assert!(matches!(
+            process_wav(in_path, out_path, 1, &[1., 1.]),
+            Err(WavError::Unsupported)
+        ));
+    }
+}
@@ -0,0 +1,720 @@
+use alloc::vec::Vec;
+use num_traits::Float;
+
+use crate::{
+    design::qmf,
+    error::QmfError,
+    fir::{dc_gain, nyquist_gain},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wavelet {
+    Haar,
+    /// Daubechies wavelet with the given number of vanishing moments (db2 to db10).
+    Daubechies(usize),
+    /// Least asymmetric Daubechies wavelet (sym2 to sym8).
+    Symlet(usize),
+    /// Coiflet whose scaling function also has vanishing moments (coif1 to coif5).
+    Coiflet(usize),
+    /// LeGall 5/3 biorthogonal wavelet with a 5-tap lowpass and 3-tap highpass.
+    LeGall53,
+    /// Cohen-Daubechies-Feauveau 9/7 biorthogonal wavelet, as used by JPEG 2000.
+    Cdf97,
+}
+
+impl Wavelet {
+    pub fn daubechies<T: Float>(order: usize) -> Result<FilterSet<T>, QmfError> {
+        tabulated(&DAUBECHIES, 2, order)
+    }
+
+    pub fn symlet<T: Float>(order: usize) -> Result<FilterSet<T>, QmfError> {
+        tabulated(&SYMLETS, 2, order)
+    }
+
+    pub fn coiflet<T: Float>(order: usize) -> Result<FilterSet<T>, QmfError> {
+        tabulated(&COIFLETS, 1, order)
+    }
+
+    pub fn filter_set<T: Float>(&self) -> Result<FilterSet<T>, QmfError> {
+        match *self {
+            Wavelet::Haar => Ok(FilterSet::haar()),
+            Wavelet::Daubechies(order) => Self::daubechies(order),
+            Wavelet::Symlet(order) => Self::symlet(order),
+            Wavelet::Coiflet(order) => Self::coiflet(order),
+            Wavelet::LeGall53 => Ok(FilterSet::legall53()),
+            Wavelet::Cdf97 => Ok(FilterSet::cdf97()),
+        }
+    }
+}
+
+fn tabulated<T: Float>(
+    table: &[&[f64]],
+    first_order: usize,
+    order: usize,
+) -> Result<FilterSet<T>, QmfError> {
+    order
+        .checked_sub(first_order)
+        .and_then(|i| table.get(i))
+        .map(|scaling| FilterSet::orthogonal(scaling))
+        .ok_or(QmfError::UnsupportedOrder(order))
+}
+
+/// How the gain of a filter bank is split between analysis and synthesis.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Normalization {
+    /// Unit DC gain on analysis and a gain of two on synthesis, so the Haar
+    /// taps are 0.5 and 1.
+    #[default]
+    Rational,
+    /// A DC gain of √2 on both sides. For orthogonal wavelets the subband
+    /// coefficients then carry the energy of the signal (Parseval).
+    Orthonormal,
+}
+
+/// Analysis and synthesis taps of a two-channel filter bank.
+///
+/// Taps follow the convention of the Haar bank: the analysis lowpass has unit
+/// DC gain and the synthesis lowpass a DC gain of two.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterSet<T> {
+    pub(crate) analysis_low: Vec<T>,
+    pub(crate) analysis_high: Vec<T>,
+    pub(crate) synthesis_low: Vec<T>,
+    pub(crate) synthesis_high: Vec<T>,
+}
+
+impl<T> FilterSet<T>
+where
+    T: Float,
+{
+    /// A filter set from user supplied taps. The bank only reconstructs the
+    /// input if the four filters form a perfect reconstruction pair.
+    pub fn new(
+        analysis_low: &[T],
+        analysis_high: &[T],
+        synthesis_low: &[T],
+        synthesis_high: &[T],
+    ) -> Result<Self, QmfError> {
+        let filters = [analysis_low, analysis_high, synthesis_low, synthesis_high];
+        if filters.iter().any(|taps| taps.is_empty()) {
+            return Err(QmfError::EmptyFilter);
+        }
+        Ok(Self {
+            analysis_low: analysis_low.to_vec(),
+            analysis_high: analysis_high.to_vec(),
+            synthesis_low: synthesis_low.to_vec(),
+            synthesis_high: synthesis_high.to_vec(),
+        })
+    }
+
+    pub(crate) fn haar() -> Self {
+        // rational number coefficients are taken from
+        // [奥村 博造. ハールウェーブレット変換と完全再構成QMフィルタ](https://nagano.repo.nii.ac.jp/record/457/files/nagano_20-04-01.pdf)
+        let half = T::from(0.5).unwrap();
+        let one = T::one();
+        Self {
+            analysis_low: alloc::vec![half, half],
+            analysis_high: alloc::vec![-half, half],
+            synthesis_low: alloc::vec![one, one],
+            synthesis_high: alloc::vec![one, -one],
+        }
+    }
+
+    fn legall53() -> Self {
+        // dyadic rationals, exact in any binary floating point type
+        let taps = |taps: &[f64]| taps.iter().map(|&h| T::from(h).unwrap()).collect();
+        Self {
+            analysis_low: taps(&[-0.125, 0.25, 0.75, 0.25, -0.125]),
+            analysis_high: taps(&[-0.5, 1., -0.5]),
+            synthesis_low: taps(&[0.5, 1., 0.5]),
+            synthesis_high: taps(&[-0.125, -0.25, 0.75, -0.25, -0.125]),
+        }
+    }
+
+    fn cdf97() -> Self {
+        // from the spectral factorization of 1 + 4y + 10y² + 20y³ into a real
+        // root (7-tap synthesis lowpass) and a complex pair (9-tap analysis)
+        let taps = |taps: &[f64]| taps.iter().map(|&h| T::from(h).unwrap()).collect();
+        Self {
+            analysis_low: taps(&[
+                0.02674875741081009,
+                -0.016864118442874953,
+                -0.07822326652899027,
+                0.26686411844287494,
+                0.6029490182363604,
+                0.26686411844287494,
+                -0.07822326652899027,
+                -0.016864118442874953,
+                0.02674875741081009,
+            ]),
+            analysis_high: taps(&[
+                0.09127176311425009,
+                -0.05754352622850018,
+                -0.5912717631142501,
+                1.1150870524570005,
+                -0.5912717631142501,
+                -0.05754352622850018,
+                0.09127176311425009,
+            ]),
+            synthesis_low: taps(&[
+                -0.09127176311425009,
+                -0.05754352622850018,
+                0.5912717631142501,
+                1.1150870524570005,
+                0.5912717631142501,
+                -0.05754352622850018,
+                -0.09127176311425009,
+            ]),
+            synthesis_high: taps(&[
+                0.02674875741081009,
+                0.016864118442874953,
+                -0.07822326652899027,
+                -0.26686411844287494,
+                0.6029490182363604,
+                -0.26686411844287494,
+                -0.07822326652899027,
+                0.016864118442874953,
+                0.02674875741081009,
+            ]),
+        }
+    }
+
+    /// Derives the four filters of an orthogonal wavelet from its orthonormal
+    /// scaling filter (taps summing to √2).
+    fn orthogonal(scaling: &[f64]) -> Self {
+        let sqrt2 = T::from(2).unwrap().sqrt();
+        let c: Vec<T> = scaling
+            .iter()
+            .map(|&h| T::from(h).unwrap() / sqrt2)
+            .collect();
+        let two = T::from(2).unwrap();
+        let high = qmf(&c);
+
+        // scaling filters have an even length, so the reversed mirror is
+        // `(-1)^n·c[n]`
+        Self {
+            analysis_low: c.clone(),
+            analysis_high: high.clone(),
+            synthesis_low: c.iter().rev().map(|&x| two * x).collect(),
+            synthesis_high: high.iter().rev().map(|&x| two * x).collect(),
+        }
+    }
+
+    /// Rescales the taps, which follow the [`Rational`] convention, to the
+    /// given one. Analysis and synthesis are scaled inversely, so the bank
+    /// reconstructs as before.
+    ///
+    /// [`Rational`]: Normalization::Rational
+    pub fn normalized(&self, normalization: Normalization) -> Self {
+        let (analysis, synthesis) = match normalization {
+            Normalization::Rational => (T::one(), T::one()),
+            Normalization::Orthonormal => {
+                let sqrt2 = T::from(2).unwrap().sqrt();
+                (sqrt2, sqrt2.recip())
+            }
+        };
+        let scale = |taps: &[T], gain: T| taps.iter().map(|&h| h * gain).collect();
+        Self {
+            analysis_low: scale(&self.analysis_low, analysis),
+            analysis_high: scale(&self.analysis_high, analysis),
+            synthesis_low: scale(&self.synthesis_low, synthesis),
+            synthesis_high: scale(&self.synthesis_high, synthesis),
+        }
+    }
+
+    /// Rescales the synthesis filters so a round trip has unit gain, keeping
+    /// the analysis filters as given.
+    ///
+    /// The lowpass branch passes DC with gain `H0(1)·G0(1) / 2`, the factor
+    /// two lost to downsampling, and the highpass branch passes Nyquist with
+    /// gain `|H1(-1)·G1(-1)| / 2`. `G0` is scaled by `2 / H0(1)·G0(1)` and `G1`
+    /// by `2 / |H1(-1)·G1(-1)|`, which keeps the aliasing cancelled for any
+    /// gain error of the analysis filters. Signs are kept as given. Fails with
+    /// [`QmfError::ZeroGain`] if either product vanishes.
+    pub fn with_unity_gain(&self) -> Result<Self, QmfError> {
+        let two = T::from(2).unwrap();
+        let compensation = |gain: T| {
+            if gain.is_normal() {
+                Ok(two / gain.abs())
+            } else {
+                Err(QmfError::ZeroGain)
+            }
+        };
+        let low = compensation(dc_gain(&self.analysis_low) * dc_gain(&self.synthesis_low))?;
+        let high =
+            compensation(nyquist_gain(&self.analysis_high) * nyquist_gain(&self.synthesis_high))?;
+        Ok(Self {
+            analysis_low: self.analysis_low.clone(),
+            analysis_high: self.analysis_high.clone(),
+            synthesis_low: self.synthesis_low.iter().map(|&g| g * low).collect(),
+            synthesis_high: self.synthesis_high.iter().map(|&g| g * high).collect(),
+        })
+    }
+
+    pub fn analysis_low(&self) -> &[T] {
+        &self.analysis_low
+    }
+
+    pub fn analysis_high(&self) -> &[T] {
+        &self.analysis_high
+    }
+
+    pub fn synthesis_low(&self) -> &[T] {
+        &self.synthesis_low
+    }
+
+    pub fn synthesis_high(&self) -> &[T] {
+        &self.synthesis_high
+    }
+}
+
+// orthonormal scaling filters, as tabulated by Daubechies (Ten Lectures on Wavelets)
+const DAUBECHIES: [&[f64]; 9] = [
+    // db2
+    &[
+        0.48296291314453416,
+        0.8365163037378079,
+        0.2241438680420134,
+        -0.12940952255126037,
+    ],
+    // db3
+    &[
+        0.33267055295008263,
+        0.8068915093110925,
+        0.45987750211849154,
+        -0.13501102001025458,
+        -0.08544127388202666,
+        0.03522629188570953,
+    ],
+    // db4
+    &[
+        0.2303778133088965,
+        0.7148465705529157,
+        0.6308807679298589,
+        -0.027983769416859854,
+        -0.18703481171909309,
+        0.030841381835560764,
+        0.0328830116668852,
+        -0.010597401785069032,
+    ],
+    // db5
+    &[
+        0.16010239797419293,
+        0.6038292697971896,
+        0.7243085284377729,
+        0.13842814590132074,
+        -0.24229488706638203,
+        -0.032244869584638375,
+        0.07757149384004572,
+        -0.006241490212798274,
+        -0.012580751999081999,
+        0.0033357252854737712,
+    ],
+    // db6
+    &[
+        0.11154074335010947,
+        0.49462389039845306,
+        0.7511339080210954,
+        0.31525035170919763,
+        -0.22626469396543983,
+        -0.12976686756726194,
+        0.09750160558732304,
+        0.027522865530305727,
+        -0.03158203931748603,
+        0.0005538422011614961,
+        0.004777257510945511,
+        -0.0010773010853084796,
+    ],
+    // db7
+    &[
+        0.07785205408500918,
+        0.3965393194819173,
+        0.7291320908462351,
+        0.4697822874051931,
+        -0.14390600392856498,
+        -0.22403618499387498,
+        0.07130921926683026,
+        0.08061260915108308,
+        -0.03802993693501441,
+        -0.01657454163066688,
+        0.01255099855609984,
+        0.0004295779729213665,
+        -0.0018016407040474908,
+        0.00035371379997452024,
+    ],
+    // db8
+    &[
+        0.05441584224310401,
+        0.31287159091429995,
+        0.6756307362972898,
+        0.5853546836542067,
+        -0.015829105256349306,
+        -0.2840155429615469,
+        0.0004724845739132828,
+        0.12874742662047847,
+        -0.017369301001807547,
+        -0.044088253930794755,
+        0.013981027917398282,
+        0.008746094047405777,
+        -0.004870352993451574,
+        -0.00039174037337694705,
+        0.0006754494064505693,
+        -0.00011747678412476953,
+    ],
+    // db9
+    &[
+        0.038077947363878345,
+        0.24383467461259034,
+        0.6048231236901112,
+        0.6572880780513005,
+        0.13319738582500756,
+        -0.2932737832791749,
+        -0.09684078322297646,
+        0.14854074933810638,
+        0.03072568147933338,
+        -0.06763282906132997,
+        0.00025094711483145197,
+        0.022361662123679096,
+        -0.004723204757751397,
+        -0.00428150368246343,
+        0.0018476468830562265,
+        0.00023038576352319597,
+        -0.0002519631889427101,
+        3.93473203162716e-05,
+    ],
+    // db10
+    &[
+        0.026670057900555554,
+        0.1881768000776915,
+        0.5272011889317256,
+        0.6884590394536035,
+        0.2811723436605775,
+        -0.24984642432731538,
+        -0.19594627437737705,
+        0.12736934033579325,
+        0.09305736460357235,
+        -0.07139414716639708,
+        -0.029457536821875813,
+        0.033212674059341,
+        0.0036065535669561697,
+        -0.010733175483330575,
+        0.001395351747052901,
+        0.001992405295185056,
+        -0.0006858566949597116,
+        -0.00011646685512928545,
+        9.358867032006959e-05,
+        -1.3264202894521244e-05,
+    ],
+];
+
+// the same spectral factorization as `DAUBECHIES`, but with the roots picked so
+// the phase response is as close to linear as possible
+const SYMLETS: [&[f64]; 7] = [
+    // sym2
+    &[
+        0.48296291314453416,
+        0.8365163037378079,
+        0.2241438680420134,
+        -0.12940952255126037,
+    ],
+    // sym3
+    &[
+        0.33267055295008263,
+        0.8068915093110925,
+        0.45987750211849154,
+        -0.13501102001025458,
+        -0.08544127388202666,
+        0.03522629188570953,
+    ],
+    // sym4
+    &[
+        -0.07576571478950221,
+        -0.029635527646002493,
+        0.497618667632775,
+        0.8037387518051321,
+        0.29785779560530606,
+        -0.09921954357663353,
+        -0.012603967262031304,
+        0.032223100604051466,
+    ],
+    // sym5
+    &[
+        0.027333068344998768,
+        0.02951949092570626,
+        -0.039134249302313844,
+        0.19939753397685558,
+        0.7234076904040407,
+        0.633978963456792,
+        0.01660210576451085,
+        -0.17532808990805623,
+        -0.021101834024689042,
+        0.019538882735249827,
+    ],
+    // sym6
+    &[
+        -0.00780070832503238,
+        0.0017677118642540077,
+        0.04472490177078139,
+        -0.02106029251237085,
+        -0.07263752278637658,
+        0.3379294217281658,
+        0.787641141028651,
+        0.49105594192797375,
+        -0.04831174258569806,
+        -0.11799011114852002,
+        0.0034907120842221626,
+        0.015404109327044824,
+    ],
+    // sym7
+    &[
+        0.012015419283549189,
+        0.017213376300804502,
+        -0.06490800354718848,
+        -0.06413128980738582,
+        0.3602184609062602,
+        0.7819215932917282,
+        0.4836109156822677,
+        -0.05680447688966697,
+        -0.1010109208684203,
+        0.04474234946835238,
+        0.020464207577546033,
+        -0.01812660513133846,
+        -0.003283297847466811,
+        0.0022918339540537714,
+    ],
+    // sym8
+    &[
+        -0.0033824159510050028,
+        -0.0005421323318000107,
+        0.03169508781152599,
+        0.007607487324976609,
+        -0.14329423835127267,
+        -0.061273359067811076,
+        0.4813596512590534,
+        0.777185751699628,
+        0.36444189483617895,
+        -0.0519458381078818,
+        -0.027219029917103486,
+        0.04913717967373029,
+        0.0038087520138944896,
+        -0.014952258337062199,
+        -0.0003029205147241331,
+        0.001889950332767689,
+    ],
+];
+
+// solutions of the coiflet moment equations of order K (6K taps, 2K vanishing
+// wavelet moments, 2K-1 vanishing scaling moments)
+const COIFLETS: [&[f64]; 5] = [
+    // coif1
+    &[
+        -0.07273261951252645,
+        0.33789766245748176,
+        0.8525720202116004,
+        0.3848648468648577,
+        -0.07273261951252645,
+        -0.015655728135791993,
+    ],
+    // coif2
+    &[
+        0.01638733646320364,
+        -0.04146493678687178,
+        -0.0673725547237256,
+        0.38611006682276283,
+        0.8127236354494135,
+        0.41700518442323903,
+        -0.07648859907828076,
+        -0.059434418646431085,
+        0.02368017194684777,
+        0.005611434819368834,
+        -0.001823208870911032,
+        -0.000720549445520347,
+    ],
+    // coif3
+    &[
+        -0.0037935128643808015,
+        0.0077825964256727454,
+        0.023452696142077165,
+        -0.06577191128146936,
+        -0.06112339000297254,
+        0.4051769024091182,
+        0.7937772226260872,
+        0.42848347637737,
+        -0.07179982161915484,
+        -0.08230192710629981,
+        0.03455502757329773,
+        0.015880544863669452,
+        -0.009007976136730624,
+        -0.002574517688136797,
+        0.0011175187708306303,
+        0.0004662169598204029,
+        -7.0983302506379e-05,
+        -3.4599773197272774e-05,
+    ],
+    // coif4
+    &[
+        0.000892313902537003,
+        -0.0016294924252267858,
+        -0.00734616793626805,
+        0.016068947131575025,
+        0.026682304669604834,
+        -0.08126671024919373,
+        -0.05607731960356926,
+        0.41530842700068227,
+        0.7822389344242826,
+        0.43438603311435653,
+        -0.06662747236681715,
+        -0.09622042453595264,
+        0.03933442260558915,
+        0.025082253337949608,
+        -0.015211728187697211,
+        -0.0056582838001308835,
+        0.003751434697146086,
+        0.0012665610789256603,
+        -0.0005890202246332164,
+        -0.0002599743371222568,
+        6.233885431278718e-05,
+        3.1229861599195265e-05,
+        -3.2596479400307506e-06,
+        -1.7849909144933466e-06,
+    ],
+    // coif5
+    &[
+        -0.000212081862067494,
+        0.0003585777411617577,
+        0.0021782943778456947,
+        -0.004159312627578639,
+        -0.010131584846900275,
+        0.023408322118927783,
+        0.028169744270532353,
+        -0.09192158806008609,
+        -0.05204667025355476,
+        0.42157126673075435,
+        0.7742936228603274,
+        0.4379823066591633,
+        -0.06203775157498195,
+        -0.10556315130733723,
+        0.041287530472117834,
+        0.03267479946705735,
+        -0.019758391600965465,
+        -0.009159507338676163,
+        0.006761520220620417,
+        0.0024315754425382886,
+        -0.0016616273039298788,
+        -0.0006375589261258812,
+        0.00030185794166824473,
+        0.00014035632812373243,
+        -4.12198619242655e-05,
+        -2.1270221672515614e-05,
+        3.7007277113394796e-06,
+        2.0612203985788783e-06,
+        -1.6237995172048335e-07,
+        -9.604010112767892e-08,
+    ],
+];
+
+#[cfg(test)]
+mod tests {
+    use super::{FilterSet, Normalization, Wavelet};
+    use crate::error::QmfError;
+
+    fn sum(taps: &[f64]) -> f64 {
+        taps.iter().sum()
+    }
+
+    #[test]
+    fn test_daubechies_orders() {
+        for order in 2..=10 {
+            let filters: FilterSet<f64> = Wavelet::daubechies(order).unwrap();
+            assert_eq!(2 * order, filters.analysis_low().len());
+            assert!((sum(filters.analysis_low()) - 1.).abs() < 1e-12);
+            assert!((sum(filters.synthesis_low()) - 2.).abs() < 1e-12);
+            assert!(sum(filters.analysis_high()).abs() < 1e-12);
+            assert!(sum(filters.synthesis_high()).abs() < 1e-12);
+        }
+        assert_eq!(
+            Some(QmfError::UnsupportedOrder(1)),
+            Wavelet::daubechies::<f64>(1).err()
+        );
+        assert_eq!(
+            Some(QmfError::UnsupportedOrder(11)),
+            Wavelet::Daubechies(11).filter_set::<f64>().err()
+        );
+    }
+
+    #[test]
+    fn test_symlet_orders() {
+        for order in 2..=8 {
+            let filters: FilterSet<f64> = Wavelet::Symlet(order).filter_set().unwrap();
+            assert_eq!(2 * order, filters.synthesis_high().len());
+            assert!((sum(filters.analysis_low()) - 1.).abs() < 1e-12);
+            assert!(sum(filters.analysis_high()).abs() < 1e-12);
+        }
+        // the two shortest symlets coincide with their Daubechies counterparts
+        assert_eq!(Wavelet::daubechies::<f64>(3), Wavelet::symlet::<f64>(3));
+        assert!(Wavelet::symlet::<f64>(9).is_err());
+    }
+
+    #[test]
+    fn test_coiflet_orders() {
+        for order in 1..=5 {
+            let filters: FilterSet<f64> = Wavelet::coiflet(order).unwrap();
+            let c = filters.analysis_low();
+            assert_eq!(6 * order, c.len());
+            // the same unit / two DC gain split as the Haar bank
+            assert!((sum(c) - 1.).abs() < 1e-12);
+            assert!((sum(filters.synthesis_low()) - 2.).abs() < 1e-12);
+
+            // vanishing moments of the scaling function around its center
+            let center: f64 = c.iter().enumerate().map(|(n, h)| n as f64 * h).sum();
+            for p in 1..2 * order as i32 {
+                let moment: f64 = c
+                    .iter()
+                    .enumerate()
+                    .map(|(n, h)| (n as f64 - center).powi(p) * h)
+                    .sum();
+                assert!(moment.abs() < 1e-6, "coif{order} moment {p}: {moment}");
+            }
+        }
+        assert!(Wavelet::coiflet::<f64>(0).is_err());
+        assert!(Wavelet::Coiflet(6).filter_set::<f64>().is_err());
+    }
+
+    #[test]
+    fn test_orthonormal() {
+        // the scaling filter is orthogonal to its own even shifts
+        let sets = (2..=10)
+            .map(Wavelet::Daubechies)
+            .chain((2..=8).map(Wavelet::Symlet))
+            .chain((1..=5).map(Wavelet::Coiflet));
+        for wavelet in sets {
+            let filters: FilterSet<f64> = wavelet.filter_set().unwrap();
+            let c = filters.analysis_low();
+            for shift in (2..c.len()).step_by(2) {
+                let dot: f64 = c.iter().zip(&c[shift..]).map(|(a, b)| a * b).sum();
+                assert!(dot.abs() < 1e-12, "{wavelet:?} shift {shift}: {dot}");
+            }
+            let energy: f64 = c.iter().map(|x| x * x).sum();
+            assert!((energy - 0.5).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_normalized() {
+        let haar = FilterSet::<f64>::haar();
+        assert_eq!(haar, haar.normalized(Normalization::Rational));
+
+        let orthonormal = haar.normalized(Normalization::Orthonormal);
+        let h = core::f64::consts::FRAC_1_SQRT_2;
+        assert!(orthonormal
+            .analysis_low()
+            .iter()
+            .all(|x| (x - h).abs() < 1e-15));
+        assert!(orthonormal
+            .synthesis_low()
+            .iter()
+            .all(|x| (x - h).abs() < 1e-15));
+        assert!((orthonormal.analysis_high()[0] + h).abs() < 1e-15);
+        assert!((orthonormal.synthesis_high()[1] + h).abs() < 1e-15);
+    }
+}
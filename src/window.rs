@@ -0,0 +1,62 @@
+//! Symmetric window functions for tapering FIR prototypes, see
+//! [`FirFilter::apply_window`](crate::FirFilter::apply_window). Point `n` of
+//! an `M` point window sits at `x = n / (M - 1)`, so both ends are included
+//! and a one point window is `[1]`.
+
+use core::{array, f64::consts::PI};
+use num_traits::Float;
+
+pub fn hann<T: Float, const M: usize>() -> [T; M] {
+    generate(hann_at)
+}
+
+pub fn hamming<T: Float, const M: usize>() -> [T; M] {
+    generate(hamming_at)
+}
+
+pub fn blackman<T: Float, const M: usize>() -> [T; M] {
+    generate(blackman_at)
+}
+
+fn generate<T: Float, const M: usize>(at: fn(f64) -> f64) -> [T; M] {
+    array::from_fn(|n| {
+        let w = if M == 1 {
+            1.
+        } else {
+            at(n as f64 / (M - 1) as f64)
+        };
+        T::from(w).unwrap()
+    })
+}
+
+pub(crate) fn hann_at(x: f64) -> f64 {
+    0.5 - 0.5 * Float::cos(2. * PI * x)
+}
+
+pub(crate) fn hamming_at(x: f64) -> f64 {
+    0.54 - 0.46 * Float::cos(2. * PI * x)
+}
+
+pub(crate) fn blackman_at(x: f64) -> f64 {
+    0.42 - 0.5 * Float::cos(2. * PI * x) + 0.08 * Float::cos(4. * PI * x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{blackman, hamming, hann};
+
+    #[test]
+    fn test_windows() {
+        let windows: [[f64; 5]; 3] = [hann(), hamming(), blackman()];
+        for (window, edge) in windows.iter().zip([0., 0.08, 0.]) {
+            assert!((window[2] - 1.).abs() < 1e-12);
+            assert!((window[0] - edge).abs() < 1e-12);
+            for n in 0..2 {
+                assert!((window[n] - window[4 - n]).abs() < 1e-12);
+                assert!(window[n] < window[n + 1]);
+            }
+        }
+        assert_eq!([1f32], hann());
+        assert!((hann::<f64, 3>()[1] - 1.).abs() < 1e-12);
+    }
+}